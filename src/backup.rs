@@ -0,0 +1,202 @@
+//! Backup SRAM and RTC backup registers
+//!
+//! Both live in the backup domain and keep their contents across system resets and, when
+//! VBAT is supplied, across power loss of VDD. Writing requires backup domain write access
+//! (`PWR_CR.DBP`), which is enabled by the constructors here and by `rtc::Rtc::new`.
+//!
+//! The backup SRAM has no PAC peripheral, so its unique ownership is represented by the
+//! [`BkpSram`](struct.BkpSram.html) token handed out as part of `rcc::Rcc`. The backup
+//! registers belong to the RTC register block and are borrowed from either the `RTC`
+//! peripheral or an `rtc::Rtc`.
+
+use core::marker::PhantomData;
+use core::ptr;
+
+use crate::bb;
+use crate::stm32::{PWR, RCC, RTC};
+
+/// Number of RTC backup registers
+pub const BACKUP_REGISTERS: usize = 20;
+
+/// Offset of BKP0R in the RTC register block
+const BKP0R_OFFSET: usize = 0x50;
+
+/// Enables the PWR clock and disables backup domain write protection
+fn enable_backup_access() {
+    unsafe {
+        // NOTE(unsafe) this reference will only be used for atomic writes with no side effects.
+        let rcc = &(*RCC::ptr());
+
+        // Enable the PWR clock
+        bb::set(&rcc.apb1enr, 28);
+
+        let pwr = &(*PWR::ptr());
+        pwr.cr.modify(|_, w| w.dbp().set_bit());
+        while pwr.cr.read().dbp().bit_is_clear() {}
+    }
+}
+
+/// RTC backup registers
+pub struct BackupRegisters<'a> {
+    _rtc: PhantomData<&'a mut RTC>,
+}
+
+impl<'a> BackupRegisters<'a> {
+    /// Gives access to the backup registers without setting up the RTC
+    pub fn new(_rtc: &'a mut RTC, _pwr: &mut PWR) -> Self {
+        enable_backup_access();
+        BackupRegisters { _rtc: PhantomData }
+    }
+
+    /// Access for `rtc::Rtc`, which already enabled backup domain write access
+    pub(crate) fn new_unchecked() -> Self {
+        BackupRegisters { _rtc: PhantomData }
+    }
+
+    fn register(idx: usize) -> *mut u32 {
+        assert!(idx < BACKUP_REGISTERS);
+        (RTC::ptr() as usize + BKP0R_OFFSET + 4 * idx) as *mut u32
+    }
+
+    /// Reads backup register `idx`
+    ///
+    /// # Panics
+    ///
+    /// Panics if `idx` is not below `BACKUP_REGISTERS`.
+    pub fn read(&self, idx: usize) -> u32 {
+        unsafe { ptr::read_volatile(Self::register(idx)) }
+    }
+
+    /// Writes backup register `idx`
+    ///
+    /// # Panics
+    ///
+    /// Panics if `idx` is not below `BACKUP_REGISTERS`.
+    pub fn write(&mut self, idx: usize, value: u32) {
+        unsafe { ptr::write_volatile(Self::register(idx), value) }
+    }
+}
+
+#[cfg(any(
+    feature = "stm32f405",
+    feature = "stm32f407",
+    feature = "stm32f415",
+    feature = "stm32f417",
+    feature = "stm32f427",
+    feature = "stm32f429",
+    feature = "stm32f437",
+    feature = "stm32f439",
+    feature = "stm32f446",
+    feature = "stm32f469",
+    feature = "stm32f479"
+))]
+mod sram {
+    use super::*;
+    use core::{mem, slice};
+
+    /// Start address of the backup SRAM
+    pub const BKPSRAM_START: usize = 0x4002_4000;
+    /// Size of the backup SRAM in bytes
+    pub const BKPSRAM_SIZE: usize = 4096;
+
+    // PWR_CSR bits
+    const CSR_BRR: u32 = 1 << 3;
+    const CSR_BRE: u32 = 1 << 9;
+
+    /// Ownership token of the backup SRAM, part of `rcc::Rcc`
+    pub struct BkpSram {
+        _0: (),
+    }
+
+    impl BkpSram {
+        pub(crate) fn new() -> Self {
+            BkpSram { _0: () }
+        }
+    }
+
+    /// Backup SRAM
+    pub struct BackupSram {
+        token: BkpSram,
+    }
+
+    impl BackupSram {
+        /// Enables the backup SRAM clock and the backup regulator, which keeps the contents
+        /// while the device is only supplied by VBAT.
+        pub fn new(token: BkpSram, _pwr: &mut PWR) -> Self {
+            enable_backup_access();
+
+            unsafe {
+                const EN_BIT: u8 = 18;
+                // NOTE(unsafe) this reference will only be used for atomic writes with no side effects.
+                let rcc = &(*RCC::ptr());
+
+                // Enable clock.
+                bb::set(&rcc.ahb1enr, EN_BIT);
+
+                // Enable the backup regulator and wait until it is ready
+                let pwr = &(*PWR::ptr());
+                pwr.csr.modify(|r, w| w.bits(r.bits() | CSR_BRE));
+                while pwr.csr.read().bits() & CSR_BRR == 0 {}
+            }
+
+            BackupSram { token }
+        }
+
+        /// Returns the contents of the backup SRAM
+        pub fn as_slice(&self) -> &[u8] {
+            unsafe { slice::from_raw_parts(BKPSRAM_START as *const u8, BKPSRAM_SIZE) }
+        }
+
+        /// Returns the contents of the backup SRAM for modification
+        pub fn as_mut_slice(&mut self) -> &mut [u8] {
+            unsafe { slice::from_raw_parts_mut(BKPSRAM_START as *mut u8, BKPSRAM_SIZE) }
+        }
+
+        /// Returns the start of the backup SRAM as a `T`
+        ///
+        /// # Safety
+        ///
+        /// The memory contents must be a valid `T`. After power loss without VBAT or a backup
+        /// domain reset the contents are undefined, so this is only sound for types without
+        /// invalid bit patterns or after validating the data, e.g. with a checksum.
+        ///
+        /// # Panics
+        ///
+        /// Panics if `T` is larger than the backup SRAM or needs an alignment above 4 KiB.
+        pub unsafe fn as_mut<T>(&mut self) -> &mut T {
+            assert!(mem::size_of::<T>() <= BKPSRAM_SIZE);
+            assert!(BKPSRAM_START % mem::align_of::<T>() == 0);
+            &mut *(BKPSRAM_START as *mut T)
+        }
+
+        /// Disables the backup regulator and the backup SRAM clock. The contents are kept
+        /// as long as VDD is present.
+        pub fn free(self, _pwr: &mut PWR) -> BkpSram {
+            unsafe {
+                // NOTE(unsafe) this reference will only be used for atomic writes with no side effects.
+                let rcc = &(*RCC::ptr());
+                bb::clear(&rcc.ahb1enr, 18);
+
+                let pwr = &(*PWR::ptr());
+                pwr.csr.modify(|r, w| w.bits(r.bits() & !CSR_BRE));
+            }
+
+            self.token
+        }
+    }
+}
+
+#[cfg(any(
+    feature = "stm32f405",
+    feature = "stm32f407",
+    feature = "stm32f415",
+    feature = "stm32f417",
+    feature = "stm32f427",
+    feature = "stm32f429",
+    feature = "stm32f437",
+    feature = "stm32f439",
+    feature = "stm32f446",
+    feature = "stm32f469",
+    feature = "stm32f479"
+))]
+pub use self::sram::*;
@@ -0,0 +1,1107 @@
+//! Controller area network (bxCAN)
+//!
+//! CAN1 and CAN2 share a single set of 28 filter banks which live in the CAN1 register block.
+//! They are configured through [`Can::modify_filters`](struct.Can.html#method.modify_filters),
+//! which is only available on CAN1. Banks below the split point belong to CAN1, banks at or
+//! above it to CAN2, so CAN1 must be set up even if only CAN2 is used for communication.
+//!
+//! Received frames are only stored in a FIFO if they pass at least one active filter bank.
+//! After reset no bank is active, so at least one bank has to be enabled before anything can
+//! be received.
+
+use core::cmp::Ordering;
+use core::ops::Deref;
+
+use crate::bb;
+use crate::gpio::{Alternate, AF9};
+use crate::rcc::Clocks;
+use crate::stm32::{can1, CAN1, CAN2, RCC};
+
+#[cfg(any(feature = "stm32f412", feature = "stm32f413", feature = "stm32f423"))]
+use crate::gpio::AF8;
+
+use crate::gpio::gpioa::{PA11, PA12};
+use crate::gpio::gpiob::{PB12, PB13, PB5, PB6, PB8, PB9};
+use crate::gpio::gpiod::{PD0, PD1};
+
+#[cfg(any(feature = "stm32f413", feature = "stm32f423"))]
+use crate::gpio::gpiog::{PG0, PG1, PG11, PG12};
+
+#[cfg(any(
+    feature = "stm32f405",
+    feature = "stm32f407",
+    feature = "stm32f415",
+    feature = "stm32f417",
+    feature = "stm32f427",
+    feature = "stm32f429",
+    feature = "stm32f437",
+    feature = "stm32f439",
+    feature = "stm32f469",
+    feature = "stm32f479"
+))]
+use crate::gpio::{gpioh::PH13, gpioi::PI9};
+
+/// Number of filter banks shared between CAN1 and CAN2
+pub const FILTER_BANKS: u8 = 28;
+
+// MCR bits
+const MCR_INRQ: u32 = 1 << 0;
+const MCR_SLEEP: u32 = 1 << 1;
+const MCR_TXFP: u32 = 1 << 2;
+const MCR_RFLM: u32 = 1 << 3;
+const MCR_NART: u32 = 1 << 4;
+const MCR_AWUM: u32 = 1 << 5;
+const MCR_ABOM: u32 = 1 << 6;
+const MCR_DBF: u32 = 1 << 16;
+
+// MSR bits
+const MSR_INAK: u32 = 1 << 0;
+const MSR_SLAK: u32 = 1 << 1;
+
+// TSR bits, per mailbox fields are shifted by 8 * mailbox
+const TSR_RQCP: u32 = 1 << 0;
+const TSR_TXOK: u32 = 1 << 1;
+const TSR_ABRQ: u32 = 1 << 7;
+const TSR_TME0: u32 = 1 << 26;
+
+// RFxR bits
+const RFR_FMP: u32 = 0b11;
+const RFR_FOVR: u32 = 1 << 4;
+const RFR_RFOM: u32 = 1 << 5;
+
+// BTR bits
+const BTR_LBKM: u32 = 1 << 30;
+const BTR_SILM: u32 = 1 << 31;
+
+// ESR bits
+const ESR_EWGF: u32 = 1 << 0;
+const ESR_EPVF: u32 = 1 << 1;
+const ESR_BOFF: u32 = 1 << 2;
+
+// Mailbox identifier register (TIxR / RIxR) bits
+const IR_TXRQ: u32 = 1 << 0;
+const IR_RTR: u32 = 1 << 1;
+const IR_IDE: u32 = 1 << 2;
+
+// FMR bits
+const FMR_FINIT: u32 = 1 << 0;
+
+pub mod config {
+    use crate::time::Bps;
+    use crate::time::U32Ext;
+
+    /// Operating mode of the controller
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    pub enum Mode {
+        /// Normal operation on the bus
+        Normal,
+        /// Transmitted frames are looped back internally and the bus is ignored on reception
+        Loopback,
+        /// Bus monitoring: frames are received but nothing (not even ACK bits) is sent
+        Silent,
+        /// Loopback and silent combined, for self tests without touching the bus
+        SilentLoopback,
+    }
+
+    pub struct Config {
+        pub bitrate: Bps,
+        /// Sample point position in per mille of the bit time
+        pub sample_point: u16,
+        /// Resynchronization jump width in time quanta (1..=4)
+        pub sjw: u8,
+        pub mode: Mode,
+        pub automatic_retransmission: bool,
+        pub automatic_bus_off_management: bool,
+        pub automatic_wakeup: bool,
+        /// Transmit pending mailboxes in request order instead of by identifier priority
+        pub transmit_fifo_priority: bool,
+        /// Discard new frames instead of overwriting the last one when a receive FIFO is full
+        pub rx_fifo_locked: bool,
+        /// Stop the controller while the core is halted by a debugger
+        pub debug_freeze: bool,
+    }
+
+    impl Config {
+        pub fn bitrate(mut self, bitrate: Bps) -> Self {
+            self.bitrate = bitrate;
+            self
+        }
+
+        pub fn sample_point(mut self, per_mille: u16) -> Self {
+            self.sample_point = per_mille;
+            self
+        }
+
+        pub fn sjw(mut self, sjw: u8) -> Self {
+            self.sjw = sjw;
+            self
+        }
+
+        pub fn mode(mut self, mode: Mode) -> Self {
+            self.mode = mode;
+            self
+        }
+
+        pub fn automatic_retransmission(mut self, enabled: bool) -> Self {
+            self.automatic_retransmission = enabled;
+            self
+        }
+
+        pub fn automatic_bus_off_management(mut self, enabled: bool) -> Self {
+            self.automatic_bus_off_management = enabled;
+            self
+        }
+
+        pub fn automatic_wakeup(mut self, enabled: bool) -> Self {
+            self.automatic_wakeup = enabled;
+            self
+        }
+
+        pub fn transmit_fifo_priority(mut self, enabled: bool) -> Self {
+            self.transmit_fifo_priority = enabled;
+            self
+        }
+
+        pub fn rx_fifo_locked(mut self, enabled: bool) -> Self {
+            self.rx_fifo_locked = enabled;
+            self
+        }
+
+        pub fn debug_freeze(mut self, enabled: bool) -> Self {
+            self.debug_freeze = enabled;
+            self
+        }
+    }
+
+    #[derive(Debug)]
+    pub struct InvalidConfig;
+
+    impl Default for Config {
+        fn default() -> Config {
+            Config {
+                bitrate: 500_000_u32.bps(),
+                sample_point: 875,
+                sjw: 1,
+                mode: Mode::Normal,
+                automatic_retransmission: true,
+                automatic_bus_off_management: false,
+                automatic_wakeup: false,
+                transmit_fifo_priority: false,
+                rx_fifo_locked: false,
+                debug_freeze: false,
+            }
+        }
+    }
+}
+
+/// CAN error
+#[derive(Debug)]
+pub enum Error {
+    /// A frame was lost because the receive FIFO was full
+    Overrun,
+    #[doc(hidden)]
+    _Extensible,
+}
+
+/// Interrupt event
+pub enum Event {
+    /// A transmit mailbox became empty
+    TxMailboxEmpty,
+    /// A frame is pending in FIFO 0
+    Fifo0MessagePending,
+    /// FIFO 0 is full
+    Fifo0Full,
+    /// FIFO 0 overrun
+    Fifo0Overrun,
+    /// A frame is pending in FIFO 1
+    Fifo1MessagePending,
+    /// FIFO 1 is full
+    Fifo1Full,
+    /// FIFO 1 overrun
+    Fifo1Overrun,
+    /// An error counter reached the warning limit (96)
+    ErrorWarning,
+    /// An error counter exceeded 127
+    ErrorPassive,
+    /// The controller entered the bus-off state
+    BusOff,
+    /// The last error code was updated by the hardware
+    LastErrorCode,
+    /// Any enabled error condition. Has to be enabled together with the individual error
+    /// events to generate the status change error interrupt.
+    Error,
+    /// Start of frame detected while in sleep mode
+    Wakeup,
+    /// Sleep mode entered
+    Sleep,
+}
+
+impl Event {
+    fn ier_bit(&self) -> u32 {
+        match self {
+            Event::TxMailboxEmpty => 1 << 0,
+            Event::Fifo0MessagePending => 1 << 1,
+            Event::Fifo0Full => 1 << 2,
+            Event::Fifo0Overrun => 1 << 3,
+            Event::Fifo1MessagePending => 1 << 4,
+            Event::Fifo1Full => 1 << 5,
+            Event::Fifo1Overrun => 1 << 6,
+            Event::ErrorWarning => 1 << 8,
+            Event::ErrorPassive => 1 << 9,
+            Event::BusOff => 1 << 10,
+            Event::LastErrorCode => 1 << 11,
+            Event::Error => 1 << 15,
+            Event::Wakeup => 1 << 16,
+            Event::Sleep => 1 << 17,
+        }
+    }
+}
+
+/// Receive FIFO
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Fifo {
+    Fifo0 = 0,
+    Fifo1 = 1,
+}
+
+/// Transmit mailbox
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Mailbox {
+    Mailbox0 = 0,
+    Mailbox1 = 1,
+    Mailbox2 = 2,
+}
+
+impl Mailbox {
+    fn from_index(idx: usize) -> Self {
+        match idx {
+            0 => Mailbox::Mailbox0,
+            1 => Mailbox::Mailbox1,
+            _ => Mailbox::Mailbox2,
+        }
+    }
+}
+
+/// Error code of the last error detected on the bus
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LastErrorCode {
+    StuffError,
+    FormError,
+    AcknowledgmentError,
+    BitRecessiveError,
+    BitDominantError,
+    CrcError,
+}
+
+/// Error state of the controller
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ErrorState {
+    /// Both error counters are below 96
+    Active,
+    /// At least one error counter reached the warning limit of 96
+    Warning,
+    /// At least one error counter exceeded 127
+    Passive,
+    /// The transmit error counter exceeded 255, the controller no longer takes part in bus
+    /// activity
+    BusOff,
+}
+
+/// Transmit and receive error counters
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ErrorCounters {
+    pub transmit: u8,
+    pub receive: u8,
+}
+
+/// CAN identifier
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Id {
+    /// 11-bit standard identifier
+    Standard(u16),
+    /// 29-bit extended identifier
+    Extended(u32),
+}
+
+impl Id {
+    /// Identifier in the layout of the mailbox identifier registers (without RTR and TXRQ)
+    fn to_ir_bits(self) -> u32 {
+        match self {
+            Id::Standard(id) => u32::from(id & 0x7ff) << 21,
+            Id::Extended(id) => ((id & 0x1fff_ffff) << 3) | IR_IDE,
+        }
+    }
+
+    fn from_ir_bits(bits: u32) -> Self {
+        if bits & IR_IDE != 0 {
+            Id::Extended(bits >> 3)
+        } else {
+            Id::Standard((bits >> 21) as u16)
+        }
+    }
+}
+
+/// A CAN data or remote frame
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Frame {
+    id: Id,
+    rtr: bool,
+    dlc: u8,
+    data: [u8; 8],
+}
+
+impl Frame {
+    /// Creates a data frame. Returns `None` if more than 8 bytes of data are given.
+    pub fn new_data(id: Id, data: &[u8]) -> Option<Self> {
+        if data.len() > 8 {
+            return None;
+        }
+        let mut buf = [0; 8];
+        buf[..data.len()].copy_from_slice(data);
+        Some(Frame {
+            id,
+            rtr: false,
+            dlc: data.len() as u8,
+            data: buf,
+        })
+    }
+
+    /// Creates a remote frame requesting `dlc` bytes. Returns `None` if `dlc` is larger than 8.
+    pub fn new_remote(id: Id, dlc: u8) -> Option<Self> {
+        if dlc > 8 {
+            return None;
+        }
+        Some(Frame {
+            id,
+            rtr: true,
+            dlc,
+            data: [0; 8],
+        })
+    }
+
+    pub fn id(&self) -> Id {
+        self.id
+    }
+
+    pub fn is_remote_frame(&self) -> bool {
+        self.rtr
+    }
+
+    /// Data length code
+    pub fn dlc(&self) -> u8 {
+        self.dlc
+    }
+
+    /// Frame payload, empty for remote frames
+    pub fn data(&self) -> &[u8] {
+        if self.rtr {
+            &[]
+        } else {
+            &self.data[..usize::from(self.dlc)]
+        }
+    }
+
+    /// Identifier register value used for arbitration. Lower values win.
+    fn ir_bits(&self) -> u32 {
+        self.id.to_ir_bits() | if self.rtr { IR_RTR } else { 0 }
+    }
+
+    /// Compares the bus arbitration priority of two frames. `Ordering::Greater` means `self`
+    /// wins arbitration against `other`.
+    pub fn priority_cmp(&self, other: &Frame) -> Ordering {
+        other.ir_bits().cmp(&self.ir_bits())
+    }
+}
+
+/// Configuration of one filter bank
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BankConfig {
+    fr1: u32,
+    fr2: u32,
+    list: bool,
+    scale32: bool,
+}
+
+impl BankConfig {
+    /// Accepts every frame
+    pub fn accept_all() -> Self {
+        BankConfig {
+            fr1: 0,
+            fr2: 0,
+            list: false,
+            scale32: true,
+        }
+    }
+
+    /// Accepts frames whose identifier bits selected by `mask` match `id`. The identifier type
+    /// (standard or extended) always has to match. If `match_rtr` is false, both data and
+    /// remote frames pass the filter, otherwise only data frames.
+    pub fn mask32(id: Id, mask: u32, match_rtr: bool) -> Self {
+        let id_mask = match id {
+            Id::Standard(_) => Id::Standard(mask as u16),
+            Id::Extended(_) => Id::Extended(mask),
+        };
+        let mut fr2 = id_mask.to_ir_bits() | IR_IDE;
+        if match_rtr {
+            fr2 |= IR_RTR;
+        }
+        BankConfig {
+            fr1: id.to_ir_bits(),
+            fr2,
+            list: false,
+            scale32: true,
+        }
+    }
+
+    /// Accepts exactly the two given frame identifiers (data or remote frames as selected)
+    pub fn list32(ids: [(Id, bool); 2]) -> Self {
+        let bits = |(id, rtr): (Id, bool)| id.to_ir_bits() | if rtr { IR_RTR } else { 0 };
+        BankConfig {
+            fr1: bits(ids[0]),
+            fr2: bits(ids[1]),
+            list: true,
+            scale32: true,
+        }
+    }
+
+    /// Two mask filters for standard data frames, given as `(id, mask)` pairs
+    pub fn mask16(filters: [(u16, u16); 2]) -> Self {
+        let half = |(id, mask): (u16, u16)| {
+            let id = u32::from(id & 0x7ff) << 5;
+            // Always match IDE (bit 3) and RTR (bit 4)
+            let mask = (u32::from(mask & 0x7ff) << 5) | (1 << 4) | (1 << 3);
+            id | (mask << 16)
+        };
+        BankConfig {
+            fr1: half(filters[0]),
+            fr2: half(filters[1]),
+            list: false,
+            scale32: false,
+        }
+    }
+
+    /// Accepts exactly four standard data frame identifiers
+    pub fn list16(ids: [u16; 4]) -> Self {
+        let pair = |a: u16, b: u16| (u32::from(a & 0x7ff) << 5) | (u32::from(b & 0x7ff) << 21);
+        BankConfig {
+            fr1: pair(ids[0], ids[1]),
+            fr2: pair(ids[2], ids[3]),
+            list: true,
+            scale32: false,
+        }
+    }
+}
+
+/// Access to the shared filter banks while they are in initialization mode
+///
+/// Frames are not received while this is alive. The banks are activated again when it is
+/// dropped.
+pub struct FilterBanks<'a> {
+    can: &'a CAN1,
+}
+
+impl<'a> FilterBanks<'a> {
+    fn new(can: &'a CAN1) -> Self {
+        can.fmr
+            .modify(|r, w| unsafe { w.bits(r.bits() | FMR_FINIT) });
+        FilterBanks { can }
+    }
+
+    /// Sets the first bank assigned to CAN2. Banks `0..start` belong to CAN1, the rest to
+    /// CAN2. Disables all banks because their owner may have changed.
+    pub fn set_split(&mut self, start: u8) -> &mut Self {
+        assert!(start <= FILTER_BANKS);
+        self.clear();
+        self.can.fmr.modify(|r, w| unsafe {
+            w.bits((r.bits() & !(0x3f << 8)) | (u32::from(start) << 8) | FMR_FINIT)
+        });
+        self
+    }
+
+    /// First bank assigned to CAN2
+    pub fn split(&self) -> u8 {
+        ((self.can.fmr.read().bits() >> 8) & 0x3f) as u8
+    }
+
+    /// Configures and activates `bank`, storing matching frames in `fifo`
+    pub fn enable_bank(&mut self, bank: u8, fifo: Fifo, config: BankConfig) -> &mut Self {
+        assert!(bank < FILTER_BANKS);
+        let bit = 1 << bank;
+        let set = |value: bool, bits: u32| if value { bits | bit } else { bits & !bit };
+
+        // The bank has to be deactivated while its registers are modified
+        self.can
+            .fa1r
+            .modify(|r, w| unsafe { w.bits(r.bits() & !bit) });
+        self.can
+            .fm1r
+            .modify(|r, w| unsafe { w.bits(set(config.list, r.bits())) });
+        self.can
+            .fs1r
+            .modify(|r, w| unsafe { w.bits(set(config.scale32, r.bits())) });
+        self.can
+            .ffa1r
+            .modify(|r, w| unsafe { w.bits(set(fifo == Fifo::Fifo1, r.bits())) });
+
+        let fb = &self.can.fb[usize::from(bank)];
+        fb.fr1.write(|w| unsafe { w.bits(config.fr1) });
+        fb.fr2.write(|w| unsafe { w.bits(config.fr2) });
+
+        self.can
+            .fa1r
+            .modify(|r, w| unsafe { w.bits(r.bits() | bit) });
+        self
+    }
+
+    /// Deactivates `bank`
+    pub fn disable_bank(&mut self, bank: u8) -> &mut Self {
+        assert!(bank < FILTER_BANKS);
+        self.can
+            .fa1r
+            .modify(|r, w| unsafe { w.bits(r.bits() & !(1 << bank)) });
+        self
+    }
+
+    /// Deactivates all banks
+    pub fn clear(&mut self) -> &mut Self {
+        self.can.fa1r.write(|w| unsafe { w.bits(0) });
+        self
+    }
+}
+
+impl<'a> Drop for FilterBanks<'a> {
+    fn drop(&mut self) {
+        self.can
+            .fmr
+            .modify(|r, w| unsafe { w.bits(r.bits() & !FMR_FINIT) });
+    }
+}
+
+pub trait Pins<CAN> {}
+pub trait PinTx<CAN> {}
+pub trait PinRx<CAN> {}
+
+impl<CAN, TX, RX> Pins<CAN> for (TX, RX)
+where
+    TX: PinTx<CAN>,
+    RX: PinRx<CAN>,
+{
+}
+
+macro_rules! pins {
+    ($($CANX:ty: TX: [$($TX:ty),*] RX: [$($RX:ty),*])+) => {
+        $(
+            $(
+                impl PinTx<$CANX> for $TX {}
+            )*
+            $(
+                impl PinRx<$CANX> for $RX {}
+            )*
+        )+
+    }
+}
+
+pins! {
+    CAN1:
+        TX: [
+            PA12<Alternate<AF9>>,
+            PD1<Alternate<AF9>>
+        ]
+        RX: [
+            PA11<Alternate<AF9>>,
+            PD0<Alternate<AF9>>
+        ]
+
+    CAN2:
+        TX: [
+            PB6<Alternate<AF9>>,
+            PB13<Alternate<AF9>>
+        ]
+        RX: [
+            PB5<Alternate<AF9>>,
+            PB12<Alternate<AF9>>
+        ]
+}
+
+#[cfg(any(
+    feature = "stm32f405",
+    feature = "stm32f407",
+    feature = "stm32f415",
+    feature = "stm32f417",
+    feature = "stm32f427",
+    feature = "stm32f429",
+    feature = "stm32f437",
+    feature = "stm32f439",
+    feature = "stm32f446",
+    feature = "stm32f469",
+    feature = "stm32f479"
+))]
+pins! {
+    CAN1:
+        TX: [PB9<Alternate<AF9>>]
+        RX: [PB8<Alternate<AF9>>]
+}
+
+#[cfg(any(feature = "stm32f412", feature = "stm32f413", feature = "stm32f423"))]
+pins! {
+    CAN1:
+        TX: [PB9<Alternate<AF8>>]
+        RX: [PB8<Alternate<AF8>>]
+}
+
+#[cfg(any(
+    feature = "stm32f405",
+    feature = "stm32f407",
+    feature = "stm32f415",
+    feature = "stm32f417",
+    feature = "stm32f427",
+    feature = "stm32f429",
+    feature = "stm32f437",
+    feature = "stm32f439",
+    feature = "stm32f469",
+    feature = "stm32f479"
+))]
+pins! {
+    CAN1:
+        TX: [PH13<Alternate<AF9>>]
+        RX: [PI9<Alternate<AF9>>]
+}
+
+#[cfg(any(feature = "stm32f413", feature = "stm32f423"))]
+pins! {
+    CAN1:
+        TX: [PG1<Alternate<AF9>>]
+        RX: [PG0<Alternate<AF9>>]
+
+    CAN2:
+        TX: [PG12<Alternate<AF9>>]
+        RX: [PG11<Alternate<AF9>>]
+}
+
+/// CAN peripheral
+pub struct Can<CAN, PINS> {
+    can: CAN,
+    pins: PINS,
+}
+
+impl<PINS> Can<CAN1, PINS> {
+    pub fn can1(
+        can: CAN1,
+        pins: PINS,
+        config: config::Config,
+        clocks: Clocks,
+    ) -> Result<Self, config::InvalidConfig>
+    where
+        PINS: Pins<CAN1>,
+    {
+        unsafe {
+            const EN_BIT: u8 = 25;
+            // NOTE(unsafe) this reference will only be used for atomic writes with no side effects.
+            let rcc = &(*RCC::ptr());
+
+            // Enable clock.
+            bb::set(&rcc.apb1enr, EN_BIT);
+        }
+
+        Can { can, pins }.init(config, clocks)
+    }
+
+    /// Gives access to the filter banks shared by CAN1 and CAN2
+    pub fn modify_filters(&mut self) -> FilterBanks<'_> {
+        FilterBanks::new(&self.can)
+    }
+}
+
+impl<PINS> Can<CAN2, PINS> {
+    /// Configures CAN2. The filter banks are owned by CAN1 and have to be set up with
+    /// [`Can::modify_filters`](struct.Can.html#method.modify_filters) on the CAN1 instance.
+    pub fn can2(
+        can: CAN2,
+        pins: PINS,
+        config: config::Config,
+        clocks: Clocks,
+    ) -> Result<Self, config::InvalidConfig>
+    where
+        PINS: Pins<CAN2>,
+    {
+        unsafe {
+            // NOTE(unsafe) this reference will only be used for atomic writes with no side effects.
+            let rcc = &(*RCC::ptr());
+
+            // Enable clock. CAN2 needs the CAN1 clock for access to the filter banks.
+            bb::set(&rcc.apb1enr, 25);
+            bb::set(&rcc.apb1enr, 26);
+        }
+
+        Can { can, pins }.init(config, clocks)
+    }
+}
+
+impl<CAN, PINS> Can<CAN, PINS>
+where
+    CAN: Deref<Target = can1::RegisterBlock>,
+{
+    fn init(self, config: config::Config, clocks: Clocks) -> Result<Self, config::InvalidConfig> {
+        use self::config::Mode;
+
+        if config.sjw < 1 || config.sjw > 4 {
+            return Err(config::InvalidConfig);
+        }
+        let (brp, ts1, ts2) = bit_timing(clocks.pclk1().0, config.bitrate.0, config.sample_point)
+            .ok_or(config::InvalidConfig)?;
+        // SJW must not be longer than phase segment 2
+        if u32::from(config.sjw) > ts2 {
+            return Err(config::InvalidConfig);
+        }
+
+        // Leave sleep mode and request initialization
+        self.can
+            .mcr
+            .modify(|r, w| unsafe { w.bits((r.bits() & !MCR_SLEEP) | MCR_INRQ) });
+        while self.can.msr.read().bits() & (MSR_INAK | MSR_SLAK) != MSR_INAK {}
+
+        let mode = match config.mode {
+            Mode::Normal => 0,
+            Mode::Loopback => BTR_LBKM,
+            Mode::Silent => BTR_SILM,
+            Mode::SilentLoopback => BTR_LBKM | BTR_SILM,
+        };
+        self.can.btr.write(|w| unsafe {
+            w.bits(
+                mode | (u32::from(config.sjw - 1) << 24)
+                    | ((ts2 - 1) << 20)
+                    | ((ts1 - 1) << 16)
+                    | (brp - 1),
+            )
+        });
+
+        let flag = |enabled: bool, bit: u32| if enabled { bit } else { 0 };
+        self.can.mcr.write(|w| unsafe {
+            w.bits(
+                MCR_INRQ
+                    | flag(config.debug_freeze, MCR_DBF)
+                    | flag(config.automatic_bus_off_management, MCR_ABOM)
+                    | flag(config.automatic_wakeup, MCR_AWUM)
+                    | flag(!config.automatic_retransmission, MCR_NART)
+                    | flag(config.rx_fifo_locked, MCR_RFLM)
+                    | flag(config.transmit_fifo_priority, MCR_TXFP),
+            )
+        });
+
+        // Leave initialization mode. The controller synchronizes to the bus after
+        // 11 consecutive recessive bits.
+        self.leave_init();
+
+        Ok(self)
+    }
+
+    fn leave_init(&self) {
+        self.can
+            .mcr
+            .modify(|r, w| unsafe { w.bits(r.bits() & !MCR_INRQ) });
+        while self.can.msr.read().bits() & MSR_INAK != 0 {}
+    }
+
+    /// Puts a frame into a free transmit mailbox.
+    ///
+    /// If all mailboxes are occupied and `frame` has a higher priority than one of the pending
+    /// frames, the lowest priority pending frame is aborted and replaced by `frame`. The
+    /// aborted frame is returned so it can be sent again later. Returns `WouldBlock` if all
+    /// mailboxes hold frames of higher or equal priority.
+    pub fn transmit(&mut self, frame: &Frame) -> nb::Result<Option<Frame>, Error> {
+        let tsr = self.can.tsr.read().bits();
+
+        let (idx, replaced) = if tsr & (0b111 * TSR_TME0) != 0 {
+            // CODE holds the number of the next empty mailbox
+            (((tsr >> 24) & 0b11) as usize, None)
+        } else {
+            // Find the pending frame with the lowest priority
+            let idx = (0..3)
+                .max_by_key(|&i| self.can.tx[i].tir.read().bits() & !IR_TXRQ)
+                .unwrap();
+            let pending = self.can.tx[idx].tir.read().bits() & !IR_TXRQ;
+            if frame.ir_bits() >= pending {
+                return Err(nb::Error::WouldBlock);
+            }
+            (idx, self.abort_mailbox(idx))
+        };
+
+        let tx = &self.can.tx[idx];
+        tx.tdtr.write(|w| unsafe { w.bits(u32::from(frame.dlc)) });
+        tx.tdlr.write(|w| unsafe {
+            w.bits(u32::from_le_bytes([
+                frame.data[0],
+                frame.data[1],
+                frame.data[2],
+                frame.data[3],
+            ]))
+        });
+        tx.tdhr.write(|w| unsafe {
+            w.bits(u32::from_le_bytes([
+                frame.data[4],
+                frame.data[5],
+                frame.data[6],
+                frame.data[7],
+            ]))
+        });
+        tx.tir
+            .write(|w| unsafe { w.bits(frame.ir_bits() | IR_TXRQ) });
+
+        Ok(replaced)
+    }
+
+    /// Aborts a pending transmission. Returns the frame if it was still pending and not
+    /// already transmitted.
+    pub fn abort(&mut self, mailbox: Mailbox) -> Option<Frame> {
+        let idx = mailbox as usize;
+        if self.can.tsr.read().bits() & (TSR_TME0 << idx) != 0 {
+            return None;
+        }
+        self.abort_mailbox(idx)
+    }
+
+    fn abort_mailbox(&self, idx: usize) -> Option<Frame> {
+        let shift = 8 * idx;
+        self.can.tsr.write(|w| unsafe { w.bits(TSR_ABRQ << shift) });
+        // The request completes once the frame is aborted or its transmission finished
+        while self.can.tsr.read().bits() & (TSR_RQCP << shift) == 0 {}
+
+        let tsr = self.can.tsr.read().bits();
+        // Clear the status bits of the mailbox
+        self.can.tsr.write(|w| unsafe { w.bits(TSR_RQCP << shift) });
+        if tsr & (TSR_TXOK << shift) != 0 {
+            return None;
+        }
+
+        let tx = &self.can.tx[idx];
+        let tir = tx.tir.read().bits();
+        let dlc = (tx.tdtr.read().bits() & 0xf).min(8) as u8;
+        let mut data = [0; 8];
+        data[..4].copy_from_slice(&tx.tdlr.read().bits().to_le_bytes());
+        data[4..].copy_from_slice(&tx.tdhr.read().bits().to_le_bytes());
+        Some(Frame {
+            id: Id::from_ir_bits(tir),
+            rtr: tir & IR_RTR != 0,
+            dlc,
+            data,
+        })
+    }
+
+    /// Returns `true` if no transmission is pending in any mailbox
+    pub fn is_transmitter_idle(&self) -> bool {
+        self.can.tsr.read().bits() & (0b111 * TSR_TME0) == 0b111 * TSR_TME0
+    }
+
+    /// Returns `true` if `mailbox` is empty
+    pub fn is_mailbox_empty(&self, mailbox: Mailbox) -> bool {
+        self.can.tsr.read().bits() & (TSR_TME0 << mailbox as usize) != 0
+    }
+
+    /// Returns the mailbox that will be used by the next call to `transmit`, if any is free
+    pub fn next_free_mailbox(&self) -> Option<Mailbox> {
+        let tsr = self.can.tsr.read().bits();
+        if tsr & (0b111 * TSR_TME0) != 0 {
+            Some(Mailbox::from_index(((tsr >> 24) & 0b11) as usize))
+        } else {
+            None
+        }
+    }
+
+    /// Receives a frame from FIFO 0, or from FIFO 1 if FIFO 0 is empty
+    pub fn receive(&mut self) -> nb::Result<Frame, Error> {
+        match self.receive_fifo(Fifo::Fifo0) {
+            Err(nb::Error::WouldBlock) => self.receive_fifo(Fifo::Fifo1),
+            result => result,
+        }
+    }
+
+    /// Receives a frame from the given FIFO.
+    ///
+    /// Returns `Error::Overrun` once after a frame was lost. The frames still stored in the
+    /// FIFO can be read with the following calls.
+    pub fn receive_fifo(&mut self, fifo: Fifo) -> nb::Result<Frame, Error> {
+        let rfr = match fifo {
+            Fifo::Fifo0 => &self.can.rf0r,
+            Fifo::Fifo1 => &self.can.rf1r,
+        };
+        let status = rfr.read().bits();
+
+        if status & RFR_FOVR != 0 {
+            rfr.write(|w| unsafe { w.bits(RFR_FOVR) });
+            return Err(nb::Error::Other(Error::Overrun));
+        }
+        if status & RFR_FMP == 0 {
+            return Err(nb::Error::WouldBlock);
+        }
+
+        let rx = &self.can.rx[fifo as usize];
+        let rir = rx.rir.read().bits();
+        let dlc = (rx.rdtr.read().bits() & 0xf).min(8) as u8;
+        let mut data = [0; 8];
+        data[..4].copy_from_slice(&rx.rdlr.read().bits().to_le_bytes());
+        data[4..].copy_from_slice(&rx.rdhr.read().bits().to_le_bytes());
+
+        // Release the output mailbox
+        rfr.write(|w| unsafe { w.bits(RFR_RFOM) });
+
+        Ok(Frame {
+            id: Id::from_ir_bits(rir),
+            rtr: rir & IR_RTR != 0,
+            dlc,
+            data,
+        })
+    }
+
+    /// Number of frames pending in `fifo`
+    pub fn pending_frames(&self, fifo: Fifo) -> u8 {
+        let rfr = match fifo {
+            Fifo::Fifo0 => &self.can.rf0r,
+            Fifo::Fifo1 => &self.can.rf1r,
+        };
+        (rfr.read().bits() & RFR_FMP) as u8
+    }
+
+    /// Current values of the transmit and receive error counters
+    pub fn error_counters(&self) -> ErrorCounters {
+        let esr = self.can.esr.read().bits();
+        ErrorCounters {
+            transmit: (esr >> 16) as u8,
+            receive: (esr >> 24) as u8,
+        }
+    }
+
+    /// Current error state derived from the error counters
+    pub fn error_state(&self) -> ErrorState {
+        let esr = self.can.esr.read().bits();
+        if esr & ESR_BOFF != 0 {
+            ErrorState::BusOff
+        } else if esr & ESR_EPVF != 0 {
+            ErrorState::Passive
+        } else if esr & ESR_EWGF != 0 {
+            ErrorState::Warning
+        } else {
+            ErrorState::Active
+        }
+    }
+
+    /// Returns `true` if the controller is in the bus-off state
+    pub fn is_bus_off(&self) -> bool {
+        self.can.esr.read().bits() & ESR_BOFF != 0
+    }
+
+    /// Error code of the last error detected on the bus, if any
+    pub fn last_error_code(&self) -> Option<LastErrorCode> {
+        match (self.can.esr.read().bits() >> 4) & 0b111 {
+            0b001 => Some(LastErrorCode::StuffError),
+            0b010 => Some(LastErrorCode::FormError),
+            0b011 => Some(LastErrorCode::AcknowledgmentError),
+            0b100 => Some(LastErrorCode::BitRecessiveError),
+            0b101 => Some(LastErrorCode::BitDominantError),
+            0b110 => Some(LastErrorCode::CrcError),
+            _ => None,
+        }
+    }
+
+    /// Resets the last error code. Setting the code to "set by software" lets the next error
+    /// be told apart from an old one.
+    pub fn clear_last_error_code(&mut self) {
+        self.can
+            .esr
+            .modify(|r, w| unsafe { w.bits(r.bits() | (0b111 << 4)) });
+    }
+
+    /// Starts the bus-off recovery sequence when automatic bus-off management is disabled.
+    ///
+    /// The controller rejoins the bus after it has monitored 128 occurrences of 11 consecutive
+    /// recessive bits. This blocks until the controller left initialization mode, not until
+    /// the recovery is complete; check `is_bus_off` for that.
+    pub fn recover_from_bus_off(&mut self) {
+        self.can
+            .mcr
+            .modify(|r, w| unsafe { w.bits(r.bits() | MCR_INRQ) });
+        while self.can.msr.read().bits() & MSR_INAK == 0 {}
+        self.leave_init();
+    }
+
+    /// Enable the interrupt for the given `event`
+    pub fn listen(&mut self, event: Event) {
+        let bit = event.ier_bit();
+        self.can
+            .ier
+            .modify(|r, w| unsafe { w.bits(r.bits() | bit) });
+    }
+
+    /// Disable the interrupt for the given `event`
+    pub fn unlisten(&mut self, event: Event) {
+        let bit = event.ier_bit();
+        self.can
+            .ier
+            .modify(|r, w| unsafe { w.bits(r.bits() & !bit) });
+    }
+
+    /// Clears the request completed flags of all transmit mailboxes. Has to be called from the
+    /// transmit interrupt handler.
+    pub fn clear_tx_interrupt(&mut self) {
+        self.can
+            .tsr
+            .write(|w| unsafe { w.bits(TSR_RQCP | (TSR_RQCP << 8) | (TSR_RQCP << 16)) });
+    }
+
+    /// Clears the status change error, wakeup and sleep acknowledge interrupt flags
+    pub fn clear_status_interrupts(&mut self) {
+        // ERRI (bit 2), WKUI (bit 3) and SLAKI (bit 4) are cleared by writing 1
+        self.can
+            .msr
+            .write(|w| unsafe { w.bits((1 << 2) | (1 << 3) | (1 << 4)) });
+    }
+
+    pub fn free(self) -> (CAN, PINS) {
+        (self.can, self.pins)
+    }
+}
+
+/// Finds a prescaler and segment lengths in time quanta that produce exactly `bitrate` from
+/// `pclk`, with the sample point as close as possible to `sample_point` (per mille).
+/// Returns `(brp, ts1, ts2)`.
+fn bit_timing(pclk: u32, bitrate: u32, sample_point: u16) -> Option<(u32, u32, u32)> {
+    let sample_point = u32::from(sample_point);
+    if bitrate == 0 || sample_point == 0 || sample_point >= 1000 {
+        return None;
+    }
+
+    let mut best: Option<(u32, u32, u32, u32)> = None;
+    // Prefer more time quanta per bit for a finer sample point resolution
+    for tq in (8..=25).rev() {
+        let div = match bitrate.checked_mul(tq) {
+            Some(div) => div,
+            None => continue,
+        };
+        if pclk % div != 0 {
+            continue;
+        }
+        let brp = pclk / div;
+        if brp == 0 || brp > 1024 {
+            continue;
+        }
+
+        // Sample point lies after the sync segment and TS1
+        let before = ((tq * sample_point + 500) / 1000).max(2).min(17);
+        let ts1 = before - 1;
+        let ts2 = tq - before;
+        if ts2 < 1 || ts2 > 8 {
+            continue;
+        }
+
+        let actual = before * 1000 / tq;
+        let error = if actual > sample_point {
+            actual - sample_point
+        } else {
+            sample_point - actual
+        };
+        if best.map_or(true, |(e, _, _, _)| error < e) {
+            best = Some((error, brp, ts1, ts2));
+        }
+    }
+
+    best.map(|(_, brp, ts1, ts2)| (brp, ts1, ts2))
+}
@@ -0,0 +1,701 @@
+//! Input capture and PWM input
+//!
+//! [`Capture`](struct.Capture.html) latches the counter value on edges of the channels given by
+//! its pins, which are the same pin sets as used by `pwm`. The difference of two captures is
+//! the time between the edges in timer ticks.
+//!
+//! [`PwmInput`](struct.PwmInput.html) measures period and high time of a signal on channel 1.
+//! Both capture channels are connected to TI1, the rising edge resets the counter through the
+//! slave mode controller and latches the period, the falling edge latches the high time.
+
+use cast::u16;
+
+use crate::{bb, hal, pwm::Pins, rcc::Clocks, stm32::RCC, time::Hertz, timer::PinC1};
+
+#[cfg(any(
+    feature = "stm32f401",
+    feature = "stm32f405",
+    feature = "stm32f407",
+    feature = "stm32f410",
+    feature = "stm32f411",
+    feature = "stm32f412",
+    feature = "stm32f413",
+    feature = "stm32f415",
+    feature = "stm32f417",
+    feature = "stm32f423",
+    feature = "stm32f427",
+    feature = "stm32f429",
+    feature = "stm32f437",
+    feature = "stm32f439",
+    feature = "stm32f446",
+    feature = "stm32f469",
+    feature = "stm32f479"
+))]
+use crate::stm32::{TIM1, TIM11, TIM5, TIM9};
+
+#[cfg(any(
+    feature = "stm32f401",
+    feature = "stm32f405",
+    feature = "stm32f407",
+    feature = "stm32f411",
+    feature = "stm32f412",
+    feature = "stm32f413",
+    feature = "stm32f415",
+    feature = "stm32f417",
+    feature = "stm32f423",
+    feature = "stm32f427",
+    feature = "stm32f429",
+    feature = "stm32f437",
+    feature = "stm32f439",
+    feature = "stm32f446",
+    feature = "stm32f469",
+    feature = "stm32f479"
+))]
+use crate::stm32::{TIM10, TIM2, TIM3, TIM4};
+
+#[cfg(any(
+    feature = "stm32f405",
+    feature = "stm32f407",
+    feature = "stm32f412",
+    feature = "stm32f413",
+    feature = "stm32f415",
+    feature = "stm32f417",
+    feature = "stm32f423",
+    feature = "stm32f427",
+    feature = "stm32f429",
+    feature = "stm32f437",
+    feature = "stm32f439",
+    feature = "stm32f446",
+    feature = "stm32f469",
+    feature = "stm32f479"
+))]
+use crate::stm32::{TIM12, TIM13, TIM14, TIM8};
+
+// TIMx_SR bits
+const SR_UIF: u32 = 1 << 0;
+const SR_CC1IF: u32 = 1 << 1;
+const SR_CC1OF: u32 = 1 << 9;
+
+/// Input capture error
+#[derive(Debug)]
+pub enum Error {
+    /// An edge was captured before the previous capture was read
+    Overcapture,
+    /// The resolution is higher than the timer clock or too low for the prescaler
+    InvalidResolution,
+    /// The channel is not available on this timer
+    InvalidChannel,
+    #[doc(hidden)]
+    _Extensible,
+}
+
+/// Capture channel
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Channel {
+    C1 = 0,
+    C2 = 1,
+    C3 = 2,
+    C4 = 3,
+}
+
+/// Interrupt events
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Event {
+    /// An edge was captured on the channel
+    Capture(Channel),
+    /// The counter wrapped around
+    Overflow,
+}
+
+/// Edges that trigger a capture
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Edge {
+    Rising,
+    Falling,
+    Both,
+}
+
+/// Input prescaler, a capture is done on every Nth edge
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Prescaler {
+    Div1 = 0,
+    Div2 = 1,
+    Div4 = 2,
+    Div8 = 3,
+}
+
+/// Capture channel configuration
+#[derive(Clone, Copy, Debug)]
+pub struct Config {
+    pub edge: Edge,
+    /// Input filter (ICxF, 0 to 15). Higher values need more consecutive equal samples
+    /// before a level change is accepted, see the reference manual for the sampling clocks.
+    pub filter: u8,
+    pub prescaler: Prescaler,
+}
+
+impl Config {
+    pub fn edge(mut self, edge: Edge) -> Self {
+        self.edge = edge;
+        self
+    }
+
+    pub fn filter(mut self, filter: u8) -> Self {
+        self.filter = filter;
+        self
+    }
+
+    pub fn prescaler(mut self, prescaler: Prescaler) -> Self {
+        self.prescaler = prescaler;
+        self
+    }
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            edge: Edge::Rising,
+            filter: 0,
+            prescaler: Prescaler::Div1,
+        }
+    }
+}
+
+/// Input capture on the channels given by `PINS`
+pub struct Capture<TIM, PINS> {
+    tim: TIM,
+    pins: PINS,
+    clk: Hertz,
+}
+
+/// PWM input measurement on channel 1
+pub struct PwmInput<TIM, PIN> {
+    tim: TIM,
+    pin: PIN,
+    clk: Hertz,
+}
+
+/// Prescaler value for a counter running at `resolution`
+fn prescaler(clk: Hertz, resolution: Hertz) -> Result<u16, Error> {
+    match clk.0.checked_div(resolution.0) {
+        Some(div) if div > 0 => u16(div - 1).map_err(|_| Error::InvalidResolution),
+        _ => Err(Error::InvalidResolution),
+    }
+}
+
+/// Frequency of a period of `ticks` counter ticks at `tick_frequency`
+fn ticks_to_hertz(tick_frequency: Hertz, ticks: u32) -> Hertz {
+    Hertz(tick_frequency.0 / ticks.max(1))
+}
+
+macro_rules! hal {
+    ($($TIM:ident: ($tim:ident, $apbenr:ident, $apbrstr:ident, $bit:expr, $pclk:ident, $ppre:ident, $bits:ident), [$($CH:ident: ($ccmr:ident, $ccr:ident),)+],)+) => {
+        $(
+            impl<PINS> Capture<$TIM, PINS> {
+                /// Configures a TIM peripheral for input capture on the channels of `pins`
+                ///
+                /// The counter runs at `resolution`, which must not exceed the timer clock and
+                /// must be at least the timer clock divided by 65536, otherwise
+                /// `Error::InvalidResolution` is returned.
+                pub fn $tim<P, T>(
+                    tim: $TIM,
+                    pins: PINS,
+                    clocks: Clocks,
+                    resolution: T,
+                    config: Config,
+                ) -> Result<Self, Error>
+                where
+                    PINS: Pins<$TIM, P>,
+                    T: Into<Hertz>,
+                {
+                    let clk = Hertz(clocks.$pclk().0 * if clocks.$ppre() == 1 { 1 } else { 2 });
+                    let psc = prescaler(clk, resolution.into())?;
+
+                    unsafe {
+                        //NOTE(unsafe) this reference will only be used for atomic writes with no side effects
+                        let rcc = &(*RCC::ptr());
+                        // Enable and reset the timer peripheral, it's the same bit position for both registers
+                        bb::set(&rcc.$apbenr, $bit);
+                        bb::set(&rcc.$apbrstr, $bit);
+                        bb::clear(&rcc.$apbrstr, $bit);
+                    }
+
+                    let mut capture = Capture { tim, pins, clk };
+
+                    capture.tim.arr.write(|w| unsafe { w.bits(core::$bits::MAX as u32) });
+                    capture.set_prescaler(psc);
+
+                    let channels = [
+                        (Channel::C1, PINS::C1),
+                        (Channel::C2, PINS::C2),
+                        (Channel::C3, PINS::C3),
+                        (Channel::C4, PINS::C4),
+                    ];
+                    for &(channel, used) in channels.iter() {
+                        if used {
+                            capture.configure(channel, config)?;
+                            hal::Capture::enable(&mut capture, channel);
+                        }
+                    }
+
+                    capture.tim.cr1.modify(|_, w| w.cen().set_bit());
+
+                    Ok(capture)
+                }
+
+                /// Changes edge, filter and prescaler of `channel`, or returns
+                /// `Error::InvalidChannel` if the timer doesn't have `channel`
+                pub fn configure(&mut self, channel: Channel, config: Config) -> Result<(), Error> {
+                    let idx = channel as u8;
+                    let ccer_bits = 0b1111 << (4 * idx);
+                    let polarity = match config.edge {
+                        Edge::Rising => 0b0000,
+                        Edge::Falling => 0b0010,
+                        Edge::Both => 0b1010,
+                    } << (4 * idx);
+
+                    match channel {
+                        $(
+                            Channel::$CH => {}
+                        )+
+                        #[allow(unreachable_patterns)]
+                        _ => return Err(Error::InvalidChannel),
+                    }
+
+                    // CCxS can only be written while the channel is disabled
+                    let ccer = self.tim.ccer.read().bits();
+                    self.tim.ccer.write(|w| unsafe { w.bits(ccer & !ccer_bits) });
+
+                    // CCxS = 01: ICx is mapped on TIx
+                    let shift = 8 * (idx % 2);
+                    let ccmr = (0b01 | (config.prescaler as u32) << 2 | u32::from(config.filter & 0xf) << 4) << shift;
+                    match channel {
+                        $(
+                            Channel::$CH => self.tim.$ccmr().modify(|r, w| unsafe {
+                                w.bits((r.bits() & !(0xff << shift)) | ccmr)
+                            }),
+                        )+
+                        #[allow(unreachable_patterns)]
+                        _ => unreachable!(),
+                    }
+
+                    self.tim.ccer.write(|w| unsafe {
+                        w.bits((ccer & !(ccer_bits & !(1 << (4 * idx)))) | polarity)
+                    });
+                    Ok(())
+                }
+
+                fn set_prescaler(&mut self, psc: u16) {
+                    self.tim.psc.write(|w| w.psc().bits(psc));
+
+                    // Trigger update event to load the prescaler, without setting UIF
+                    self.tim.cr1.modify(|_, w| w.urs().set_bit());
+                    self.tim.egr.write(|w| w.ug().set_bit());
+                    self.tim.cr1.modify(|_, w| w.urs().clear_bit());
+                }
+
+                /// Current counter value
+                pub fn counter(&self) -> u32 {
+                    self.tim.cnt.read().bits()
+                }
+
+                /// Ticks from capture `from` to capture `to`, taking one counter wrap around into
+                /// account
+                pub fn elapsed(&self, from: u32, to: u32) -> u32 {
+                    (to as $bits).wrapping_sub(from as $bits) as u32
+                }
+
+                /// Frequency of a signal with a period of `ticks`, e.g. the `elapsed` time between
+                /// two captures of the same edge
+                pub fn frequency(&self, ticks: u32) -> Hertz {
+                    ticks_to_hertz(hal::Capture::get_resolution(self), ticks)
+                }
+
+                /// Starts listening for an `event`
+                pub fn listen(&mut self, event: Event) {
+                    //NOTE(unsafe) atomic write with no side effects
+                    unsafe { bb::set(&self.tim.dier, Self::event_bit(event)) }
+                }
+
+                /// Stops listening for an `event`
+                pub fn unlisten(&mut self, event: Event) {
+                    //NOTE(unsafe) atomic write with no side effects
+                    unsafe { bb::clear(&self.tim.dier, Self::event_bit(event)) }
+                }
+
+                /// Clears the interrupt flag of `event`. Capture flags are also cleared by
+                /// reading the capture.
+                pub fn clear_interrupt(&mut self, event: Event) {
+                    let bit = Self::event_bit(event);
+                    self.tim.sr.write(|w| unsafe { w.bits(!(1 << bit)) });
+                }
+
+                fn event_bit(event: Event) -> u8 {
+                    match event {
+                        Event::Capture(channel) => channel as u8 + 1,
+                        Event::Overflow => 0,
+                    }
+                }
+
+                /// Stops the counter and releases the TIM peripheral and pins
+                pub fn release(self) -> ($TIM, PINS) {
+                    self.tim.cr1.modify(|_, w| w.cen().clear_bit());
+                    (self.tim, self.pins)
+                }
+            }
+
+            impl<PINS> hal::Capture for Capture<$TIM, PINS> {
+                type Error = Error;
+                type Channel = Channel;
+                type Time = Hertz;
+                type Capture = u32;
+
+                /// Returns `Error::InvalidChannel` if the timer doesn't have `channel`
+                fn capture(&mut self, channel: Channel) -> nb::Result<u32, Error> {
+                    let idx = channel as u8;
+                    let sr = self.tim.sr.read().bits();
+                    let pending = sr & (SR_CC1IF << idx) != 0;
+
+                    // Reading the capture clears CCxIF
+                    let value = match channel {
+                        $(
+                            Channel::$CH if pending => self.tim.$ccr.read().bits(),
+                            Channel::$CH => return Err(nb::Error::WouldBlock),
+                        )+
+                        #[allow(unreachable_patterns)]
+                        _ => return Err(nb::Error::Other(Error::InvalidChannel)),
+                    };
+
+                    if sr & (SR_CC1OF << idx) != 0 {
+                        self.tim.sr.write(|w| unsafe { w.bits(!(SR_CC1OF << idx)) });
+                        Err(nb::Error::Other(Error::Overcapture))
+                    } else {
+                        Ok(value)
+                    }
+                }
+
+                fn disable(&mut self, channel: Channel) {
+                    //NOTE(unsafe) atomic write with no side effects
+                    unsafe { bb::clear(&self.tim.ccer, 4 * channel as u8) }
+                }
+
+                fn enable(&mut self, channel: Channel) {
+                    //NOTE(unsafe) atomic write with no side effects
+                    unsafe { bb::set(&self.tim.ccer, 4 * channel as u8) }
+                }
+
+                fn get_resolution(&self) -> Hertz {
+                    Hertz(self.clk.0 / (u32::from(self.tim.psc.read().psc().bits()) + 1))
+                }
+
+                /// `set_resolution` can't report an error, so the resolution is clamped to the
+                /// range from the timer clock divided by 65536 to the timer clock
+                fn set_resolution<R>(&mut self, resolution: R)
+                where
+                    R: Into<Hertz>,
+                {
+                    let resolution = resolution.into();
+                    let psc = match prescaler(self.clk, resolution) {
+                        Ok(psc) => psc,
+                        Err(_) if resolution.0 > self.clk.0 => 0,
+                        Err(_) => core::u16::MAX,
+                    };
+                    self.set_prescaler(psc);
+                }
+            }
+        )+
+    }
+}
+
+macro_rules! pwm_input {
+    ($($TIM:ident: ($tim:ident, $apbenr:ident, $apbrstr:ident, $bit:expr, $pclk:ident, $ppre:ident, $bits:ident),)+) => {
+        $(
+            impl<PIN> PwmInput<$TIM, PIN> {
+                /// Configures a TIM peripheral to measure the PWM signal on `pin`
+                ///
+                /// The counter runs at `resolution`, so the longest measurable period is the
+                /// counter range at that frequency. `filter` is the input filter (IC1F, 0 to 15).
+                /// Returns `Error::InvalidResolution` if `resolution` exceeds the timer clock or
+                /// is below the timer clock divided by 65536.
+                pub fn $tim<T>(
+                    tim: $TIM,
+                    pin: PIN,
+                    clocks: Clocks,
+                    resolution: T,
+                    filter: u8,
+                ) -> Result<Self, Error>
+                where
+                    PIN: PinC1<$TIM>,
+                    T: Into<Hertz>,
+                {
+                    let clk = Hertz(clocks.$pclk().0 * if clocks.$ppre() == 1 { 1 } else { 2 });
+                    let psc = prescaler(clk, resolution.into())?;
+
+                    unsafe {
+                        //NOTE(unsafe) this reference will only be used for atomic writes with no side effects
+                        let rcc = &(*RCC::ptr());
+                        // Enable and reset the timer peripheral, it's the same bit position for both registers
+                        bb::set(&rcc.$apbenr, $bit);
+                        bb::set(&rcc.$apbrstr, $bit);
+                        bb::clear(&rcc.$apbrstr, $bit);
+                    }
+
+                    // IC1 on TI1 (CC1S = 01) with the input filter, IC2 on TI1 as well (CC2S = 10)
+                    tim.ccmr1_output().write(|w| unsafe {
+                        w.bits(0b01 | u32::from(filter & 0xf) << 4 | 0b10 << 8)
+                    });
+                    // IC1 captures the rising edge, IC2 the falling edge
+                    tim.ccer.write(|w| unsafe { w.bits(1 << 0 | 1 << 4 | 1 << 5) });
+                    // Reset the counter on TI1FP1 (TS = 101, SMS = 100)
+                    tim.smcr.write(|w| unsafe { w.bits(0b101 << 4 | 0b100) });
+
+                    tim.arr.write(|w| unsafe { w.bits(core::$bits::MAX as u32) });
+                    tim.psc.write(|w| w.psc().bits(psc));
+
+                    // Only counter overflows set UIF, not the resets by the slave mode controller
+                    tim.cr1.modify(|_, w| w.urs().set_bit());
+                    tim.egr.write(|w| w.ug().set_bit());
+                    tim.cr1.modify(|_, w| w.cen().set_bit());
+
+                    Ok(PwmInput { tim, pin, clk })
+                }
+
+                /// Frequency of the counter
+                pub fn tick_frequency(&self) -> Hertz {
+                    Hertz(self.clk.0 / (u32::from(self.tim.psc.read().psc().bits()) + 1))
+                }
+
+                /// Period and high time of the last complete cycle in counter ticks
+                ///
+                /// Returns `None` before the first edge and when the input did not toggle for a
+                /// full counter range, e.g. because the signal stopped or is constantly high or
+                /// low. The first result after either case covers the time since the counter
+                /// started instead of a full period.
+                pub fn measurement(&self) -> Option<(u32, u32)> {
+                    let sr = self.tim.sr.read().bits();
+                    if sr & SR_CC1IF != 0 {
+                        // A new period started, so the last overflow is history
+                        self.tim.sr.write(|w| unsafe { w.bits(!SR_UIF) });
+                    } else if sr & SR_UIF != 0 {
+                        return None;
+                    }
+
+                    // Reading CCR1 clears CC1IF
+                    let period = self.tim.ccr1.read().bits();
+                    let high = self.tim.ccr2.read().bits();
+                    if period == 0 {
+                        None
+                    } else {
+                        Some((period, high))
+                    }
+                }
+
+                /// Frequency of the input signal
+                pub fn frequency(&self) -> Option<Hertz> {
+                    self.measurement()
+                        .map(|(period, _)| ticks_to_hertz(self.tick_frequency(), period))
+                }
+
+                /// Duty cycle of the input signal from 0.0 to 1.0
+                pub fn duty_cycle(&self) -> Option<f32> {
+                    self.measurement()
+                        .map(|(period, high)| high as f32 / period as f32)
+                }
+
+                /// Stops the counter and releases the TIM peripheral and pin
+                pub fn release(self) -> ($TIM, PIN) {
+                    self.tim.cr1.modify(|_, w| w.cen().clear_bit());
+                    (self.tim, self.pin)
+                }
+            }
+        )+
+    }
+}
+
+#[cfg(any(
+    feature = "stm32f401",
+    feature = "stm32f405",
+    feature = "stm32f407",
+    feature = "stm32f410",
+    feature = "stm32f411",
+    feature = "stm32f412",
+    feature = "stm32f413",
+    feature = "stm32f415",
+    feature = "stm32f417",
+    feature = "stm32f423",
+    feature = "stm32f427",
+    feature = "stm32f429",
+    feature = "stm32f437",
+    feature = "stm32f439",
+    feature = "stm32f446",
+    feature = "stm32f469",
+    feature = "stm32f479"
+))]
+hal! {
+    TIM1: (tim1, apb2enr, apb2rstr, 0, pclk2, ppre2, u16), [
+        C1: (ccmr1_output, ccr1),
+        C2: (ccmr1_output, ccr2),
+        C3: (ccmr2_output, ccr3),
+        C4: (ccmr2_output, ccr4),
+    ],
+    TIM5: (tim5, apb1enr, apb1rstr, 3, pclk1, ppre1, u32), [
+        C1: (ccmr1_output, ccr1),
+        C2: (ccmr1_output, ccr2),
+        C3: (ccmr2_output, ccr3),
+        C4: (ccmr2_output, ccr4),
+    ],
+    TIM9: (tim9, apb2enr, apb2rstr, 16, pclk2, ppre2, u16), [
+        C1: (ccmr1_output, ccr1),
+        C2: (ccmr1_output, ccr2),
+    ],
+    TIM11: (tim11, apb2enr, apb2rstr, 18, pclk2, ppre2, u16), [
+        C1: (ccmr1_output, ccr1),
+    ],
+}
+
+#[cfg(any(
+    feature = "stm32f401",
+    feature = "stm32f405",
+    feature = "stm32f407",
+    feature = "stm32f410",
+    feature = "stm32f411",
+    feature = "stm32f412",
+    feature = "stm32f413",
+    feature = "stm32f415",
+    feature = "stm32f417",
+    feature = "stm32f423",
+    feature = "stm32f427",
+    feature = "stm32f429",
+    feature = "stm32f437",
+    feature = "stm32f439",
+    feature = "stm32f446",
+    feature = "stm32f469",
+    feature = "stm32f479"
+))]
+pwm_input! {
+    TIM1: (tim1, apb2enr, apb2rstr, 0, pclk2, ppre2, u16),
+    TIM5: (tim5, apb1enr, apb1rstr, 3, pclk1, ppre1, u32),
+    TIM9: (tim9, apb2enr, apb2rstr, 16, pclk2, ppre2, u16),
+}
+
+#[cfg(any(
+    feature = "stm32f401",
+    feature = "stm32f405",
+    feature = "stm32f407",
+    feature = "stm32f411",
+    feature = "stm32f412",
+    feature = "stm32f413",
+    feature = "stm32f415",
+    feature = "stm32f417",
+    feature = "stm32f423",
+    feature = "stm32f427",
+    feature = "stm32f429",
+    feature = "stm32f437",
+    feature = "stm32f439",
+    feature = "stm32f446",
+    feature = "stm32f469",
+    feature = "stm32f479"
+))]
+hal! {
+    TIM2: (tim2, apb1enr, apb1rstr, 0, pclk1, ppre1, u32), [
+        C1: (ccmr1_output, ccr1),
+        C2: (ccmr1_output, ccr2),
+        C3: (ccmr2_output, ccr3),
+        C4: (ccmr2_output, ccr4),
+    ],
+    TIM3: (tim3, apb1enr, apb1rstr, 1, pclk1, ppre1, u16), [
+        C1: (ccmr1_output, ccr1),
+        C2: (ccmr1_output, ccr2),
+        C3: (ccmr2_output, ccr3),
+        C4: (ccmr2_output, ccr4),
+    ],
+    TIM4: (tim4, apb1enr, apb1rstr, 2, pclk1, ppre1, u16), [
+        C1: (ccmr1_output, ccr1),
+        C2: (ccmr1_output, ccr2),
+        C3: (ccmr2_output, ccr3),
+        C4: (ccmr2_output, ccr4),
+    ],
+    TIM10: (tim10, apb2enr, apb2rstr, 17, pclk2, ppre2, u16), [
+        C1: (ccmr1_output, ccr1),
+    ],
+}
+
+#[cfg(any(
+    feature = "stm32f401",
+    feature = "stm32f405",
+    feature = "stm32f407",
+    feature = "stm32f411",
+    feature = "stm32f412",
+    feature = "stm32f413",
+    feature = "stm32f415",
+    feature = "stm32f417",
+    feature = "stm32f423",
+    feature = "stm32f427",
+    feature = "stm32f429",
+    feature = "stm32f437",
+    feature = "stm32f439",
+    feature = "stm32f446",
+    feature = "stm32f469",
+    feature = "stm32f479"
+))]
+pwm_input! {
+    TIM2: (tim2, apb1enr, apb1rstr, 0, pclk1, ppre1, u32),
+    TIM3: (tim3, apb1enr, apb1rstr, 1, pclk1, ppre1, u16),
+    TIM4: (tim4, apb1enr, apb1rstr, 2, pclk1, ppre1, u16),
+}
+
+#[cfg(any(
+    feature = "stm32f405",
+    feature = "stm32f407",
+    feature = "stm32f412",
+    feature = "stm32f413",
+    feature = "stm32f415",
+    feature = "stm32f417",
+    feature = "stm32f423",
+    feature = "stm32f427",
+    feature = "stm32f429",
+    feature = "stm32f437",
+    feature = "stm32f439",
+    feature = "stm32f446",
+    feature = "stm32f469",
+    feature = "stm32f479"
+))]
+hal! {
+    TIM8: (tim8, apb2enr, apb2rstr, 1, pclk2, ppre2, u16), [
+        C1: (ccmr1_output, ccr1),
+        C2: (ccmr1_output, ccr2),
+        C3: (ccmr2_output, ccr3),
+        C4: (ccmr2_output, ccr4),
+    ],
+    TIM12: (tim12, apb1enr, apb1rstr, 6, pclk1, ppre1, u16), [
+        C1: (ccmr1_output, ccr1),
+        C2: (ccmr1_output, ccr2),
+    ],
+    TIM13: (tim13, apb1enr, apb1rstr, 7, pclk1, ppre1, u16), [
+        C1: (ccmr1_output, ccr1),
+    ],
+    TIM14: (tim14, apb1enr, apb1rstr, 8, pclk1, ppre1, u16), [
+        C1: (ccmr1_output, ccr1),
+    ],
+}
+
+#[cfg(any(
+    feature = "stm32f405",
+    feature = "stm32f407",
+    feature = "stm32f412",
+    feature = "stm32f413",
+    feature = "stm32f415",
+    feature = "stm32f417",
+    feature = "stm32f423",
+    feature = "stm32f427",
+    feature = "stm32f429",
+    feature = "stm32f437",
+    feature = "stm32f439",
+    feature = "stm32f446",
+    feature = "stm32f469",
+    feature = "stm32f479"
+))]
+pwm_input! {
+    TIM8: (tim8, apb2enr, apb2rstr, 1, pclk2, ppre2, u16),
+    TIM12: (tim12, apb1enr, apb1rstr, 6, pclk1, ppre1, u16),
+}
@@ -0,0 +1,166 @@
+//! Hardware CRC calculation unit
+//!
+//! The CRC unit only processes 32-bit words, most significant bit first, with the polynomial
+//! 0x04C11DB7 and an initial value of 0xFFFFFFFF (CRC-32/MPEG-2). [`Crc32`](struct.Crc32.html)
+//! bit-reverses the words written by its `feed_*` methods and the result, buffers bytes that
+//! don't fill a word and processes trailing bytes in software, so
+//! [`result`](struct.Crc32.html#method.result) matches the standard CRC-32 used by Ethernet,
+//! zlib and PNG.
+//!
+//! `Crc32` is also a memory to peripheral target for `dma::Transfer` on DMA2. The CRC unit has
+//! no DMA requests, so the stream runs in memory to memory mode and needs the FIFO enabled. The
+//! DMA writes the words unchanged, which yields the CRC-32/MPEG-2 value returned by
+//! [`raw_value`](struct.Crc32.html#method.raw_value) instead.
+//!
+//! ```no_run
+//! use stm32f4xx_hal::{crc::Crc32, stm32};
+//!
+//! let dp = stm32::Peripherals::take().unwrap();
+//! let mut crc = Crc32::new(dp.CRC);
+//!
+//! crc.feed_bytes(b"123456789");
+//! assert_eq!(crc.result(), 0xCBF4_3926);
+//! ```
+
+use core::hash::Hasher;
+
+use crate::bb;
+use crate::dma::traits::PeriAddress;
+use crate::stm32::{CRC, RCC};
+
+/// Reflected CRC-32 polynomial, used for trailing bytes
+const POLY_REFLECTED: u32 = 0xEDB8_8320;
+
+/// CRC unit computing the standard CRC-32
+pub struct Crc32 {
+    crc: CRC,
+    /// Bytes that don't fill a word yet
+    pending: [u8; 4],
+    pending_len: usize,
+}
+
+impl Crc32 {
+    /// Enables the CRC clock and resets the unit
+    pub fn new(crc: CRC) -> Self {
+        unsafe {
+            const EN_BIT: u8 = 12;
+            // NOTE(unsafe) this reference will only be used for atomic writes with no side effects.
+            let rcc = &(*RCC::ptr());
+
+            // Enable clock.
+            bb::set(&rcc.ahb1enr, EN_BIT);
+        }
+
+        let mut crc = Crc32 {
+            crc,
+            pending: [0; 4],
+            pending_len: 0,
+        };
+        crc.reset();
+        crc
+    }
+
+    /// Starts a new calculation
+    pub fn reset(&mut self) {
+        self.crc.cr.write(|w| unsafe { w.bits(1) });
+        self.pending_len = 0;
+    }
+
+    /// Feeds a word, processed as its four little-endian bytes
+    pub fn feed_u32(&mut self, word: u32) {
+        if self.pending_len == 0 {
+            self.feed_raw(word.reverse_bits());
+        } else {
+            self.feed_bytes(&word.to_le_bytes());
+        }
+    }
+
+    /// Feeds words, each processed as its four little-endian bytes
+    pub fn feed_words(&mut self, words: &[u32]) {
+        for &word in words {
+            self.feed_u32(word);
+        }
+    }
+
+    /// Feeds bytes
+    pub fn feed_bytes(&mut self, mut bytes: &[u8]) {
+        // Complete the pending word first
+        if self.pending_len != 0 {
+            let n = (4 - self.pending_len).min(bytes.len());
+            self.pending[self.pending_len..self.pending_len + n].copy_from_slice(&bytes[..n]);
+            self.pending_len += n;
+            bytes = &bytes[n..];
+
+            if self.pending_len < 4 {
+                return;
+            }
+            self.pending_len = 0;
+            self.feed_raw(u32::from_le_bytes(self.pending).reverse_bits());
+        }
+
+        let mut words = bytes.chunks_exact(4);
+        for word in &mut words {
+            let word = u32::from_le_bytes([word[0], word[1], word[2], word[3]]);
+            self.feed_raw(word.reverse_bits());
+        }
+
+        let rest = words.remainder();
+        self.pending[..rest.len()].copy_from_slice(rest);
+        self.pending_len = rest.len();
+    }
+
+    /// Returns the CRC-32 of the data fed since the last reset. Feeding can continue afterwards.
+    pub fn result(&self) -> u32 {
+        let mut crc = self.raw_value().reverse_bits();
+        for &byte in &self.pending[..self.pending_len] {
+            crc ^= u32::from(byte);
+            for _ in 0..8 {
+                crc = if crc & 1 != 0 {
+                    (crc >> 1) ^ POLY_REFLECTED
+                } else {
+                    crc >> 1
+                };
+            }
+        }
+        !crc
+    }
+
+    /// Writes a word to the data register as is, e.g. to continue a CRC-32/MPEG-2 calculation
+    /// started by DMA. This bypasses the byte buffer of the `feed_*` methods.
+    pub fn feed_raw(&mut self, word: u32) {
+        self.crc.dr.write(|w| unsafe { w.bits(word) });
+    }
+
+    /// Returns the contents of the data register, the CRC-32/MPEG-2 of the raw words
+    pub fn raw_value(&self) -> u32 {
+        self.crc.dr.read().bits()
+    }
+
+    /// Releases the CRC peripheral
+    pub fn release(self) -> CRC {
+        self.crc
+    }
+}
+
+impl Hasher for Crc32 {
+    fn write(&mut self, bytes: &[u8]) {
+        self.feed_bytes(bytes);
+    }
+
+    fn finish(&self) -> u64 {
+        u64::from(self.result())
+    }
+}
+
+/// DMA writes the words to the data register unchanged, so the result of a DMA transfer is the
+/// CRC-32/MPEG-2 returned by `raw_value`, not the standard CRC-32 returned by `result`.
+unsafe impl PeriAddress for Crc32 {
+    #[inline(always)]
+    fn address(&self) -> u32 {
+        &self.crc.dr as *const _ as u32
+    }
+
+    type MemSize = u32;
+
+    const REQUEST: bool = false;
+}
@@ -272,7 +272,7 @@ impl<T: RccEnable> StreamsTuple<T> {
 // The implementation does the heavy lifting of mapping to the right fields on the stream
 macro_rules! dma_stream {
     ($(($name:ident, $number:expr ,$ifcr:ident, $tcif:ident, $htif:ident, $teif:ident, $dmeif:ident,
-        $feif:ident, $isr:ident, $tcisr:ident, $htisr:ident)),+ $(,)*) => {
+        $feif:ident, $isr:ident, $tcisr:ident, $htisr:ident, $teisr:ident)),+ $(,)*) => {
         $(
             impl<I: Instance> Stream for $name<I> {
 
@@ -346,6 +346,13 @@ macro_rules! dma_stream {
                     dma.$isr.read().$htisr().bit_is_set()
                 }
 
+                #[inline(always)]
+                fn get_transfer_error_flag() -> bool {
+                    //NOTE(unsafe) Atomic read with no side effects
+                    let dma = unsafe { &*I::ptr() };
+                    dma.$isr.read().$teisr().bit_is_set()
+                }
+
                 #[inline(always)]
                 fn set_peripheral_address(&mut self, value: u32) {
                     //NOTE(unsafe) We only access the registers that belongs to the StreamX
@@ -598,14 +605,14 @@ macro_rules! dma_stream {
 }
 
 dma_stream!(
-    (Stream0, 0, lifcr, ctcif0, chtif0, cteif0, cdmeif0, cfeif0, lisr, tcif0, htif0),
-    (Stream1, 1, lifcr, ctcif1, chtif1, cteif1, cdmeif1, cfeif1, lisr, tcif1, htif1),
-    (Stream2, 2, lifcr, ctcif2, chtif2, cteif2, cdmeif2, cfeif2, lisr, tcif2, htif2),
-    (Stream3, 3, lifcr, ctcif3, chtif3, cteif3, cdmeif3, cfeif3, lisr, tcif3, htif3),
-    (Stream4, 4, hifcr, ctcif4, chtif4, cteif4, cdmeif4, cfeif4, hisr, tcif4, htif4),
-    (Stream5, 5, hifcr, ctcif5, chtif5, cteif5, cdmeif5, cfeif5, hisr, tcif5, htif5),
-    (Stream6, 6, hifcr, ctcif6, chtif6, cteif6, cdmeif6, cfeif6, hisr, tcif6, htif6),
-    (Stream7, 7, hifcr, ctcif7, chtif7, cteif7, cdmeif7, cfeif7, hisr, tcif7, htif7),
+    (Stream0, 0, lifcr, ctcif0, chtif0, cteif0, cdmeif0, cfeif0, lisr, tcif0, htif0, teif0),
+    (Stream1, 1, lifcr, ctcif1, chtif1, cteif1, cdmeif1, cfeif1, lisr, tcif1, htif1, teif1),
+    (Stream2, 2, lifcr, ctcif2, chtif2, cteif2, cdmeif2, cfeif2, lisr, tcif2, htif2, teif2),
+    (Stream3, 3, lifcr, ctcif3, chtif3, cteif3, cdmeif3, cfeif3, lisr, tcif3, htif3, teif3),
+    (Stream4, 4, hifcr, ctcif4, chtif4, cteif4, cdmeif4, cfeif4, hisr, tcif4, htif4, teif4),
+    (Stream5, 5, hifcr, ctcif5, chtif5, cteif5, cdmeif5, cfeif5, hisr, tcif5, htif5, teif5),
+    (Stream6, 6, hifcr, ctcif6, chtif6, cteif6, cdmeif6, cfeif6, hisr, tcif6, htif6, teif6),
+    (Stream7, 7, hifcr, ctcif7, chtif7, cteif7, cdmeif7, cfeif7, hisr, tcif7, htif7, teif7),
 );
 
 // Macro that defines a channel and it's conversion to u8
@@ -868,6 +875,21 @@ where
     BUF: WriteBuffer<Word = <PERIPHERAL as PeriAddress>::MemSize> + 'static,
     (STREAM, CHANNEL, PERIPHERAL, DIR): DMASet,
 {
+    /// Returns `true` for memory to peripheral transfers to a target without DMA requests, which
+    /// run in memory to memory mode with the roles of the address registers swapped.
+    fn is_unpaced() -> bool {
+        DIR::direction() == DmaDirection::MemoryToPeripheral && !PERIPHERAL::REQUEST
+    }
+
+    /// Sets the address of the (first) buffer.
+    fn set_buffer_address(&mut self, address: u32) {
+        if Self::is_unpaced() {
+            self.stream.set_peripheral_address(address);
+        } else {
+            self.stream.set_memory_address(address);
+        }
+    }
+
     /// Applies all fields in DmaConfig.
     fn apply_config(&mut self, config: config::DmaConfig) {
         let msize = mem::size_of::<<PERIPHERAL as PeriAddress>::MemSize>() / 2;
@@ -879,9 +901,16 @@ where
             self.stream.set_memory_size(msize as u8);
             self.stream.set_peripheral_size(msize as u8);
         }
-        self.stream.set_memory_increment(config.memory_increment);
-        self.stream
-            .set_peripheral_increment(config.peripheral_increment);
+        if Self::is_unpaced() {
+            self.stream
+                .set_memory_increment(config.peripheral_increment);
+            self.stream
+                .set_peripheral_increment(config.memory_increment);
+        } else {
+            self.stream.set_memory_increment(config.memory_increment);
+            self.stream
+                .set_peripheral_increment(config.peripheral_increment);
+        }
         self.stream
             .set_transfer_complete_interrupt_enable(config.transfer_complete_interrupt);
         self.stream
@@ -909,6 +938,8 @@ where
     /// * When the FIFO is disabled or double buffering is enabled in `DmaConfig` while initializing
     /// a memory to memory transfer.
     /// * When double buffering is enabled but the `double_buf` argument is `None`.
+    /// * When the FIFO is disabled or double buffering is enabled in `DmaConfig` while initializing
+    /// a memory to peripheral transfer to a target without DMA requests.
     pub fn init(
         mut stream: STREAM,
         peripheral: PERIPHERAL,
@@ -921,18 +952,27 @@ where
         // Set the channel
         stream.set_channel(CHANNEL::new());
 
-        // Set peripheral to memory mode
-        stream.set_direction(DIR::new());
-
         // NOTE(unsafe) We now own this buffer and we won't call any &mut methods on it until the
         // end of the DMA transfer
         let (buf_ptr, buf_len) = unsafe { memory.write_buffer() };
 
-        // Set the memory address
-        stream.set_memory_address(buf_ptr as u32);
-
         let is_mem2mem = DIR::direction() == DmaDirection::MemoryToMemory;
-        if is_mem2mem {
+        let unpaced = Self::is_unpaced();
+        if unpaced {
+            // Without DMA requests the stream has to run in memory to memory mode, which reads
+            // from the peripheral address and writes to the memory address
+            stream.set_direction(MemoryToMemory::<()>::new());
+            stream.set_peripheral_address(buf_ptr as u32);
+            stream.set_memory_address(peripheral.address());
+        } else {
+            // Set peripheral to memory mode
+            stream.set_direction(DIR::new());
+
+            // Set the memory address
+            stream.set_memory_address(buf_ptr as u32);
+        }
+
+        if is_mem2mem || unpaced {
             // Fifo must be enabled for memory to memory
             if !config.fifo_enable {
                 panic!("Fifo disabled.");
@@ -1077,7 +1117,7 @@ where
         // NOTE(unsafe) We now own this buffer and we won't call any &mut methods on it until
         // the end of the DMA transfer
         let (buf_ptr, buf_len) = unsafe { new_buf.write_buffer() };
-        self.stream.set_memory_address(buf_ptr as u32);
+        self.set_buffer_address(buf_ptr as u32);
         self.stream.set_number_of_transfers(buf_len as u16);
         let old_buf = self.buf.replace(new_buf);
 
@@ -1255,7 +1295,7 @@ where
         let mut new_buf = r.0;
 
         let (buf_ptr, buf_len) = new_buf.write_buffer();
-        self.stream.set_memory_address(buf_ptr as u32);
+        self.set_buffer_address(buf_ptr as u32);
         self.stream.set_number_of_transfers(buf_len as u16);
         self.buf.replace(new_buf);
 
@@ -1,11 +1,14 @@
 use super::*;
 use crate::{
-    bb,
+    bb, crc,
     pac::{self, DMA1, DMA2, RCC},
     serial::{Rx, Tx},
 };
 use core::ops::Deref;
 
+// Short names keep the SPI entries of the maps on one line
+use crate::spi::{Rx as SRx, Tx as STx};
+
 pub(crate) mod sealed {
     /// Converts value to bits for setting a register value.
     pub trait Bits<T> {
@@ -45,6 +48,9 @@ pub trait Stream: Sealed {
     /// Get half transfer flag.
     fn get_half_transfer_flag() -> bool;
 
+    /// Get transfer error flag.
+    fn get_transfer_error_flag() -> bool;
+
     /// Set the peripheral address (par) for the DMA stream.
     fn set_peripheral_address(&mut self, value: u32);
 
@@ -190,6 +196,10 @@ pub unsafe trait PeriAddress {
 
     /// Returns the address to be used by the DMA stream.
     fn address(&self) -> u32;
+
+    /// `false` for targets that don't generate DMA requests, like the CRC unit. Memory to
+    /// peripheral transfers to them run as memory to memory transfers, which only DMA2 supports.
+    const REQUEST: bool = true;
 }
 
 // Convenience macro for implementing addresses on peripherals
@@ -368,10 +378,14 @@ dma_map!(
     (Stream7<DMA1>, Channel5, CCR3<pac::TIM3>, MemoryToPeripheral), //TIM3_CH3
     (Stream7<DMA1>, Channel5, CCR3<pac::TIM3>, PeripheralToMemory), //TIM3_CH3
     (Stream0<DMA1>, Channel0, pac::SPI3, PeripheralToMemory),       //SPI3_RX
+    (Stream0<DMA1>, Channel0, SRx<pac::SPI3>, PeripheralToMemory),  //SPI3_RX
     (Stream2<DMA1>, Channel0, pac::SPI3, PeripheralToMemory),       //SPI3_RX
+    (Stream2<DMA1>, Channel0, SRx<pac::SPI3>, PeripheralToMemory),  //SPI3_RX
     (Stream4<DMA1>, Channel3, pac::I2C3, MemoryToPeripheral),       //I2C3_TX
     (Stream5<DMA1>, Channel0, pac::SPI3, MemoryToPeripheral),       //SPI3_TX
+    (Stream5<DMA1>, Channel0, STx<pac::SPI3>, MemoryToPeripheral),  //SPI3_TX
     (Stream7<DMA1>, Channel0, pac::SPI3, MemoryToPeripheral),       //SPI3_TX
+    (Stream7<DMA1>, Channel0, STx<pac::SPI3>, MemoryToPeripheral),  //SPI3_TX
 );
 
 #[cfg(any(
@@ -494,8 +508,10 @@ dma_map!(
     (Stream0<DMA1>, Channel1, pac::I2C1, PeripheralToMemory),       //I2C1_RX
     (Stream2<DMA1>, Channel7, pac::I2C2, PeripheralToMemory),       //I2C2_RX
     (Stream3<DMA1>, Channel0, pac::SPI2, PeripheralToMemory),       //SPI2_RX
+    (Stream3<DMA1>, Channel0, SRx<pac::SPI2>, PeripheralToMemory),  //SPI2_RX
     (Stream3<DMA1>, Channel7, pac::I2C2, PeripheralToMemory),       //I2C2_RX
     (Stream4<DMA1>, Channel0, pac::SPI2, MemoryToPeripheral),       //SPI2_TX
+    (Stream4<DMA1>, Channel0, STx<pac::SPI2>, MemoryToPeripheral),  //SPI2_TX
     (Stream5<DMA1>, Channel1, pac::I2C1, PeripheralToMemory),       //I2C1_RX
     (Stream5<DMA1>, Channel4, pac::USART2, PeripheralToMemory),     //USART2_RX
     (Stream5<DMA1>, Channel4, Rx<pac::USART2>, PeripheralToMemory), //USART2_RX
@@ -504,9 +520,11 @@ dma_map!(
     (Stream7<DMA1>, Channel7, pac::I2C2, MemoryToPeripheral),       //I2C2_TX
     (Stream0<DMA2>, Channel0, pac::ADC1, PeripheralToMemory),       //ADC1
     (Stream0<DMA2>, Channel3, pac::SPI1, PeripheralToMemory),       //SPI1_RX
+    (Stream0<DMA2>, Channel3, SRx<pac::SPI1>, PeripheralToMemory),  //SPI1_RX
     (Stream1<DMA2>, Channel5, pac::USART6, PeripheralToMemory),     //USART6_RX
     (Stream1<DMA2>, Channel5, Rx<pac::USART6>, PeripheralToMemory), //USART6_RX
     (Stream2<DMA2>, Channel3, pac::SPI1, PeripheralToMemory),       //SPI1_RX
+    (Stream2<DMA2>, Channel3, SRx<pac::SPI1>, PeripheralToMemory),  //SPI1_RX
     (Stream2<DMA2>, Channel4, pac::USART1, PeripheralToMemory),     //USART1_RX
     (Stream2<DMA2>, Channel4, Rx<pac::USART1>, PeripheralToMemory), //USART1_RX
     (Stream2<DMA2>, Channel5, pac::USART6, PeripheralToMemory),     //USART6_RX
@@ -664,6 +682,14 @@ dma_map!(
         MemoryToMemory<u32>,
         MemoryToMemory<u32>
     ),
+    (Stream0<DMA2>, Channel0, crc::Crc32, MemoryToPeripheral), //CRC
+    (Stream1<DMA2>, Channel0, crc::Crc32, MemoryToPeripheral), //CRC
+    (Stream2<DMA2>, Channel0, crc::Crc32, MemoryToPeripheral), //CRC
+    (Stream3<DMA2>, Channel0, crc::Crc32, MemoryToPeripheral), //CRC
+    (Stream4<DMA2>, Channel0, crc::Crc32, MemoryToPeripheral), //CRC
+    (Stream5<DMA2>, Channel0, crc::Crc32, MemoryToPeripheral), //CRC
+    (Stream6<DMA2>, Channel0, crc::Crc32, MemoryToPeripheral), //CRC
+    (Stream7<DMA2>, Channel0, crc::Crc32, MemoryToPeripheral), //CRC
 );
 
 #[cfg(any(
@@ -761,7 +787,9 @@ dma_map!(
     (Stream6<DMA1>, Channel1, pac::I2C1, MemoryToPeripheral), //I2C1_TX
     (Stream7<DMA1>, Channel1, pac::I2C1, MemoryToPeripheral), //I2C1_TX
     (Stream3<DMA2>, Channel3, pac::SPI1, MemoryToPeripheral), //SPI1_TX
+    (Stream3<DMA2>, Channel3, STx<pac::SPI1>, MemoryToPeripheral), //SPI1_TX
     (Stream5<DMA2>, Channel3, pac::SPI1, MemoryToPeripheral), //SPI1_TX
+    (Stream5<DMA2>, Channel3, STx<pac::SPI1>, MemoryToPeripheral), //SPI1_TX
 );
 
 #[cfg(any(
@@ -780,9 +808,13 @@ dma_map!(
 ))]
 dma_map!(
     (Stream0<DMA2>, Channel4, pac::SPI4, PeripheralToMemory), //SPI4_RX
+    (Stream0<DMA2>, Channel4, SRx<pac::SPI4>, PeripheralToMemory), //SPI4_RX
     (Stream1<DMA2>, Channel4, pac::SPI4, MemoryToPeripheral), //SPI4_TX
+    (Stream1<DMA2>, Channel4, STx<pac::SPI4>, MemoryToPeripheral), //SPI4_TX
     (Stream3<DMA2>, Channel5, pac::SPI4, PeripheralToMemory), //SPI4_RX:DMA_CHANNEL_5
+    (Stream3<DMA2>, Channel5, SRx<pac::SPI4>, PeripheralToMemory), //SPI4_RX:DMA_CHANNEL_5
     (Stream4<DMA2>, Channel5, pac::SPI4, MemoryToPeripheral), //SPI4_TX:DMA_CHANNEL_5
+    (Stream4<DMA2>, Channel5, STx<pac::SPI4>, MemoryToPeripheral), //SPI4_TX:DMA_CHANNEL_5
 );
 
 #[cfg(any(
@@ -1125,9 +1157,13 @@ dma_map!(
     (Stream7<DMA1>, Channel1, pac::I2C1, MemoryToPeripheral), //I2C1_TX:DMA_CHANNEL_1
     (Stream7<DMA1>, Channel6, pac::USART2, PeripheralToMemory), //USART2_RX:DMA_CHANNEL_6
     (Stream2<DMA2>, Channel2, pac::SPI1, MemoryToPeripheral), //SPI1_TX
+    (Stream2<DMA2>, Channel2, STx<pac::SPI1>, MemoryToPeripheral), //SPI1_TX
     (Stream3<DMA2>, Channel3, pac::SPI1, MemoryToPeripheral), //SPI1_TX:DMA_CHANNEL_3
+    (Stream3<DMA2>, Channel3, STx<pac::SPI1>, MemoryToPeripheral), //SPI1_TX:DMA_CHANNEL_3
     (Stream5<DMA2>, Channel3, pac::SPI1, MemoryToPeripheral), //SPI1_TX:DMA_CHANNEL_3
+    (Stream5<DMA2>, Channel3, STx<pac::SPI1>, MemoryToPeripheral), //SPI1_TX:DMA_CHANNEL_3
     (Stream5<DMA2>, Channel5, pac::SPI5, MemoryToPeripheral), //SPI5_TX:DMA_CHANNEL_5
+    (Stream5<DMA2>, Channel5, STx<pac::SPI5>, MemoryToPeripheral), //SPI5_TX:DMA_CHANNEL_5
 );
 
 #[cfg(any(
@@ -1145,9 +1181,13 @@ dma_map!(
 ))]
 dma_map!(
     (Stream3<DMA2>, Channel2, pac::SPI5, PeripheralToMemory), //SPI5_RX
+    (Stream3<DMA2>, Channel2, SRx<pac::SPI5>, PeripheralToMemory), //SPI5_RX
     (Stream4<DMA2>, Channel2, pac::SPI5, MemoryToPeripheral), //SPI5_TX
+    (Stream4<DMA2>, Channel2, STx<pac::SPI5>, MemoryToPeripheral), //SPI5_TX
     (Stream5<DMA2>, Channel7, pac::SPI5, PeripheralToMemory), //SPI5_RX:DMA_CHANNEL_7
+    (Stream5<DMA2>, Channel7, SRx<pac::SPI5>, PeripheralToMemory), //SPI5_RX:DMA_CHANNEL_7
     (Stream6<DMA2>, Channel7, pac::SPI5, MemoryToPeripheral), //SPI5_TX:DMA_CHANNEL_7
+    (Stream6<DMA2>, Channel7, STx<pac::SPI5>, MemoryToPeripheral), //SPI5_TX:DMA_CHANNEL_7
 );
 
 #[cfg(any(
@@ -1171,7 +1211,10 @@ address!((pac::SPI5, dr, u8),);
     feature = "stm32f413",
     feature = "stm32f423",
 ))]
-dma_map!((Stream4<DMA2>, Channel4, pac::SPI4, PeripheralToMemory),); //SPI4_RX);
+dma_map!(
+    (Stream4<DMA2>, Channel4, pac::SPI4, PeripheralToMemory), //SPI4_RX
+    (Stream4<DMA2>, Channel4, SRx<pac::SPI4>, PeripheralToMemory), //SPI4_RX
+);
 
 /* TODO: DFSDM support
 #[cfg(feature = "stm32f412")]
@@ -1385,7 +1428,9 @@ address!(
 ))]
 dma_map!(
     (Stream5<DMA2>, Channel1, pac::SPI6, MemoryToPeripheral), //SPI6_TX
+    (Stream5<DMA2>, Channel1, STx<pac::SPI6>, MemoryToPeripheral), //SPI6_TX
     (Stream6<DMA2>, Channel1, pac::SPI6, PeripheralToMemory), //SPI6_RX
+    (Stream6<DMA2>, Channel1, SRx<pac::SPI6>, PeripheralToMemory), //SPI6_RX
 );
 
 #[cfg(any(
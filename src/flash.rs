@@ -0,0 +1,486 @@
+//! Internal flash memory programming
+//!
+//! The flash is divided into sectors of 16, 64 and 128 KiB. Sectors are the smallest unit
+//! that can be erased; programming can only change bits from 1 to 0.
+//!
+//! On the STM32F42x/F43x and F469/F479 with 2 MiB (or 1 MiB with the DB1M option bit set)
+//! the flash is split into two banks with the same layout. Sectors of the second bank are
+//! numbered from 12, independent of the size of the first bank.
+
+use core::{ptr, slice};
+
+use crate::signature::FlashSize;
+use crate::stm32::FLASH;
+
+/// Start address of the main flash memory
+pub const FLASH_START: usize = 0x0800_0000;
+
+const KEY1: u32 = 0x4567_0123;
+const KEY2: u32 = 0xCDEF_89AB;
+
+// CR bits
+const CR_PG: u32 = 1 << 0;
+const CR_SER: u32 = 1 << 1;
+const CR_STRT: u32 = 1 << 16;
+const CR_LOCK: u32 = 1 << 31;
+
+// SR bits
+const SR_EOP: u32 = 1 << 0;
+const SR_OPERR: u32 = 1 << 1;
+const SR_WRPERR: u32 = 1 << 4;
+const SR_PGAERR: u32 = 1 << 5;
+const SR_PGPERR: u32 = 1 << 6;
+const SR_PGSERR: u32 = 1 << 7;
+const SR_BSY: u32 = 1 << 16;
+const SR_ERRORS: u32 = SR_OPERR | SR_WRPERR | SR_PGAERR | SR_PGPERR | SR_PGSERR;
+
+/// Flash erase/program error
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Error {
+    /// Programming sequence error (PGSERR)
+    ProgrammingSequence,
+    /// Programming parallelism error (PGPERR)
+    ProgrammingParallelism,
+    /// Programming alignment error (PGAERR)
+    ProgrammingAlignment,
+    /// Write protection error (WRPERR)
+    WriteProtection,
+    /// Operation error (OPERR)
+    Operation,
+    /// The address range lies outside of the flash memory
+    OutOfBounds,
+    /// The erase range does not start and end on `NorFlash::ERASE_SIZE` boundaries
+    EraseAlignment,
+    /// The sector does not exist on this device
+    InvalidSector,
+    /// The flash stayed locked after the unlock sequence, which happens after a wrong unlock
+    /// sequence until the next reset
+    Locked,
+    #[doc(hidden)]
+    _Extensible,
+}
+
+impl Error {
+    fn read(flash: &FLASH) -> Option<Self> {
+        let sr = flash.sr.read().bits();
+        if sr & SR_PGSERR != 0 {
+            Some(Error::ProgrammingSequence)
+        } else if sr & SR_PGPERR != 0 {
+            Some(Error::ProgrammingParallelism)
+        } else if sr & SR_PGAERR != 0 {
+            Some(Error::ProgrammingAlignment)
+        } else if sr & SR_WRPERR != 0 {
+            Some(Error::WriteProtection)
+        } else if sr & SR_OPERR != 0 {
+            Some(Error::Operation)
+        } else {
+            None
+        }
+    }
+}
+
+/// Supply voltage range, which limits the number of bits programmed in parallel
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum VoltageRange {
+    /// 1.8 V to 2.1 V, byte parallelism
+    Range1,
+    /// 2.1 V to 2.7 V, half-word parallelism
+    Range2,
+    /// 2.7 V to 3.6 V, word parallelism
+    Range3,
+    /// 2.7 V to 3.6 V with external Vpp, double word parallelism
+    ExternalVpp,
+}
+
+impl VoltageRange {
+    /// PSIZE field value
+    fn psize(self) -> u32 {
+        match self {
+            VoltageRange::Range1 => 0b00,
+            VoltageRange::Range2 => 0b01,
+            VoltageRange::Range3 => 0b10,
+            VoltageRange::ExternalVpp => 0b11,
+        }
+    }
+
+    /// Number of bytes programmed at once
+    fn width(self) -> usize {
+        1 << self.psize()
+    }
+}
+
+/// A flash sector
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FlashSector {
+    /// Sector number
+    pub number: u8,
+    /// Offset from the start of the flash memory
+    pub offset: usize,
+    /// Size in bytes
+    pub size: usize,
+}
+
+impl FlashSector {
+    /// Returns `true` if `offset` lies within the sector
+    pub fn contains(&self, offset: usize) -> bool {
+        self.offset <= offset && offset < self.offset + self.size
+    }
+}
+
+/// Iterator over the sectors of one flash bank
+pub struct FlashSectorIterator {
+    index: u8,
+    start_sector: u8,
+    start_offset: usize,
+    end_offset: usize,
+}
+
+impl FlashSectorIterator {
+    fn new(start_sector: u8, start_offset: usize, end_offset: usize) -> Self {
+        FlashSectorIterator {
+            index: 0,
+            start_sector,
+            start_offset,
+            end_offset,
+        }
+    }
+}
+
+impl Iterator for FlashSectorIterator {
+    type Item = FlashSector;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.start_offset >= self.end_offset {
+            return None;
+        }
+
+        // Each bank starts with four 16 KiB sectors, one 64 KiB sector and 128 KiB sectors
+        let size = match self.index {
+            0..=3 => 0x4000,
+            4 => 0x1_0000,
+            _ => 0x2_0000,
+        };
+        let sector = FlashSector {
+            number: self.start_sector + self.index,
+            offset: self.start_offset,
+            size,
+        };
+
+        self.index += 1;
+        self.start_offset += size;
+        Some(sector)
+    }
+}
+
+/// Returns an iterator over all sectors of a flash memory of `len` bytes
+pub fn flash_sectors(
+    len: usize,
+    dual_bank: bool,
+) -> core::iter::Chain<FlashSectorIterator, FlashSectorIterator> {
+    if dual_bank {
+        // Second bank sectors are numbered from 12 on
+        FlashSectorIterator::new(0, 0, len / 2).chain(FlashSectorIterator::new(12, len / 2, len))
+    } else {
+        // Empty second iterator
+        FlashSectorIterator::new(0, 0, len).chain(FlashSectorIterator::new(0, 0, 0))
+    }
+}
+
+/// Flash methods implemented for `stm32::FLASH`
+#[allow(clippy::len_without_is_empty)]
+pub trait FlashExt {
+    /// Memory-mapped address
+    fn address(&self) -> usize;
+    /// Size in bytes
+    fn len(&self) -> usize;
+    /// Returns a read-only view of the flash memory
+    fn read(&self) -> &[u8] {
+        let ptr = self.address() as *const _;
+        unsafe { slice::from_raw_parts(ptr, self.len()) }
+    }
+    /// Returns `true` if the flash is split into two banks
+    fn dual_bank(&self) -> bool;
+    /// Returns the sector containing `offset`
+    fn sector(&self, offset: usize) -> Option<FlashSector> {
+        flash_sectors(self.len(), self.dual_bank()).find(|s| s.contains(offset))
+    }
+    /// Unlocks the flash for erasing and programming. It is locked again when the returned
+    /// value is dropped.
+    fn unlocked(&mut self, voltage: VoltageRange) -> Result<UnlockedFlash, Error>;
+}
+
+impl FlashExt for FLASH {
+    fn address(&self) -> usize {
+        FLASH_START
+    }
+
+    fn len(&self) -> usize {
+        FlashSize::get().bytes()
+    }
+
+    #[cfg(any(
+        feature = "stm32f427",
+        feature = "stm32f429",
+        feature = "stm32f437",
+        feature = "stm32f439",
+        feature = "stm32f469",
+        feature = "stm32f479"
+    ))]
+    fn dual_bank(&self) -> bool {
+        const OPTCR_DB1M: u32 = 1 << 30;
+
+        match self.len() / 1024 {
+            // 1 MiB devices can be configured as dual bank by the DB1M option bit
+            1024 => self.optcr.read().bits() & OPTCR_DB1M != 0,
+            2048 => true,
+            _ => false,
+        }
+    }
+
+    #[cfg(not(any(
+        feature = "stm32f427",
+        feature = "stm32f429",
+        feature = "stm32f437",
+        feature = "stm32f439",
+        feature = "stm32f469",
+        feature = "stm32f479"
+    )))]
+    fn dual_bank(&self) -> bool {
+        false
+    }
+
+    fn unlocked(&mut self, voltage: VoltageRange) -> Result<UnlockedFlash, Error> {
+        unlock(self)?;
+        Ok(UnlockedFlash {
+            flash: self,
+            voltage,
+        })
+    }
+}
+
+fn unlock(flash: &FLASH) -> Result<(), Error> {
+    if flash.cr.read().bits() & CR_LOCK != 0 {
+        flash.keyr.write(|w| unsafe { w.bits(KEY1) });
+        flash.keyr.write(|w| unsafe { w.bits(KEY2) });
+    }
+    if flash.cr.read().bits() & CR_LOCK != 0 {
+        Err(Error::Locked)
+    } else {
+        Ok(())
+    }
+}
+
+fn lock(flash: &FLASH) {
+    flash
+        .cr
+        .modify(|r, w| unsafe { w.bits(r.bits() | CR_LOCK) });
+}
+
+/// Result of `FlashExt::unlocked()`
+pub struct UnlockedFlash<'a> {
+    flash: &'a mut FLASH,
+    voltage: VoltageRange,
+}
+
+/// Automatically lock flash erase/program when leaving scope
+impl Drop for UnlockedFlash<'_> {
+    fn drop(&mut self) {
+        lock(&self.flash);
+    }
+}
+
+impl UnlockedFlash<'_> {
+    /// Erases a flash sector
+    ///
+    /// Refer to the reference manual to see which sector corresponds to which memory address,
+    /// or use `FlashExt::sector`. Returns `Error::InvalidSector` if the device doesn't have
+    /// `sector`.
+    pub fn erase(&mut self, sector: u8) -> Result<(), Error> {
+        if !flash_sectors(self.flash.len(), self.flash.dual_bank()).any(|s| s.number == sector) {
+            return Err(Error::InvalidSector);
+        }
+
+        // Sectors of the second bank are addressed with bit 4 of SNB set
+        #[cfg(any(
+            feature = "stm32f427",
+            feature = "stm32f429",
+            feature = "stm32f437",
+            feature = "stm32f439",
+            feature = "stm32f469",
+            feature = "stm32f479"
+        ))]
+        let snb = if sector < 12 { sector } else { sector + 4 };
+        #[cfg(not(any(
+            feature = "stm32f427",
+            feature = "stm32f429",
+            feature = "stm32f437",
+            feature = "stm32f439",
+            feature = "stm32f469",
+            feature = "stm32f479"
+        )))]
+        let snb = sector;
+
+        self.prepare();
+        let psize = self.voltage.psize();
+        self.flash
+            .cr
+            .write(|w| unsafe { w.bits(CR_SER | (u32::from(snb) << 3) | (psize << 8)) });
+        self.flash
+            .cr
+            .modify(|r, w| unsafe { w.bits(r.bits() | CR_STRT) });
+
+        let result = self.wait();
+        self.flash.cr.write(|w| unsafe { w.bits(0) });
+        result
+    }
+
+    /// Programs `data` starting at `offset` from the start of the flash memory.
+    ///
+    /// The voltage range parallelism is used for all aligned parts, unaligned bytes at the
+    /// start and end are programmed one by one.
+    pub fn program(&mut self, offset: usize, data: &[u8]) -> Result<(), Error> {
+        match offset.checked_add(data.len()) {
+            Some(end) if end <= self.flash.len() => {}
+            _ => return Err(Error::OutOfBounds),
+        }
+
+        let width = self.voltage.width();
+        let mut addr = self.flash.address() + offset;
+        let mut data = data;
+
+        self.prepare();
+        while !data.is_empty() {
+            let result = if addr % width == 0 && data.len() >= width {
+                let result = self.program_chunk(addr, &data[..width], self.voltage.psize());
+                addr += width;
+                data = &data[width..];
+                result
+            } else {
+                let result = self.program_chunk(addr, &data[..1], 0b00);
+                addr += 1;
+                data = &data[1..];
+                result
+            };
+
+            if result.is_err() {
+                self.flash.cr.write(|w| unsafe { w.bits(0) });
+                return result;
+            }
+        }
+        self.flash.cr.write(|w| unsafe { w.bits(0) });
+
+        Ok(())
+    }
+
+    fn program_chunk(&mut self, addr: usize, chunk: &[u8], psize: u32) -> Result<(), Error> {
+        self.flash
+            .cr
+            .write(|w| unsafe { w.bits(CR_PG | (psize << 8)) });
+
+        unsafe {
+            match chunk.len() {
+                1 => ptr::write_volatile(addr as *mut u8, chunk[0]),
+                2 => {
+                    ptr::write_volatile(addr as *mut u16, u16::from_le_bytes([chunk[0], chunk[1]]))
+                }
+                4 => ptr::write_volatile(
+                    addr as *mut u32,
+                    u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]),
+                ),
+                _ => {
+                    // Double word parallelism takes two consecutive word writes
+                    ptr::write_volatile(
+                        addr as *mut u32,
+                        u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]),
+                    );
+                    ptr::write_volatile(
+                        (addr + 4) as *mut u32,
+                        u32::from_le_bytes([chunk[4], chunk[5], chunk[6], chunk[7]]),
+                    );
+                }
+            }
+        }
+
+        self.wait()
+    }
+
+    /// Waits for a previous operation to finish and clears stale status flags
+    fn prepare(&mut self) {
+        while self.flash.sr.read().bits() & SR_BSY != 0 {}
+        self.flash
+            .sr
+            .write(|w| unsafe { w.bits(SR_EOP | SR_ERRORS) });
+    }
+
+    fn wait(&self) -> Result<(), Error> {
+        while self.flash.sr.read().bits() & SR_BSY != 0 {}
+
+        match Error::read(&self.flash) {
+            Some(error) => {
+                self.flash.sr.write(|w| unsafe { w.bits(SR_ERRORS) });
+                Err(error)
+            }
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(feature = "embedded-storage")]
+mod storage {
+    use super::{flash_sectors, Error, FlashExt, UnlockedFlash};
+    use embedded_storage::nor_flash::{NorFlash, ReadNorFlash};
+
+    impl ReadNorFlash for UnlockedFlash<'_> {
+        type Error = Error;
+
+        const READ_SIZE: usize = 1;
+
+        fn read(&mut self, offset: u32, bytes: &mut [u8]) -> Result<(), Self::Error> {
+            let offset = offset as usize;
+            let end = match offset.checked_add(bytes.len()) {
+                Some(end) if end <= self.flash.len() => end,
+                _ => return Err(Error::OutOfBounds),
+            };
+            bytes.copy_from_slice(&self.flash.read()[offset..end]);
+            Ok(())
+        }
+
+        fn capacity(&self) -> usize {
+            self.flash.len()
+        }
+    }
+
+    /// Each bank starts with four 16 KiB sectors and a 64 KiB sector, followed by 128 KiB
+    /// sectors, so the flash erases uniformly in 128 KiB blocks: the first block of a bank is
+    /// sectors 0 to 4 (12 to 16), every other block is one sector. `from` and `to` have to be
+    /// multiples of `ERASE_SIZE`, otherwise `Error::EraseAlignment` is returned. Use
+    /// `UnlockedFlash::erase` to erase the small sectors one by one.
+    impl NorFlash for UnlockedFlash<'_> {
+        const WRITE_SIZE: usize = 1;
+
+        const ERASE_SIZE: usize = 0x2_0000;
+
+        fn erase(&mut self, from: u32, to: u32) -> Result<(), Self::Error> {
+            let (from, to) = (from as usize, to as usize);
+            let len = self.flash.len();
+            if from > to || to > len {
+                return Err(Error::OutOfBounds);
+            }
+            if from % Self::ERASE_SIZE != 0 || to % Self::ERASE_SIZE != 0 {
+                return Err(Error::EraseAlignment);
+            }
+
+            let dual_bank = self.flash.dual_bank();
+            for sector in flash_sectors(len, dual_bank) {
+                if from <= sector.offset && sector.offset < to {
+                    UnlockedFlash::erase(self, sector.number)?;
+                }
+            }
+            Ok(())
+        }
+
+        fn write(&mut self, offset: u32, bytes: &[u8]) -> Result<(), Self::Error> {
+            self.program(offset as usize, bytes)
+        }
+    }
+}
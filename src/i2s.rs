@@ -0,0 +1,883 @@
+//! Inter-IC sound (I2S) on SPI2 and SPI3
+//!
+//! The I2S clock is generated by PLLI2S, which is set up by the first interface created in a
+//! master mode to get as close as possible to the configured sample rate. PLLI2S is shared by
+//! SPI2 and SPI3, so a second master interface keeps the PLLI2S settings and only adjusts its
+//! prescaler. [`I2s::sample_rate`](struct.I2s.html#method.sample_rate) returns the real rate.
+//!
+//! Data is transferred in half-words. 24 and 32-bit samples take two half-words per channel,
+//! most significant half-word first.
+//!
+//! On devices with the I2SxEXT extension blocks, full-duplex operation is done by pairing the
+//! main block with an [`I2sExt`](struct.I2sExt.html) that transfers in the opposite direction.
+
+use core::ops::Deref;
+use core::ptr;
+
+use crate::bb;
+use crate::dma::traits::{DMASet, PeriAddress};
+use crate::dma::{
+    Channel0, MemoryToPeripheral, PeripheralToMemory, Stream0, Stream2, Stream3, Stream4, Stream5,
+    Stream7,
+};
+use crate::gpio::gpioa::{PA15, PA4};
+use crate::gpio::gpiob::{PB12, PB9};
+use crate::gpio::gpioc::{PC6, PC7};
+use crate::gpio::{Alternate, AF5, AF6};
+use crate::rcc::Clocks;
+use crate::spi::{PinMosi, PinSck};
+use crate::stm32::{spi1, DMA1, RCC, SPI2, SPI3};
+use crate::time::Hertz;
+
+#[cfg(any(
+    feature = "stm32f401",
+    feature = "stm32f405",
+    feature = "stm32f407",
+    feature = "stm32f411",
+    feature = "stm32f415",
+    feature = "stm32f417",
+    feature = "stm32f427",
+    feature = "stm32f429",
+    feature = "stm32f437",
+    feature = "stm32f439",
+    feature = "stm32f469",
+    feature = "stm32f479"
+))]
+use crate::{
+    dma::{Channel2, Channel3},
+    gpio::gpiob::{PB14, PB4},
+    gpio::gpioc::{PC11, PC2},
+    gpio::AF7,
+    stm32::{I2S2EXT, I2S3EXT},
+};
+
+// I2SCFGR bits
+const I2SCFGR_CHLEN: u32 = 1 << 0;
+const I2SCFGR_CKPOL: u32 = 1 << 3;
+const I2SCFGR_PCMSYNC: u32 = 1 << 7;
+const I2SCFGR_I2SE: u32 = 1 << 10;
+const I2SCFGR_I2SMOD: u32 = 1 << 11;
+
+// I2SPR bits
+const I2SPR_ODD: u32 = 1 << 8;
+const I2SPR_MCKOE: u32 = 1 << 9;
+
+// CR2 bits
+const CR2_RXDMAEN: u32 = 1 << 0;
+const CR2_TXDMAEN: u32 = 1 << 1;
+
+pub mod config {
+    use crate::time::{Hertz, U32Ext};
+
+    /// Direction and clock role of the interface
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    pub enum Mode {
+        MasterTransmit,
+        MasterReceive,
+        SlaveTransmit,
+        SlaveReceive,
+    }
+
+    /// Audio protocol
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    pub enum Standard {
+        /// I2S Philips standard, data delayed by one clock after WS changes
+        Philips,
+        /// MSB justified (left justified)
+        Msb,
+        /// LSB justified (right justified)
+        Lsb,
+        /// PCM with a one clock frame synchronization pulse
+        PcmShortSync,
+        /// PCM with a 13 clock frame synchronization pulse
+        PcmLongSync,
+    }
+
+    /// Sample width and channel frame length
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    pub enum DataFormat {
+        /// 16-bit samples in 16-bit channel frames
+        Data16Channel16,
+        /// 16-bit samples in 32-bit channel frames
+        Data16Channel32,
+        /// 24-bit samples in 32-bit channel frames
+        Data24Channel32,
+        /// 32-bit samples in 32-bit channel frames
+        Data32Channel32,
+    }
+
+    /// Steady state of the serial clock
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    pub enum ClockPolarity {
+        IdleLow,
+        IdleHigh,
+    }
+
+    pub struct Config {
+        pub mode: Mode,
+        pub standard: Standard,
+        pub data_format: DataFormat,
+        pub clock_polarity: ClockPolarity,
+        /// Only used in master modes
+        pub sample_rate: Hertz,
+        /// Output the master clock (256 times the sample rate) on the MCK pin. Only used in
+        /// master modes.
+        pub master_clock: bool,
+    }
+
+    impl Config {
+        pub fn mode(mut self, mode: Mode) -> Self {
+            self.mode = mode;
+            self
+        }
+
+        pub fn standard(mut self, standard: Standard) -> Self {
+            self.standard = standard;
+            self
+        }
+
+        pub fn data_format(mut self, data_format: DataFormat) -> Self {
+            self.data_format = data_format;
+            self
+        }
+
+        pub fn clock_polarity(mut self, clock_polarity: ClockPolarity) -> Self {
+            self.clock_polarity = clock_polarity;
+            self
+        }
+
+        pub fn sample_rate<F>(mut self, sample_rate: F) -> Self
+        where
+            F: Into<Hertz>,
+        {
+            self.sample_rate = sample_rate.into();
+            self
+        }
+
+        pub fn master_clock(mut self, enabled: bool) -> Self {
+            self.master_clock = enabled;
+            self
+        }
+    }
+
+    #[derive(Debug)]
+    pub struct InvalidConfig;
+
+    impl Default for Config {
+        fn default() -> Config {
+            Config {
+                mode: Mode::MasterTransmit,
+                standard: Standard::Philips,
+                data_format: DataFormat::Data16Channel16,
+                clock_polarity: ClockPolarity::IdleLow,
+                sample_rate: 48.khz().into(),
+                master_clock: false,
+            }
+        }
+    }
+}
+
+use self::config::{ClockPolarity, Config, DataFormat, InvalidConfig, Mode, Standard};
+
+/// I2S error
+#[derive(Debug)]
+pub enum Error {
+    /// Received data was not read before the next sample arrived
+    Overrun,
+    /// No data was available when a slave transmitter had to send
+    Underrun,
+    /// WS changed at an unexpected time (slave mode)
+    Frame,
+    #[doc(hidden)]
+    _Extensible,
+}
+
+/// Interrupt event
+pub enum Event {
+    /// New data has been received
+    Rxne,
+    /// New data can be sent
+    Txe,
+    /// Overrun, underrun or frame error
+    Error,
+}
+
+/// Audio channel the current half-word belongs to
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Channel {
+    Left,
+    Right,
+}
+
+pub trait Pins<SPI> {}
+pub trait PinWs<SPI> {}
+pub trait PinMck<SPI> {}
+
+/// Serial clock, word select, master clock and serial data pins
+impl<SPI, WS, CK, MCK, SD> Pins<SPI> for (WS, CK, MCK, SD)
+where
+    WS: PinWs<SPI>,
+    CK: PinSck<SPI>,
+    MCK: PinMck<SPI>,
+    SD: PinMosi<SPI>,
+{
+}
+
+/// A filler type for when the master clock pin is unnecessary
+pub struct NoMck;
+
+/// Serial data pin of an I2SxEXT block
+pub trait PinExtSd<EXT> {}
+
+macro_rules! pins {
+    ($($SPIX:ty: WS: [$($WS:ty),*] MCK: [$($MCK:ty),*])+) => {
+        $(
+            $(
+                impl PinWs<$SPIX> for $WS {}
+            )*
+            $(
+                impl PinMck<$SPIX> for $MCK {}
+            )*
+        )+
+    }
+}
+
+pins! {
+    SPI2:
+        WS: [
+            PB9<Alternate<AF5>>,
+            PB12<Alternate<AF5>>
+        ]
+        MCK: [
+            NoMck,
+            PC6<Alternate<AF5>>
+        ]
+
+    SPI3:
+        WS: [
+            PA4<Alternate<AF6>>,
+            PA15<Alternate<AF6>>
+        ]
+        MCK: [
+            NoMck,
+            PC7<Alternate<AF6>>
+        ]
+}
+
+#[cfg(any(
+    feature = "stm32f401",
+    feature = "stm32f405",
+    feature = "stm32f407",
+    feature = "stm32f411",
+    feature = "stm32f415",
+    feature = "stm32f417",
+    feature = "stm32f427",
+    feature = "stm32f429",
+    feature = "stm32f437",
+    feature = "stm32f439",
+    feature = "stm32f469",
+    feature = "stm32f479"
+))]
+mod ext_pins {
+    use super::*;
+
+    impl PinExtSd<I2S2EXT> for PB14<Alternate<AF6>> {}
+    impl PinExtSd<I2S2EXT> for PC2<Alternate<AF6>> {}
+    impl PinExtSd<I2S3EXT> for PB4<Alternate<AF7>> {}
+    impl PinExtSd<I2S3EXT> for PC11<Alternate<AF5>> {}
+}
+
+/// Value of the I2SCFGR register for the given configuration, without the I2SE bit
+fn i2scfgr_bits(mode: Mode, config: &Config) -> u32 {
+    let i2scfg = match mode {
+        Mode::SlaveTransmit => 0b00,
+        Mode::SlaveReceive => 0b01,
+        Mode::MasterTransmit => 0b10,
+        Mode::MasterReceive => 0b11,
+    };
+    let (i2sstd, pcmsync) = match config.standard {
+        Standard::Philips => (0b00, 0),
+        Standard::Msb => (0b01, 0),
+        Standard::Lsb => (0b10, 0),
+        Standard::PcmShortSync => (0b11, 0),
+        Standard::PcmLongSync => (0b11, I2SCFGR_PCMSYNC),
+    };
+    let (datlen, chlen) = match config.data_format {
+        DataFormat::Data16Channel16 => (0b00, 0),
+        DataFormat::Data16Channel32 => (0b00, I2SCFGR_CHLEN),
+        DataFormat::Data24Channel32 => (0b01, I2SCFGR_CHLEN),
+        DataFormat::Data32Channel32 => (0b10, I2SCFGR_CHLEN),
+    };
+    let ckpol = match config.clock_polarity {
+        ClockPolarity::IdleLow => 0,
+        ClockPolarity::IdleHigh => I2SCFGR_CKPOL,
+    };
+
+    I2SCFGR_I2SMOD | (i2scfg << 8) | pcmsync | (i2sstd << 4) | ckpol | (datlen << 1) | chlen
+}
+
+/// Enables PLLI2S with an output that the I2S prescaler divides down to `target` as exactly as
+/// possible and returns the output frequency. If PLLI2S is already running its output is
+/// returned unchanged.
+fn plli2s_setup(clocks: Clocks, target: u32) -> Result<u32, InvalidConfig> {
+    // NOTE(unsafe) PLLI2S is only used by this module, the main PLL bits are left untouched
+    let rcc = unsafe { &*RCC::ptr() };
+
+    let vco_in = clocks.pll_in().0;
+    let plli2scfgr = rcc.plli2scfgr.read();
+    if rcc.cr.read().plli2son().bit_is_set() {
+        let plli2sn = u32::from(plli2scfgr.plli2sn().bits());
+        let plli2sr = u32::from(plli2scfgr.plli2sr().bits());
+        return Ok(vco_in * plli2sn / plli2sr);
+    }
+    if !(1_000_000..=2_000_000).contains(&vco_in) {
+        return Err(InvalidConfig);
+    }
+
+    // VCO output must be within 100 MHz and 432 MHz, PLLI2SN within 50 and 432, PLLI2SR
+    // within 2 and 7 and the I2S division factor within 4 and 511
+    let mut best: Option<(u32, u32, u64)> = None;
+    for plli2sr in 2..=7 {
+        for div in 4..=511 {
+            let freq = u64::from(target) * u64::from(div) * u64::from(plli2sr);
+            let plli2sn = (freq + u64::from(vco_in) / 2) / u64::from(vco_in);
+            let vco_out = u64::from(vco_in) * plli2sn;
+            if !(50..=432).contains(&plli2sn) || !(100_000_000..=432_000_000).contains(&vco_out) {
+                continue;
+            }
+            // Relative error in ppm
+            let error = (vco_out as i64 - freq as i64).abs() as u64 * 1_000_000 / freq;
+            if best.map_or(true, |(_, _, best_error)| error < best_error) {
+                best = Some((plli2sn as u32, plli2sr, error));
+            }
+        }
+    }
+    let (plli2sn, plli2sr, _) = best.ok_or(InvalidConfig)?;
+
+    // Devices with a dedicated PLLI2SM divider get the same value as PLLM
+    #[cfg(any(
+        feature = "stm32f401",
+        feature = "stm32f411",
+        feature = "stm32f412",
+        feature = "stm32f413",
+        feature = "stm32f423",
+        feature = "stm32f446"
+    ))]
+    let (m_mask, m_bits) = (0x3f, u32::from(rcc.pllcfgr.read().pllm().bits()));
+    #[cfg(not(any(
+        feature = "stm32f401",
+        feature = "stm32f411",
+        feature = "stm32f412",
+        feature = "stm32f413",
+        feature = "stm32f423",
+        feature = "stm32f446"
+    )))]
+    let (m_mask, m_bits) = (0, 0);
+
+    rcc.plli2scfgr.modify(|r, w| unsafe {
+        w.bits(
+            (r.bits() & !((0b111 << 28) | (0x1ff << 6) | m_mask))
+                | (plli2sr << 28)
+                | (plli2sn << 6)
+                | m_bits,
+        )
+    });
+
+    // Enable PLLI2S and wait for it to lock
+    rcc.cr.modify(|_, w| w.plli2son().set_bit());
+    while rcc.cr.read().plli2srdy().bit_is_clear() {}
+
+    Ok(vco_in * plli2sn / plli2sr)
+}
+
+fn is_transmitter(mode: Mode) -> bool {
+    mode == Mode::MasterTransmit || mode == Mode::SlaveTransmit
+}
+
+/// I2S interface
+pub struct I2s<SPI, PINS> {
+    spi: SPI,
+    pins: PINS,
+    mode: Mode,
+    sample_rate: Option<Hertz>,
+}
+
+impl<PINS> I2s<SPI2, PINS> {
+    pub fn i2s2(
+        spi: SPI2,
+        pins: PINS,
+        config: Config,
+        clocks: Clocks,
+    ) -> Result<Self, InvalidConfig>
+    where
+        PINS: Pins<SPI2>,
+    {
+        unsafe {
+            const EN_BIT: u8 = 14;
+            // NOTE(unsafe) this reference will only be used for atomic writes with no side effects.
+            let rcc = &(*RCC::ptr());
+
+            // Enable clock.
+            bb::set(&rcc.apb1enr, EN_BIT);
+        }
+
+        I2s::init(spi, pins, config, clocks)
+    }
+}
+
+impl<PINS> I2s<SPI3, PINS> {
+    pub fn i2s3(
+        spi: SPI3,
+        pins: PINS,
+        config: Config,
+        clocks: Clocks,
+    ) -> Result<Self, InvalidConfig>
+    where
+        PINS: Pins<SPI3>,
+    {
+        unsafe {
+            const EN_BIT: u8 = 15;
+            // NOTE(unsafe) this reference will only be used for atomic writes with no side effects.
+            let rcc = &(*RCC::ptr());
+
+            // Enable clock.
+            bb::set(&rcc.apb1enr, EN_BIT);
+        }
+
+        I2s::init(spi, pins, config, clocks)
+    }
+}
+
+impl<SPI, PINS> I2s<SPI, PINS>
+where
+    SPI: Deref<Target = spi1::RegisterBlock>,
+{
+    fn init(spi: SPI, pins: PINS, config: Config, clocks: Clocks) -> Result<Self, InvalidConfig> {
+        let master = config.mode == Mode::MasterTransmit || config.mode == Mode::MasterReceive;
+
+        let (i2spr, sample_rate) = if master {
+            let fs = config.sample_rate.0;
+            if !(8_000..=192_000).contains(&fs) {
+                return Err(InvalidConfig);
+            }
+
+            // The bit clock is 32 or 64 times the sample rate, the master clock 256 times
+            let divisor = if config.master_clock {
+                256
+            } else if config.data_format == DataFormat::Data16Channel16 {
+                32
+            } else {
+                64
+            };
+            let i2sclk = plli2s_setup(clocks, divisor * fs)?;
+            // Total division factor 2 * I2SDIV + ODD, rounded to the nearest value
+            let div = (i2sclk + divisor * fs / 2) / (divisor * fs);
+            if !(4..=511).contains(&div) {
+                return Err(InvalidConfig);
+            }
+
+            let mut i2spr = (div / 2) | if div & 1 != 0 { I2SPR_ODD } else { 0 };
+            if config.master_clock {
+                i2spr |= I2SPR_MCKOE;
+            }
+            (i2spr, Some(Hertz(i2sclk / (divisor * div))))
+        } else {
+            // Reset value, the prescaler is unused in slave mode
+            (2, None)
+        };
+
+        spi.i2scfgr.write(|w| unsafe { w.bits(0) });
+        spi.cr2.write(|w| unsafe { w.bits(0) });
+        spi.i2spr.write(|w| unsafe { w.bits(i2spr) });
+        spi.i2scfgr
+            .write(|w| unsafe { w.bits(i2scfgr_bits(config.mode, &config)) });
+
+        Ok(I2s {
+            spi,
+            pins,
+            mode: config.mode,
+            sample_rate,
+        })
+    }
+
+    /// Real sample rate in master modes
+    pub fn sample_rate(&self) -> Option<Hertz> {
+        self.sample_rate
+    }
+
+    /// Starts the interface. In full-duplex operation the extension block has to be enabled
+    /// first.
+    pub fn enable(&mut self) {
+        self.spi
+            .i2scfgr
+            .modify(|r, w| unsafe { w.bits(r.bits() | I2SCFGR_I2SE) });
+    }
+
+    /// Stops the interface after the current frame
+    pub fn disable(&mut self) {
+        if is_transmitter(self.mode) {
+            // Wait until the last data has left the shift register
+            while self.spi.sr.read().txe().bit_is_clear() {}
+            while self.spi.sr.read().bsy().bit_is_set() {}
+        }
+        self.spi
+            .i2scfgr
+            .modify(|r, w| unsafe { w.bits(r.bits() & !I2SCFGR_I2SE) });
+    }
+
+    /// Writes the next half-word to send
+    pub fn write(&mut self, data: u16) -> nb::Result<(), Error> {
+        let sr = self.spi.sr.read();
+
+        Err(if sr.udr().bit_is_set() {
+            nb::Error::Other(Error::Underrun)
+        } else if sr.fre().bit_is_set() {
+            nb::Error::Other(Error::Frame)
+        } else if sr.txe().bit_is_set() {
+            // NOTE(write_volatile) the svd2rust API does not allow to write a plain half-word
+            unsafe { ptr::write_volatile(&self.spi.dr as *const _ as *mut u16, data) }
+            return Ok(());
+        } else {
+            nb::Error::WouldBlock
+        })
+    }
+
+    /// Reads the next received half-word and the channel it belongs to
+    pub fn read(&mut self) -> nb::Result<(Channel, u16), Error> {
+        let sr = self.spi.sr.read();
+
+        Err(if sr.ovr().bit_is_set() {
+            // Reading DR and then SR clears the flag
+            let _ = unsafe { ptr::read_volatile(&self.spi.dr as *const _ as *const u16) };
+            let _ = self.spi.sr.read();
+            nb::Error::Other(Error::Overrun)
+        } else if sr.fre().bit_is_set() {
+            nb::Error::Other(Error::Frame)
+        } else if sr.rxne().bit_is_set() {
+            let channel = if sr.chside().bit_is_set() {
+                Channel::Right
+            } else {
+                Channel::Left
+            };
+            let data = unsafe { ptr::read_volatile(&self.spi.dr as *const _ as *const u16) };
+            return Ok((channel, data));
+        } else {
+            nb::Error::WouldBlock
+        })
+    }
+
+    /// Enable interrupts for the given `event`
+    pub fn listen(&mut self, event: Event) {
+        match event {
+            Event::Rxne => self.spi.cr2.modify(|_, w| w.rxneie().set_bit()),
+            Event::Txe => self.spi.cr2.modify(|_, w| w.txeie().set_bit()),
+            Event::Error => self.spi.cr2.modify(|_, w| w.errie().set_bit()),
+        }
+    }
+
+    /// Disable interrupts for the given `event`
+    pub fn unlisten(&mut self, event: Event) {
+        match event {
+            Event::Rxne => self.spi.cr2.modify(|_, w| w.rxneie().clear_bit()),
+            Event::Txe => self.spi.cr2.modify(|_, w| w.txeie().clear_bit()),
+            Event::Error => self.spi.cr2.modify(|_, w| w.errie().clear_bit()),
+        }
+    }
+
+    /// Enables the DMA request matching the transfer direction
+    pub fn enable_dma(&mut self) {
+        let bit = if is_transmitter(self.mode) {
+            CR2_TXDMAEN
+        } else {
+            CR2_RXDMAEN
+        };
+        self.spi
+            .cr2
+            .modify(|r, w| unsafe { w.bits(r.bits() | bit) });
+    }
+
+    /// Disables the DMA requests
+    pub fn disable_dma(&mut self) {
+        self.spi
+            .cr2
+            .modify(|r, w| unsafe { w.bits(r.bits() & !(CR2_TXDMAEN | CR2_RXDMAEN)) });
+    }
+
+    pub fn free(self) -> (SPI, PINS) {
+        (self.spi, self.pins)
+    }
+}
+
+unsafe impl<SPI, PINS> PeriAddress for I2s<SPI, PINS>
+where
+    SPI: Deref<Target = spi1::RegisterBlock>,
+{
+    #[inline(always)]
+    fn address(&self) -> u32 {
+        &self.spi.dr as *const _ as u32
+    }
+
+    type MemSize = u16;
+}
+
+#[cfg(any(
+    feature = "stm32f401",
+    feature = "stm32f405",
+    feature = "stm32f407",
+    feature = "stm32f411",
+    feature = "stm32f415",
+    feature = "stm32f417",
+    feature = "stm32f427",
+    feature = "stm32f429",
+    feature = "stm32f437",
+    feature = "stm32f439",
+    feature = "stm32f469",
+    feature = "stm32f479"
+))]
+/// I2SxEXT block used for the second direction in full-duplex operation
+///
+/// It always runs as slave of the main block and shares its clock and word select signals,
+/// so only the serial data pin is needed.
+pub struct I2sExt<EXT, PIN> {
+    ext: EXT,
+    pin: PIN,
+    mode: Mode,
+}
+
+#[cfg(any(
+    feature = "stm32f401",
+    feature = "stm32f405",
+    feature = "stm32f407",
+    feature = "stm32f411",
+    feature = "stm32f415",
+    feature = "stm32f417",
+    feature = "stm32f427",
+    feature = "stm32f429",
+    feature = "stm32f437",
+    feature = "stm32f439",
+    feature = "stm32f469",
+    feature = "stm32f479"
+))]
+impl<PIN> I2sExt<I2S2EXT, PIN> {
+    /// Configures I2S2EXT to complement an `I2s<SPI2, _>` created with `config`
+    pub fn i2s2ext(ext: I2S2EXT, pin: PIN, config: &Config) -> Self
+    where
+        PIN: PinExtSd<I2S2EXT>,
+    {
+        I2sExt::init(ext, pin, config)
+    }
+}
+
+#[cfg(any(
+    feature = "stm32f401",
+    feature = "stm32f405",
+    feature = "stm32f407",
+    feature = "stm32f411",
+    feature = "stm32f415",
+    feature = "stm32f417",
+    feature = "stm32f427",
+    feature = "stm32f429",
+    feature = "stm32f437",
+    feature = "stm32f439",
+    feature = "stm32f469",
+    feature = "stm32f479"
+))]
+impl<PIN> I2sExt<I2S3EXT, PIN> {
+    /// Configures I2S3EXT to complement an `I2s<SPI3, _>` created with `config`
+    pub fn i2s3ext(ext: I2S3EXT, pin: PIN, config: &Config) -> Self
+    where
+        PIN: PinExtSd<I2S3EXT>,
+    {
+        I2sExt::init(ext, pin, config)
+    }
+}
+
+#[cfg(any(
+    feature = "stm32f401",
+    feature = "stm32f405",
+    feature = "stm32f407",
+    feature = "stm32f411",
+    feature = "stm32f415",
+    feature = "stm32f417",
+    feature = "stm32f427",
+    feature = "stm32f429",
+    feature = "stm32f437",
+    feature = "stm32f439",
+    feature = "stm32f469",
+    feature = "stm32f479"
+))]
+impl<EXT, PIN> I2sExt<EXT, PIN>
+where
+    EXT: Deref<Target = spi1::RegisterBlock>,
+{
+    fn init(ext: EXT, pin: PIN, config: &Config) -> Self {
+        // The extension block transfers in the opposite direction of the main block
+        let mode = if is_transmitter(config.mode) {
+            Mode::SlaveReceive
+        } else {
+            Mode::SlaveTransmit
+        };
+
+        ext.i2scfgr.write(|w| unsafe { w.bits(0) });
+        ext.cr2.write(|w| unsafe { w.bits(0) });
+        ext.i2scfgr
+            .write(|w| unsafe { w.bits(i2scfgr_bits(mode, config)) });
+
+        I2sExt { ext, pin, mode }
+    }
+
+    /// Starts the extension block. Has to be done before enabling the main block.
+    pub fn enable(&mut self) {
+        self.ext
+            .i2scfgr
+            .modify(|r, w| unsafe { w.bits(r.bits() | I2SCFGR_I2SE) });
+    }
+
+    pub fn disable(&mut self) {
+        self.ext
+            .i2scfgr
+            .modify(|r, w| unsafe { w.bits(r.bits() & !I2SCFGR_I2SE) });
+    }
+
+    /// Writes the next half-word to send
+    pub fn write(&mut self, data: u16) -> nb::Result<(), Error> {
+        let sr = self.ext.sr.read();
+
+        Err(if sr.udr().bit_is_set() {
+            nb::Error::Other(Error::Underrun)
+        } else if sr.txe().bit_is_set() {
+            // NOTE(write_volatile) see note in `I2s::write`
+            unsafe { ptr::write_volatile(&self.ext.dr as *const _ as *mut u16, data) }
+            return Ok(());
+        } else {
+            nb::Error::WouldBlock
+        })
+    }
+
+    /// Reads the next received half-word and the channel it belongs to
+    pub fn read(&mut self) -> nb::Result<(Channel, u16), Error> {
+        let sr = self.ext.sr.read();
+
+        Err(if sr.ovr().bit_is_set() {
+            let _ = unsafe { ptr::read_volatile(&self.ext.dr as *const _ as *const u16) };
+            let _ = self.ext.sr.read();
+            nb::Error::Other(Error::Overrun)
+        } else if sr.rxne().bit_is_set() {
+            let channel = if sr.chside().bit_is_set() {
+                Channel::Right
+            } else {
+                Channel::Left
+            };
+            let data = unsafe { ptr::read_volatile(&self.ext.dr as *const _ as *const u16) };
+            return Ok((channel, data));
+        } else {
+            nb::Error::WouldBlock
+        })
+    }
+
+    /// Enable interrupts for the given `event`
+    pub fn listen(&mut self, event: Event) {
+        match event {
+            Event::Rxne => self.ext.cr2.modify(|_, w| w.rxneie().set_bit()),
+            Event::Txe => self.ext.cr2.modify(|_, w| w.txeie().set_bit()),
+            Event::Error => self.ext.cr2.modify(|_, w| w.errie().set_bit()),
+        }
+    }
+
+    /// Disable interrupts for the given `event`
+    pub fn unlisten(&mut self, event: Event) {
+        match event {
+            Event::Rxne => self.ext.cr2.modify(|_, w| w.rxneie().clear_bit()),
+            Event::Txe => self.ext.cr2.modify(|_, w| w.txeie().clear_bit()),
+            Event::Error => self.ext.cr2.modify(|_, w| w.errie().clear_bit()),
+        }
+    }
+
+    /// Enables the DMA request matching the transfer direction
+    pub fn enable_dma(&mut self) {
+        let bit = if is_transmitter(self.mode) {
+            CR2_TXDMAEN
+        } else {
+            CR2_RXDMAEN
+        };
+        self.ext
+            .cr2
+            .modify(|r, w| unsafe { w.bits(r.bits() | bit) });
+    }
+
+    /// Disables the DMA requests
+    pub fn disable_dma(&mut self) {
+        self.ext
+            .cr2
+            .modify(|r, w| unsafe { w.bits(r.bits() & !(CR2_TXDMAEN | CR2_RXDMAEN)) });
+    }
+
+    pub fn free(self) -> (EXT, PIN) {
+        (self.ext, self.pin)
+    }
+}
+
+#[cfg(any(
+    feature = "stm32f401",
+    feature = "stm32f405",
+    feature = "stm32f407",
+    feature = "stm32f411",
+    feature = "stm32f415",
+    feature = "stm32f417",
+    feature = "stm32f427",
+    feature = "stm32f429",
+    feature = "stm32f437",
+    feature = "stm32f439",
+    feature = "stm32f469",
+    feature = "stm32f479"
+))]
+unsafe impl<EXT, PIN> PeriAddress for I2sExt<EXT, PIN>
+where
+    EXT: Deref<Target = spi1::RegisterBlock>,
+{
+    #[inline(always)]
+    fn address(&self) -> u32 {
+        &self.ext.dr as *const _ as u32
+    }
+
+    type MemSize = u16;
+}
+
+macro_rules! dma_map {
+    ($(($Stream:ty, $channel:ty, $Peripheral:ident<$Instance:ty>, $dir:ty)),+ $(,)*) => {
+        $(
+            unsafe impl<PINS> DMASet for ($Stream, $channel, $Peripheral<$Instance, PINS>, $dir) {}
+        )+
+    };
+}
+
+dma_map!(
+    (Stream3<DMA1>, Channel0, I2s<SPI2>, PeripheralToMemory), //SPI2_RX
+    (Stream4<DMA1>, Channel0, I2s<SPI2>, MemoryToPeripheral), //SPI2_TX
+    (Stream0<DMA1>, Channel0, I2s<SPI3>, PeripheralToMemory), //SPI3_RX
+    (Stream2<DMA1>, Channel0, I2s<SPI3>, PeripheralToMemory), //SPI3_RX
+    (Stream5<DMA1>, Channel0, I2s<SPI3>, MemoryToPeripheral), //SPI3_TX
+    (Stream7<DMA1>, Channel0, I2s<SPI3>, MemoryToPeripheral), //SPI3_TX
+);
+
+#[cfg(any(
+    feature = "stm32f401",
+    feature = "stm32f405",
+    feature = "stm32f407",
+    feature = "stm32f411",
+    feature = "stm32f415",
+    feature = "stm32f417",
+    feature = "stm32f427",
+    feature = "stm32f429",
+    feature = "stm32f437",
+    feature = "stm32f439",
+    feature = "stm32f469",
+    feature = "stm32f479"
+))]
+dma_map!(
+    (Stream3<DMA1>, Channel3, I2sExt<I2S2EXT>, PeripheralToMemory), //I2S2_EXT_RX
+    (Stream4<DMA1>, Channel2, I2sExt<I2S2EXT>, MemoryToPeripheral), //I2S2_EXT_TX
+    (Stream0<DMA1>, Channel3, I2sExt<I2S3EXT>, PeripheralToMemory), //I2S3_EXT_RX
+    (Stream2<DMA1>, Channel2, I2sExt<I2S3EXT>, PeripheralToMemory), //I2S3_EXT_RX
+    (Stream5<DMA1>, Channel2, I2sExt<I2S3EXT>, MemoryToPeripheral), //I2S3_EXT_TX
+);
@@ -86,7 +86,18 @@ pub use crate::stm32::interrupt;
 #[cfg(feature = "device-selected")]
 pub mod adc;
 #[cfg(feature = "device-selected")]
+pub mod backup;
+#[cfg(feature = "device-selected")]
 pub mod bb;
+#[cfg(all(
+    feature = "device-selected",
+    not(any(feature = "stm32f401", feature = "stm32f410", feature = "stm32f411",))
+))]
+pub mod can;
+#[cfg(feature = "device-selected")]
+pub mod capture;
+#[cfg(feature = "device-selected")]
+pub mod crc;
 #[cfg(all(
     feature = "device-selected",
     not(any(feature = "stm32f411", feature = "stm32f412", feature = "stm32f401",))
@@ -95,9 +106,13 @@ pub mod dac;
 #[cfg(feature = "device-selected")]
 pub mod delay;
 #[cfg(feature = "device-selected")]
+pub mod flash;
+#[cfg(feature = "device-selected")]
 pub mod gpio;
 #[cfg(feature = "device-selected")]
 pub mod i2c;
+#[cfg(all(feature = "device-selected", not(feature = "stm32f410")))]
+pub mod i2s;
 #[cfg(all(
     feature = "usb_fs",
     any(
@@ -149,6 +164,9 @@ pub mod otg_hs;
 ))]
 pub mod rng;
 
+#[cfg(feature = "device-selected")]
+pub mod rtc;
+
 #[cfg(feature = "device-selected")]
 pub use stm32 as pac;
 
@@ -157,10 +175,14 @@ pub mod dma;
 #[cfg(feature = "device-selected")]
 pub mod dwt;
 #[cfg(feature = "device-selected")]
+pub mod one_pulse;
+#[cfg(feature = "device-selected")]
 pub mod prelude;
 #[cfg(feature = "device-selected")]
 pub mod pwm;
 #[cfg(feature = "device-selected")]
+pub mod pwr;
+#[cfg(feature = "device-selected")]
 pub mod qei;
 #[cfg(feature = "device-selected")]
 pub mod rcc;
@@ -0,0 +1,511 @@
+//! One-pulse mode
+//!
+//! After a trigger the output on channel 1 stays inactive for the configured delay, is active
+//! for the configured width and then the counter stops until the next trigger. The trigger is
+//! either software ([`OnePulse::trigger`](struct.OnePulse.html#method.trigger)) or an edge on
+//! the channel 2 input, which starts the counter through the slave mode controller.
+//!
+//! The STM32F4 timers cannot restart a running pulse from a hardware trigger, edges on TI2
+//! during a pulse are ignored. Software triggers can restart a running pulse when
+//! `Config::retriggerable` is set.
+
+use core::convert::TryInto;
+
+use cast::u16;
+use void::Void;
+
+use crate::capture::Edge;
+use crate::pwm::Polarity;
+use crate::timer::{PinC1, PinC2};
+use crate::{bb, rcc::Clocks, stm32::RCC, time::NanoSeconds};
+
+#[cfg(any(
+    feature = "stm32f401",
+    feature = "stm32f405",
+    feature = "stm32f407",
+    feature = "stm32f410",
+    feature = "stm32f411",
+    feature = "stm32f412",
+    feature = "stm32f413",
+    feature = "stm32f415",
+    feature = "stm32f417",
+    feature = "stm32f423",
+    feature = "stm32f427",
+    feature = "stm32f429",
+    feature = "stm32f437",
+    feature = "stm32f439",
+    feature = "stm32f446",
+    feature = "stm32f469",
+    feature = "stm32f479"
+))]
+use crate::stm32::{TIM1, TIM11, TIM5, TIM9};
+
+#[cfg(any(
+    feature = "stm32f401",
+    feature = "stm32f405",
+    feature = "stm32f407",
+    feature = "stm32f411",
+    feature = "stm32f412",
+    feature = "stm32f413",
+    feature = "stm32f415",
+    feature = "stm32f417",
+    feature = "stm32f423",
+    feature = "stm32f427",
+    feature = "stm32f429",
+    feature = "stm32f437",
+    feature = "stm32f439",
+    feature = "stm32f446",
+    feature = "stm32f469",
+    feature = "stm32f479"
+))]
+use crate::stm32::{TIM10, TIM2, TIM3, TIM4};
+
+#[cfg(any(
+    feature = "stm32f405",
+    feature = "stm32f407",
+    feature = "stm32f412",
+    feature = "stm32f413",
+    feature = "stm32f415",
+    feature = "stm32f417",
+    feature = "stm32f423",
+    feature = "stm32f427",
+    feature = "stm32f429",
+    feature = "stm32f437",
+    feature = "stm32f439",
+    feature = "stm32f446",
+    feature = "stm32f469",
+    feature = "stm32f479"
+))]
+use crate::stm32::TIM8;
+
+// TIMx_CR1 bits
+const CR1_CEN: u32 = 1 << 0;
+const CR1_URS: u32 = 1 << 2;
+const CR1_OPM: u32 = 1 << 3;
+const CR1_ARPE: u32 = 1 << 7;
+
+// TIMx_SR bits
+const SR_UIF: u32 = 1 << 0;
+
+/// One-pulse configuration
+#[derive(Clone, Copy, Debug)]
+pub struct Config {
+    /// Time from the trigger to the start of the pulse
+    pub delay: NanoSeconds,
+    /// Length of the pulse
+    pub width: NanoSeconds,
+    pub polarity: Polarity,
+    /// Restart a running pulse on a software trigger instead of ignoring the trigger
+    pub retriggerable: bool,
+}
+
+impl Config {
+    /// Returns `InvalidConfig` if `delay` doesn't fit into `NanoSeconds`
+    pub fn delay<T: TryInto<NanoSeconds>>(mut self, delay: T) -> Result<Self, InvalidConfig> {
+        self.delay = delay.try_into().map_err(|_| InvalidConfig)?;
+        Ok(self)
+    }
+
+    /// Returns `InvalidConfig` if `width` doesn't fit into `NanoSeconds`
+    pub fn width<T: TryInto<NanoSeconds>>(mut self, width: T) -> Result<Self, InvalidConfig> {
+        self.width = width.try_into().map_err(|_| InvalidConfig)?;
+        Ok(self)
+    }
+
+    pub fn polarity(mut self, polarity: Polarity) -> Self {
+        self.polarity = polarity;
+        self
+    }
+
+    pub fn retriggerable(mut self, retriggerable: bool) -> Self {
+        self.retriggerable = retriggerable;
+        self
+    }
+}
+
+/// The delay or width can't be generated with the timer clock
+#[derive(Debug)]
+pub struct InvalidConfig;
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            delay: NanoSeconds(1_000),
+            width: NanoSeconds(1_000),
+            polarity: Polarity::ActiveHigh,
+            retriggerable: false,
+        }
+    }
+}
+
+/// Source that starts a pulse
+pub trait Trigger<TIM> {
+    #[doc(hidden)]
+    fn edge(&self) -> Option<Edge>;
+}
+
+/// Pulses are only started by `OnePulse::trigger`
+pub struct Software;
+
+impl<TIM> Trigger<TIM> for Software {
+    fn edge(&self) -> Option<Edge> {
+        None
+    }
+}
+
+/// Pulses are started by an edge on the channel 2 input (TI2)
+pub struct Ti2<PIN> {
+    pub pin: PIN,
+    pub edge: Edge,
+}
+
+/// Timer in one-pulse mode with the output on channel 1
+pub struct OnePulse<TIM, PIN, TRIGGER> {
+    tim: TIM,
+    pin: PIN,
+    trigger: TRIGGER,
+    clk: u32,
+    retriggerable: bool,
+}
+
+/// Prescaler, compare and auto-reload values for a pulse of `width` after `delay`, `None` if
+/// the pulse doesn't fit into the counter range
+fn pulse_timing(
+    clk: u32,
+    delay: NanoSeconds,
+    width: NanoSeconds,
+    max: u32,
+) -> Option<(u16, u32, u32)> {
+    let ticks = |t: NanoSeconds| (u64::from(t.0) * u64::from(clk) / 1_000_000_000).max(1);
+    let delay = ticks(delay);
+    let width = ticks(width);
+
+    // Smallest prescaler that fits delay and width into the counter range
+    let psc = u16((delay + width - 1) / (u64::from(max) + 1)).ok()?;
+    let div = u64::from(psc) + 1;
+    let ccr = (delay / div).max(1);
+    let arr = ccr + (width / div).max(1) - 1;
+    if arr > u64::from(max) {
+        return None;
+    }
+    Some((psc, ccr as u32, arr as u32))
+}
+
+macro_rules! main_output {
+    (TIM1, $tim:ident) => {
+        $tim.bdtr.modify(|_, w| w.moe().set_bit());
+    };
+    (TIM8, $tim:ident) => {
+        $tim.bdtr.modify(|_, w| w.moe().set_bit());
+    };
+    ($_other:ident, $_tim:ident) => {};
+}
+
+macro_rules! slave_trigger {
+    (TIM10, $tim:ident, $edge:ident) => {
+        let _ = $edge;
+    };
+    (TIM11, $tim:ident, $edge:ident) => {
+        let _ = $edge;
+    };
+    ($_other:ident, $tim:ident, $edge:ident) => {
+        // IC2 mapped on TI2 (CC2S = 01)
+        $tim.ccmr1_output()
+            .modify(|r, w| unsafe { w.bits((r.bits() & !(0xff << 8)) | 0b01 << 8) });
+        let polarity = match $edge {
+            Edge::Rising => 0,
+            Edge::Falling => 1 << 5,
+            Edge::Both => 1 << 5 | 1 << 7,
+        };
+        $tim.ccer
+            .modify(|r, w| unsafe { w.bits((r.bits() & !(0b1111 << 4)) | polarity) });
+        // Start the counter on TI2FP2 (TS = 110, SMS = 110)
+        $tim.smcr.write(|w| unsafe { w.bits(0b110 << 4 | 0b110) });
+    };
+}
+
+macro_rules! hal {
+    ($($TIM:ident: ($tim:ident, $apbenr:ident, $apbrstr:ident, $bit:expr, $pclk:ident, $ppre:ident, $bits:ident),)+) => {
+        $(
+            impl<PIN, TRIGGER> OnePulse<$TIM, PIN, TRIGGER> {
+                /// Configures a TIM peripheral for one-pulse mode
+                ///
+                /// Returns `InvalidConfig` if the delay and width together are too long for the
+                /// timer.
+                pub fn $tim(
+                    tim: $TIM,
+                    pin: PIN,
+                    trigger: TRIGGER,
+                    clocks: Clocks,
+                    config: Config,
+                ) -> Result<Self, InvalidConfig>
+                where
+                    PIN: PinC1<$TIM>,
+                    TRIGGER: Trigger<$TIM>,
+                {
+                    unsafe {
+                        //NOTE(unsafe) this reference will only be used for atomic writes with no side effects
+                        let rcc = &(*RCC::ptr());
+                        // Enable and reset the timer peripheral, it's the same bit position for both registers
+                        bb::set(&rcc.$apbenr, $bit);
+                        bb::set(&rcc.$apbrstr, $bit);
+                        bb::clear(&rcc.$apbrstr, $bit);
+                    }
+
+                    let clk = clocks.$pclk().0 * if clocks.$ppre() == 1 { 1 } else { 2 };
+                    let (psc, ccr, arr) =
+                        pulse_timing(clk, config.delay, config.width, core::$bits::MAX as u32)
+                            .ok_or(InvalidConfig)?;
+
+                    // PWM mode 2 with preload: inactive until CCR1, active until the end of
+                    // the period
+                    tim.ccmr1_output().write(|w| unsafe { w.bits(0b111 << 4 | 1 << 3) });
+                    let cc1p = match config.polarity {
+                        Polarity::ActiveHigh => 0,
+                        Polarity::ActiveLow => 1 << 1,
+                    };
+                    tim.ccer.write(|w| unsafe { w.bits(cc1p | 1 << 0) });
+                    main_output!($TIM, tim);
+
+                    if let Some(edge) = trigger.edge() {
+                        slave_trigger!($TIM, tim, edge);
+                    }
+
+                    // Only counter overflows, i.e. the end of a pulse, set UIF
+                    tim.cr1.write(|w| unsafe { w.bits(CR1_ARPE | CR1_OPM | CR1_URS) });
+
+                    let mut one_pulse = OnePulse {
+                        tim,
+                        pin,
+                        trigger,
+                        clk,
+                        retriggerable: config.retriggerable,
+                    };
+                    one_pulse.write_timing(psc, ccr, arr);
+                    // Load the preloaded registers
+                    one_pulse.tim.egr.write(|w| w.ug().set_bit());
+
+                    Ok(one_pulse)
+                }
+
+                /// Changes delay and width, which take effect with the next pulse
+                ///
+                /// Returns `InvalidConfig` and keeps the current timing if the delay and width
+                /// together are too long for the timer.
+                pub fn set_timing<D, W>(&mut self, delay: D, width: W) -> Result<(), InvalidConfig>
+                where
+                    D: TryInto<NanoSeconds>,
+                    W: TryInto<NanoSeconds>,
+                {
+                    let delay = delay.try_into().map_err(|_| InvalidConfig)?;
+                    let width = width.try_into().map_err(|_| InvalidConfig)?;
+                    let (psc, ccr, arr) =
+                        pulse_timing(self.clk, delay, width, core::$bits::MAX as u32)
+                            .ok_or(InvalidConfig)?;
+                    self.write_timing(psc, ccr, arr);
+                    Ok(())
+                }
+
+                fn write_timing(&mut self, psc: u16, ccr: u32, arr: u32) {
+                    self.tim.psc.write(|w| w.psc().bits(psc));
+                    self.tim.ccr1.write(|w| unsafe { w.bits(ccr) });
+                    self.tim.arr.write(|w| unsafe { w.bits(arr) });
+                }
+
+                /// Starts a pulse
+                ///
+                /// While a pulse is running the trigger is ignored, or restarts the delay if the
+                /// timer is retriggerable.
+                pub fn trigger(&mut self) {
+                    if self.is_running() {
+                        if !self.retriggerable {
+                            return;
+                        }
+                        // Reset the counter, URS keeps UIF clear
+                        self.tim.egr.write(|w| w.ug().set_bit());
+                    }
+                    //NOTE(unsafe) atomic write with no side effects
+                    unsafe { bb::set(&self.tim.cr1, 0) }
+                }
+
+                /// Returns `true` while a pulse, including its delay, is in progress
+                pub fn is_running(&self) -> bool {
+                    self.tim.cr1.read().bits() & CR1_CEN != 0
+                }
+
+                /// Waits for the end of a pulse
+                pub fn wait(&mut self) -> nb::Result<(), Void> {
+                    if self.tim.sr.read().bits() & SR_UIF == 0 {
+                        Err(nb::Error::WouldBlock)
+                    } else {
+                        self.tim.sr.write(|w| unsafe { w.bits(!SR_UIF) });
+                        Ok(())
+                    }
+                }
+
+                /// Enables the interrupt at the end of each pulse
+                pub fn listen(&mut self) {
+                    //NOTE(unsafe) atomic write with no side effects
+                    unsafe { bb::set(&self.tim.dier, 0) }
+                }
+
+                /// Disables the interrupt at the end of each pulse
+                pub fn unlisten(&mut self) {
+                    //NOTE(unsafe) atomic write with no side effects
+                    unsafe { bb::clear(&self.tim.dier, 0) }
+                }
+
+                /// Releases the TIM peripheral, the output pin and the trigger
+                pub fn release(self) -> ($TIM, PIN, TRIGGER) {
+                    self.tim.cr1.modify(|_, w| w.cen().clear_bit());
+                    (self.tim, self.pin, self.trigger)
+                }
+            }
+        )+
+    }
+}
+
+macro_rules! ti2_trigger {
+    ($($TIM:ident,)+) => {
+        $(
+            impl<PIN> Trigger<$TIM> for Ti2<PIN>
+            where
+                PIN: PinC2<$TIM>,
+            {
+                fn edge(&self) -> Option<Edge> {
+                    Some(self.edge)
+                }
+            }
+        )+
+    };
+}
+
+#[cfg(any(
+    feature = "stm32f401",
+    feature = "stm32f405",
+    feature = "stm32f407",
+    feature = "stm32f410",
+    feature = "stm32f411",
+    feature = "stm32f412",
+    feature = "stm32f413",
+    feature = "stm32f415",
+    feature = "stm32f417",
+    feature = "stm32f423",
+    feature = "stm32f427",
+    feature = "stm32f429",
+    feature = "stm32f437",
+    feature = "stm32f439",
+    feature = "stm32f446",
+    feature = "stm32f469",
+    feature = "stm32f479"
+))]
+hal! {
+    TIM1: (tim1, apb2enr, apb2rstr, 0, pclk2, ppre2, u16),
+    TIM5: (tim5, apb1enr, apb1rstr, 3, pclk1, ppre1, u32),
+    TIM9: (tim9, apb2enr, apb2rstr, 16, pclk2, ppre2, u16),
+    TIM11: (tim11, apb2enr, apb2rstr, 18, pclk2, ppre2, u16),
+}
+
+#[cfg(any(
+    feature = "stm32f401",
+    feature = "stm32f405",
+    feature = "stm32f407",
+    feature = "stm32f410",
+    feature = "stm32f411",
+    feature = "stm32f412",
+    feature = "stm32f413",
+    feature = "stm32f415",
+    feature = "stm32f417",
+    feature = "stm32f423",
+    feature = "stm32f427",
+    feature = "stm32f429",
+    feature = "stm32f437",
+    feature = "stm32f439",
+    feature = "stm32f446",
+    feature = "stm32f469",
+    feature = "stm32f479"
+))]
+ti2_trigger!(TIM1, TIM5, TIM9,);
+
+#[cfg(any(
+    feature = "stm32f401",
+    feature = "stm32f405",
+    feature = "stm32f407",
+    feature = "stm32f411",
+    feature = "stm32f412",
+    feature = "stm32f413",
+    feature = "stm32f415",
+    feature = "stm32f417",
+    feature = "stm32f423",
+    feature = "stm32f427",
+    feature = "stm32f429",
+    feature = "stm32f437",
+    feature = "stm32f439",
+    feature = "stm32f446",
+    feature = "stm32f469",
+    feature = "stm32f479"
+))]
+hal! {
+    TIM2: (tim2, apb1enr, apb1rstr, 0, pclk1, ppre1, u32),
+    TIM3: (tim3, apb1enr, apb1rstr, 1, pclk1, ppre1, u16),
+    TIM4: (tim4, apb1enr, apb1rstr, 2, pclk1, ppre1, u16),
+    TIM10: (tim10, apb2enr, apb2rstr, 17, pclk2, ppre2, u16),
+}
+
+#[cfg(any(
+    feature = "stm32f401",
+    feature = "stm32f405",
+    feature = "stm32f407",
+    feature = "stm32f411",
+    feature = "stm32f412",
+    feature = "stm32f413",
+    feature = "stm32f415",
+    feature = "stm32f417",
+    feature = "stm32f423",
+    feature = "stm32f427",
+    feature = "stm32f429",
+    feature = "stm32f437",
+    feature = "stm32f439",
+    feature = "stm32f446",
+    feature = "stm32f469",
+    feature = "stm32f479"
+))]
+ti2_trigger!(TIM2, TIM3, TIM4,);
+
+#[cfg(any(
+    feature = "stm32f405",
+    feature = "stm32f407",
+    feature = "stm32f412",
+    feature = "stm32f413",
+    feature = "stm32f415",
+    feature = "stm32f417",
+    feature = "stm32f423",
+    feature = "stm32f427",
+    feature = "stm32f429",
+    feature = "stm32f437",
+    feature = "stm32f439",
+    feature = "stm32f446",
+    feature = "stm32f469",
+    feature = "stm32f479"
+))]
+hal! {
+    TIM8: (tim8, apb2enr, apb2rstr, 1, pclk2, ppre2, u16),
+}
+
+#[cfg(any(
+    feature = "stm32f405",
+    feature = "stm32f407",
+    feature = "stm32f412",
+    feature = "stm32f413",
+    feature = "stm32f415",
+    feature = "stm32f417",
+    feature = "stm32f423",
+    feature = "stm32f427",
+    feature = "stm32f429",
+    feature = "stm32f437",
+    feature = "stm32f439",
+    feature = "stm32f446",
+    feature = "stm32f469",
+    feature = "stm32f479"
+))]
+ti2_trigger!(TIM8,);
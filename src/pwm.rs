@@ -21,7 +21,12 @@ use core::{marker::PhantomData, mem::MaybeUninit};
     feature = "stm32f479"
 ))]
 use crate::stm32::{TIM1, TIM11, TIM5, TIM9};
-use crate::{bb, hal, rcc::Clocks, stm32::RCC, time::Hertz};
+use crate::{
+    bb, hal,
+    rcc::Clocks,
+    stm32::RCC,
+    time::{Hertz, NanoSeconds},
+};
 
 #[cfg(any(
     feature = "stm32f401",
@@ -72,6 +77,7 @@ use crate::timer::PinC1;
 use crate::timer::PinC2;
 use crate::timer::PinC3;
 use crate::timer::PinC4;
+use crate::timer::{PinBkin, PinC1N, PinC2N, PinC3N};
 
 pub struct C1;
 pub struct C2;
@@ -83,6 +89,142 @@ pub struct PwmChannels<TIM, CHANNELS> {
     _tim: PhantomData<TIM>,
 }
 
+// TIMx_CR1 bits
+const CR1_CEN: u32 = 1 << 0;
+const CR1_CMS: u32 = 0b11 << 5;
+const CR1_CKD: u32 = 0b11 << 8;
+
+/// Counter alignment
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Alignment {
+    /// Up-counting, the output changes at the compare value and at the end of the period
+    Edge = 0,
+    /// Up/down-counting, output compare flags are set while counting down
+    Center1 = 1,
+    /// Up/down-counting, output compare flags are set while counting up
+    Center2 = 2,
+    /// Up/down-counting, output compare flags are set while counting up and down
+    Center3 = 3,
+}
+
+/// PWM output compare mode
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Mode {
+    /// The channel is active while the counter is below the duty
+    Pwm1,
+    /// The channel is inactive while the counter is below the duty
+    Pwm2,
+}
+
+/// Output polarity
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Polarity {
+    ActiveHigh,
+    ActiveLow,
+}
+
+/// PWM configuration for the `timX_with_config` constructors
+///
+/// TIM9 to TIM14 only count up, so they only support `Alignment::Edge`.
+#[derive(Clone, Copy, Debug)]
+pub struct PwmConfig {
+    pub alignment: Alignment,
+    pub mode: Mode,
+    pub polarity: Polarity,
+}
+
+impl PwmConfig {
+    pub fn alignment(mut self, alignment: Alignment) -> Self {
+        self.alignment = alignment;
+        self
+    }
+
+    pub fn mode(mut self, mode: Mode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    pub fn polarity(mut self, polarity: Polarity) -> Self {
+        self.polarity = polarity;
+        self
+    }
+}
+
+impl Default for PwmConfig {
+    fn default() -> Self {
+        PwmConfig {
+            alignment: Alignment::Edge,
+            mode: Mode::Pwm1,
+            polarity: Polarity::ActiveHigh,
+        }
+    }
+}
+
+impl Mode {
+    /// OCxM value
+    fn ocm(self) -> u8 {
+        match self {
+            Mode::Pwm1 => 6,
+            Mode::Pwm2 => 7,
+        }
+    }
+}
+
+/// Prescaler and auto-reload values for `freq`. A center-aligned counter counts up and down,
+/// so it needs two counter periods for one PWM period.
+fn timing(clk: u32, freq: Hertz, center_aligned: bool) -> (u16, u16) {
+    let ticks = clk / freq.0 / if center_aligned { 2 } else { 1 };
+    let psc = u16((ticks - 1) / (1 << 16)).unwrap();
+    let arr = u16(ticks / u32(psc + 1)).unwrap();
+    (psc, arr)
+}
+
+/// CCxP bits of the `used` channels for `polarity`
+fn polarity_bits(used: [bool; 4], polarity: Polarity) -> u32 {
+    let mut ccer = 0;
+    if polarity == Polarity::ActiveLow {
+        for (i, &used) in used.iter().enumerate() {
+            if used {
+                ccer |= 1 << (4 * i + 1);
+            }
+        }
+    }
+    ccer
+}
+
+macro_rules! pwm_frequency_impl {
+    ($CHANNELS:ident, $TIMX:ident, $pclk:ident, $ppre:ident) => {
+        impl<CHANNEL> $CHANNELS<$TIMX, CHANNEL> {
+            /// Changes the PWM frequency of all channels of the timer
+            ///
+            /// Prescaler and period are preloaded, so the running period is completed with the
+            /// old values. The duties are not scaled to the new period.
+            pub fn set_frequency<T>(&mut self, freq: T, clocks: &Clocks)
+            where
+                T: Into<Hertz>,
+            {
+                let tim = unsafe { &*$TIMX::ptr() };
+                let clk = clocks.$pclk().0 * if clocks.$ppre() == 1 { 1 } else { 2 };
+                let center_aligned = tim.cr1.read().bits() & CR1_CMS != 0;
+                let (psc, arr) = timing(clk, freq.into(), center_aligned);
+
+                // Block update events until both registers are written
+                tim.cr1.modify(|_, w| w.udis().set_bit());
+                tim.psc.write(|w| w.psc().bits(psc));
+                tim.arr.write(|w| unsafe { w.bits(u32(arr)) });
+                tim.cr1.modify(|_, w| w.udis().clear_bit());
+            }
+
+            /// Changes the period in timer ticks, which is also the maximum duty. Takes effect
+            /// at the end of the running period.
+            pub fn set_period(&mut self, period: u16) {
+                //NOTE(unsafe) atomic write with no side effects
+                unsafe { (*$TIMX::ptr()).arr.write(|w| w.bits(u32(period))) }
+            }
+        }
+    };
+}
+
 macro_rules! pins_impl {
     ( $( ( $($PINX:ident),+ ), ( $($TRAIT:ident),+ ), ( $($ENCHX:ident),* ); )+ ) => {
         $(
@@ -154,9 +296,23 @@ macro_rules! brk {
 }
 
 macro_rules! pwm_all_channels {
-    ($($TIMX:ident: ($timX:ident, $apbenr:ident, $apbrstr:ident, $bit:expr, $pclk:ident, $ppre:ident),)+) => {
+    ($($TIMX:ident: ($timX:ident, $timX_with_config:ident, $apbenr:ident, $apbrstr:ident, $bit:expr, $pclk:ident, $ppre:ident),)+) => {
         $(
-            pub fn $timX<P, PINS, T>(tim: $TIMX, _pins: PINS, clocks: Clocks, freq: T) -> PINS::Channels
+            pub fn $timX<P, PINS, T>(tim: $TIMX, pins: PINS, clocks: Clocks, freq: T) -> PINS::Channels
+            where
+                PINS: Pins<$TIMX, P>,
+                T: Into<Hertz>,
+            {
+                $timX_with_config(tim, pins, clocks, freq, PwmConfig::default())
+            }
+
+            pub fn $timX_with_config<P, PINS, T>(
+                tim: $TIMX,
+                _pins: PINS,
+                clocks: Clocks,
+                freq: T,
+                config: PwmConfig,
+            ) -> PINS::Channels
             where
                 PINS: Pins<$TIMX, P>,
                 T: Into<Hertz>,
@@ -172,20 +328,40 @@ macro_rules! pwm_all_channels {
                     }
                 }
                 if PINS::C1 {
-                    tim.ccmr1_output()
-                        .modify(|_, w| w.oc1pe().set_bit().oc1m().pwm_mode1() );
+                    tim.ccmr1_output().modify(|_, w| {
+                        let w = w.oc1pe().set_bit();
+                        match config.mode {
+                            Mode::Pwm1 => w.oc1m().pwm_mode1(),
+                            Mode::Pwm2 => w.oc1m().pwm_mode2(),
+                        }
+                    });
                 }
                 if PINS::C2 {
-                    tim.ccmr1_output()
-                        .modify(|_, w| w.oc2pe().set_bit().oc2m().pwm_mode1() );
+                    tim.ccmr1_output().modify(|_, w| {
+                        let w = w.oc2pe().set_bit();
+                        match config.mode {
+                            Mode::Pwm1 => w.oc2m().pwm_mode1(),
+                            Mode::Pwm2 => w.oc2m().pwm_mode2(),
+                        }
+                    });
                 }
                 if PINS::C3 {
-                    tim.ccmr2_output()
-                        .modify(|_, w| w.oc3pe().set_bit().oc3m().pwm_mode1() );
+                    tim.ccmr2_output().modify(|_, w| {
+                        let w = w.oc3pe().set_bit();
+                        match config.mode {
+                            Mode::Pwm1 => w.oc3m().pwm_mode1(),
+                            Mode::Pwm2 => w.oc3m().pwm_mode2(),
+                        }
+                    });
                 }
                 if PINS::C4 {
-                    tim.ccmr2_output()
-                        .modify(|_, w| w.oc4pe().set_bit().oc4m().pwm_mode1() );
+                    tim.ccmr2_output().modify(|_, w| {
+                        let w = w.oc4pe().set_bit();
+                        match config.mode {
+                            Mode::Pwm1 => w.oc4m().pwm_mode1(),
+                            Mode::Pwm2 => w.oc4m().pwm_mode2(),
+                        }
+                    });
                 }
 
                 // The reference manual is a bit ambiguous about when enabling this bit is really
@@ -194,10 +370,8 @@ macro_rules! pwm_all_channels {
                 tim.cr1.modify(|_, w| w.arpe().set_bit());
 
                 let clk = clocks.$pclk().0 * if clocks.$ppre() == 1 { 1 } else { 2 };
-                let ticks = clk / freq.into().0;
-                let psc = u16((ticks - 1) / (1 << 16)).unwrap();
+                let (psc, arr) = timing(clk, freq.into(), config.alignment != Alignment::Edge);
                 tim.psc.write(|w| w.psc().bits(psc) );
-                let arr = u16(ticks / u32(psc + 1)).unwrap();
                 tim.arr.write(|w| unsafe { w.bits(u32(arr)) });
 
                 // Trigger update event to load the registers
@@ -206,9 +380,13 @@ macro_rules! pwm_all_channels {
                 tim.cr1.modify(|_, w| w.urs().clear_bit());
 
                 brk!($TIMX, tim);
-                tim.cr1.write(|w|
+                tim.ccer.modify(|r, w| unsafe {
+                    w.bits(r.bits() | polarity_bits([PINS::C1, PINS::C2, PINS::C3, PINS::C4], config.polarity))
+                });
+
+                tim.cr1.modify(|_, w|
                     w.cms()
-                        .bits(0b00)
+                        .bits(config.alignment as u8)
                         .dir()
                         .clear_bit()
                         .opm()
@@ -220,6 +398,8 @@ macro_rules! pwm_all_channels {
                 unsafe { MaybeUninit::uninit().assume_init() }
             }
 
+            pwm_frequency_impl!(PwmChannels, $TIMX, $pclk, $ppre);
+
             impl hal::PwmPin for PwmChannels<$TIMX, C1> {
                 type Duty = u16;
 
@@ -340,13 +520,32 @@ macro_rules! pwm_all_channels {
 }
 
 macro_rules! pwm_2_channels {
-    ($($TIMX:ident: ($timX:ident, $apbenr:ident, $apbrstr:ident, $bit:expr, $pclk:ident, $ppre:ident),)+) => {
+    ($($TIMX:ident: ($timX:ident, $timX_with_config:ident, $apbenr:ident, $apbrstr:ident, $bit:expr, $pclk:ident, $ppre:ident),)+) => {
         $(
-            pub fn $timX<P, PINS, T>(tim: $TIMX, _pins: PINS, clocks: Clocks, freq: T) -> PINS::Channels
+            pub fn $timX<P, PINS, T>(tim: $TIMX, pins: PINS, clocks: Clocks, freq: T) -> PINS::Channels
+            where
+                PINS: Pins<$TIMX, P>,
+                T: Into<Hertz>,
+            {
+                $timX_with_config(tim, pins, clocks, freq, PwmConfig::default())
+            }
+
+            pub fn $timX_with_config<P, PINS, T>(
+                tim: $TIMX,
+                _pins: PINS,
+                clocks: Clocks,
+                freq: T,
+                config: PwmConfig,
+            ) -> PINS::Channels
             where
                 PINS: Pins<$TIMX, P>,
                 T: Into<Hertz>,
             {
+                assert!(
+                    config.alignment == Alignment::Edge,
+                    "only edge-aligned PWM is supported by this timer"
+                );
+
                 {
                     unsafe {
                         //NOTE(unsafe) this reference will only be used for atomic writes with no side effects
@@ -358,15 +557,17 @@ macro_rules! pwm_2_channels {
                     }
                 }
                 if PINS::C1 {
-                    //NOTE(unsafe) 6 is a valid value to write to oc1m
+                    //NOTE(unsafe) 6 and 7 are valid values to write to oc1m
                     unsafe {
-                        tim.ccmr1_output().modify(|_, w| w.oc1pe().set_bit().oc1m().bits(6));
+                        tim.ccmr1_output()
+                            .modify(|_, w| w.oc1pe().set_bit().oc1m().bits(config.mode.ocm()));
                     }
                 }
                 if PINS::C2 {
-                    //NOTE(unsafe) 6 is a valid value to write to oc2m
+                    //NOTE(unsafe) 6 and 7 are valid values to write to oc2m
                     unsafe {
-                        tim.ccmr1_output().modify(|_, w| w.oc2pe().set_bit().oc2m().bits(6));
+                        tim.ccmr1_output()
+                            .modify(|_, w| w.oc2pe().set_bit().oc2m().bits(config.mode.ocm()));
                     }
                 }
 
@@ -376,10 +577,8 @@ macro_rules! pwm_2_channels {
                 tim.cr1.modify(|_, w| w.arpe().set_bit());
 
                 let clk = clocks.$pclk().0 * if clocks.$ppre() == 1 { 1 } else { 2 };
-                let ticks = clk / freq.into().0;
-                let psc = u16((ticks - 1) / (1 << 16)).unwrap();
+                let (psc, arr) = timing(clk, freq.into(), config.alignment != Alignment::Edge);
                 tim.psc.write(|w| w.psc().bits(psc) );
-                let arr = u16(ticks / u32(psc + 1)).unwrap();
                 tim.arr.write(|w| unsafe { w.bits(u32(arr)) });
 
                 // Trigger update event to load the registers
@@ -387,7 +586,11 @@ macro_rules! pwm_2_channels {
                 tim.egr.write(|w| w.ug().set_bit());
                 tim.cr1.modify(|_, w| w.urs().clear_bit());
 
-                tim.cr1.write(|w|
+                tim.ccer.modify(|r, w| unsafe {
+                    w.bits(r.bits() | polarity_bits([PINS::C1, PINS::C2, PINS::C3, PINS::C4], config.polarity))
+                });
+
+                tim.cr1.modify(|_, w|
                     w.opm()
                         .clear_bit()
                         .cen()
@@ -397,6 +600,8 @@ macro_rules! pwm_2_channels {
                 unsafe { MaybeUninit::uninit().assume_init() }
             }
 
+            pwm_frequency_impl!(PwmChannels, $TIMX, $pclk, $ppre);
+
             impl hal::PwmPin for PwmChannels<$TIMX, C1> {
                 type Duty = u16;
 
@@ -459,13 +664,32 @@ macro_rules! pwm_2_channels {
 }
 
 macro_rules! pwm_1_channel {
-    ($($TIMX:ident: ($timX:ident, $apbenr:ident, $apbrstr:ident, $bit:expr, $pclk:ident, $ppre:ident),)+) => {
+    ($($TIMX:ident: ($timX:ident, $timX_with_config:ident, $apbenr:ident, $apbrstr:ident, $bit:expr, $pclk:ident, $ppre:ident),)+) => {
         $(
-            pub fn $timX<P, PINS, T>(tim: $TIMX, _pins: PINS, clocks: Clocks, freq: T) -> PINS::Channels
+            pub fn $timX<P, PINS, T>(tim: $TIMX, pins: PINS, clocks: Clocks, freq: T) -> PINS::Channels
+            where
+                PINS: Pins<$TIMX, P>,
+                T: Into<Hertz>,
+            {
+                $timX_with_config(tim, pins, clocks, freq, PwmConfig::default())
+            }
+
+            pub fn $timX_with_config<P, PINS, T>(
+                tim: $TIMX,
+                _pins: PINS,
+                clocks: Clocks,
+                freq: T,
+                config: PwmConfig,
+            ) -> PINS::Channels
             where
                 PINS: Pins<$TIMX, P>,
                 T: Into<Hertz>,
             {
+                assert!(
+                    config.alignment == Alignment::Edge,
+                    "only edge-aligned PWM is supported by this timer"
+                );
+
                 {
                     unsafe {
                         //NOTE(unsafe) this reference will only be used for atomic writes with no side effects
@@ -477,10 +701,10 @@ macro_rules! pwm_1_channel {
                     }
                 }
                 if PINS::C1 {
-                    //NOTE(unsafe) 6 is a valid value to write to oc1m
+                    //NOTE(unsafe) 6 and 7 are valid values to write to oc1m
                     unsafe {
                         tim.ccmr1_output()
-                            .modify(|_, w| w.oc1pe().set_bit().oc1m().bits(6));
+                            .modify(|_, w| w.oc1pe().set_bit().oc1m().bits(config.mode.ocm()));
                     }
                 }
 
@@ -490,10 +714,8 @@ macro_rules! pwm_1_channel {
                 tim.cr1.modify(|_, w| w.arpe().set_bit());
 
                 let clk = clocks.$pclk().0 * if clocks.$ppre() == 1 { 1 } else { 2 };
-                let ticks = clk / freq.into().0;
-                let psc = u16((ticks - 1) / (1 << 16)).unwrap();
+                let (psc, arr) = timing(clk, freq.into(), config.alignment != Alignment::Edge);
                 tim.psc.write(|w| w.psc().bits(psc) );
-                let arr = u16(ticks / u32(psc + 1)).unwrap();
                 tim.arr.write(|w| unsafe { w.bits(u32(arr)) });
 
                 // Trigger update event to load the registers
@@ -501,14 +723,17 @@ macro_rules! pwm_1_channel {
                 tim.egr.write(|w| w.ug().set_bit());
                 tim.cr1.modify(|_, w| w.urs().clear_bit());
 
-                tim.cr1.write(|w|
-                    w.cen()
-                        .set_bit()
-                );
+                tim.ccer.modify(|r, w| unsafe {
+                    w.bits(r.bits() | polarity_bits([PINS::C1, PINS::C2, PINS::C3, PINS::C4], config.polarity))
+                });
+
+                tim.cr1.modify(|_, w| w.cen().set_bit());
                 //NOTE(unsafe) `PINS::Channels` is a ZST
                 unsafe { MaybeUninit::uninit().assume_init() }
             }
 
+            pwm_frequency_impl!(PwmChannels, $TIMX, $pclk, $ppre);
+
             impl hal::PwmPin for PwmChannels<$TIMX, C1> {
                 type Duty = u16;
 
@@ -543,9 +768,23 @@ macro_rules! pwm_1_channel {
 
 #[cfg(feature = "stm32f410")]
 macro_rules! pwm_tim5_f410 {
-    ($($TIMX:ident: ($timX:ident, $apbenr:ident, $apbrstr:ident, $bit:expr, $pclk:ident, $ppre:ident),)+) => {
+    ($($TIMX:ident: ($timX:ident, $timX_with_config:ident, $apbenr:ident, $apbrstr:ident, $bit:expr, $pclk:ident, $ppre:ident),)+) => {
         $(
-            pub fn $timX<P, PINS, T>(tim: $TIMX, _pins: PINS, clocks: Clocks, freq: T) -> PINS::Channels
+            pub fn $timX<P, PINS, T>(tim: $TIMX, pins: PINS, clocks: Clocks, freq: T) -> PINS::Channels
+            where
+                PINS: Pins<$TIMX, P>,
+                T: Into<Hertz>,
+            {
+                $timX_with_config(tim, pins, clocks, freq, PwmConfig::default())
+            }
+
+            pub fn $timX_with_config<P, PINS, T>(
+                tim: $TIMX,
+                _pins: PINS,
+                clocks: Clocks,
+                freq: T,
+                config: PwmConfig,
+            ) -> PINS::Channels
             where
                 PINS: Pins<$TIMX, P>,
                 T: Into<Hertz>,
@@ -561,20 +800,40 @@ macro_rules! pwm_tim5_f410 {
                     }
                 }
                 if PINS::C1 {
-                    tim.ccmr1_output()
-                        .modify(|_, w| w.oc1pe().set_bit().oc1m().pwm_mode1() );
+                    tim.ccmr1_output().modify(|_, w| {
+                        let w = w.oc1pe().set_bit();
+                        match config.mode {
+                            Mode::Pwm1 => w.oc1m().pwm_mode1(),
+                            Mode::Pwm2 => w.oc1m().pwm_mode2(),
+                        }
+                    });
                 }
                 if PINS::C2 {
-                    tim.ccmr1_output()
-                        .modify(|_, w| w.oc2pe().set_bit().oc2m().pwm_mode1() );
+                    tim.ccmr1_output().modify(|_, w| {
+                        let w = w.oc2pe().set_bit();
+                        match config.mode {
+                            Mode::Pwm1 => w.oc2m().pwm_mode1(),
+                            Mode::Pwm2 => w.oc2m().pwm_mode2(),
+                        }
+                    });
                 }
                 if PINS::C3 {
-                    tim.ccmr2_output()
-                        .modify(|_, w| w.oc3pe().set_bit().oc3m().pwm_mode1() );
+                    tim.ccmr2_output().modify(|_, w| {
+                        let w = w.oc3pe().set_bit();
+                        match config.mode {
+                            Mode::Pwm1 => w.oc3m().pwm_mode1(),
+                            Mode::Pwm2 => w.oc3m().pwm_mode2(),
+                        }
+                    });
                 }
                 if PINS::C4 {
-                    tim.ccmr2_output()
-                        .modify(|_, w| w.oc4pe().set_bit().oc4m().pwm_mode1() );
+                    tim.ccmr2_output().modify(|_, w| {
+                        let w = w.oc4pe().set_bit();
+                        match config.mode {
+                            Mode::Pwm1 => w.oc4m().pwm_mode1(),
+                            Mode::Pwm2 => w.oc4m().pwm_mode2(),
+                        }
+                    });
                 }
 
                 // The reference manual is a bit ambiguous about when enabling this bit is really
@@ -583,10 +842,8 @@ macro_rules! pwm_tim5_f410 {
                 tim.cr1.modify(|_, w| w.arpe().set_bit());
 
                 let clk = clocks.$pclk().0 * if clocks.$ppre() == 1 { 1 } else { 2 };
-                let ticks = clk / freq.into().0;
-                let psc = u16((ticks - 1) / (1 << 16)).unwrap();
+                let (psc, arr) = timing(clk, freq.into(), config.alignment != Alignment::Edge);
                 tim.psc.write(|w| w.psc().bits(psc) );
-                let arr = u16(ticks / u32(psc + 1)).unwrap();
                 tim.arr.write(|w| unsafe { w.arr_l().bits(arr) });
 
                 // Trigger update event to load the registers
@@ -594,9 +851,13 @@ macro_rules! pwm_tim5_f410 {
                 tim.egr.write(|w| w.ug().set_bit());
                 tim.cr1.modify(|_, w| w.urs().clear_bit());
 
-                tim.cr1.write(|w|
+                tim.ccer.modify(|r, w| unsafe {
+                    w.bits(r.bits() | polarity_bits([PINS::C1, PINS::C2, PINS::C3, PINS::C4], config.polarity))
+                });
+
+                tim.cr1.modify(|_, w|
                     w.cms()
-                        .bits(0b00)
+                        .bits(config.alignment as u8)
                         .dir()
                         .clear_bit()
                         .opm()
@@ -608,6 +869,8 @@ macro_rules! pwm_tim5_f410 {
                 unsafe { MaybeUninit::uninit().assume_init() }
             }
 
+            pwm_frequency_impl!(PwmChannels, $TIMX, $pclk, $ppre);
+
             impl hal::PwmPin for PwmChannels<$TIMX, C1> {
                 type Duty = u16;
 
@@ -747,7 +1010,7 @@ macro_rules! pwm_tim5_f410 {
     feature = "stm32f479"
 ))]
 pwm_all_channels!(
-    TIM1: (tim1, apb2enr, apb2rstr, 0u8, pclk2, ppre2),
+    TIM1: (tim1, tim1_with_config, apb2enr, apb2rstr, 0u8, pclk2, ppre2),
 );
 
 #[cfg(any(
@@ -770,7 +1033,7 @@ pwm_all_channels!(
     feature = "stm32f479"
 ))]
 pwm_2_channels!(
-    TIM9: (tim9, apb2enr, apb2rstr, 16u8, pclk2, ppre2),
+    TIM9: (tim9, tim9_with_config, apb2enr, apb2rstr, 16u8, pclk2, ppre2),
 );
 
 #[cfg(any(
@@ -793,7 +1056,7 @@ pwm_2_channels!(
     feature = "stm32f479"
 ))]
 pwm_1_channel!(
-    TIM11: (tim11, apb2enr, apb2rstr, 18u8, pclk2, ppre2),
+    TIM11: (tim11, tim11_with_config, apb2enr, apb2rstr, 18u8, pclk2, ppre2),
 );
 
 #[cfg(any(
@@ -815,10 +1078,10 @@ pwm_1_channel!(
     feature = "stm32f479"
 ))]
 pwm_all_channels!(
-    TIM2: (tim2, apb1enr, apb1rstr, 0u8, pclk1, ppre1),
-    TIM3: (tim3, apb1enr, apb1rstr, 1u8, pclk1, ppre1),
-    TIM4: (tim4, apb1enr, apb1rstr, 2u8, pclk1, ppre1),
-    TIM5: (tim5, apb1enr, apb1rstr, 3u8, pclk1, ppre1),
+    TIM2: (tim2, tim2_with_config, apb1enr, apb1rstr, 0u8, pclk1, ppre1),
+    TIM3: (tim3, tim3_with_config, apb1enr, apb1rstr, 1u8, pclk1, ppre1),
+    TIM4: (tim4, tim4_with_config, apb1enr, apb1rstr, 2u8, pclk1, ppre1),
+    TIM5: (tim5, tim5_with_config, apb1enr, apb1rstr, 3u8, pclk1, ppre1),
 );
 
 #[cfg(any(
@@ -840,7 +1103,7 @@ pwm_all_channels!(
     feature = "stm32f479"
 ))]
 pwm_1_channel!(
-    TIM10: (tim10, apb2enr, apb2rstr, 17u8, pclk2, ppre2),
+    TIM10: (tim10, tim10_with_config, apb2enr, apb2rstr, 17u8, pclk2, ppre2),
 );
 
 #[cfg(any(
@@ -860,7 +1123,7 @@ pwm_1_channel!(
     feature = "stm32f479"
 ))]
 pwm_all_channels!(
-    TIM8: (tim8, apb2enr, apb2rstr, 1u8, pclk2, ppre2),
+    TIM8: (tim8, tim8_with_config, apb2enr, apb2rstr, 1u8, pclk2, ppre2),
 );
 
 #[cfg(any(
@@ -880,7 +1143,7 @@ pwm_all_channels!(
     feature = "stm32f479"
 ))]
 pwm_2_channels!(
-    TIM12: (tim12, apb1enr, apb1rstr, 6u8, pclk1, ppre1),
+    TIM12: (tim12, tim12_with_config, apb1enr, apb1rstr, 6u8, pclk1, ppre1),
 );
 
 #[cfg(any(
@@ -900,11 +1163,543 @@ pwm_2_channels!(
     feature = "stm32f479"
 ))]
 pwm_1_channel!(
-    TIM13: (tim13, apb1enr, apb1rstr, 7u8, pclk1, ppre1),
-    TIM14: (tim14, apb1enr, apb1rstr, 8u8, pclk1, ppre1),
+    TIM13: (tim13, tim13_with_config, apb1enr, apb1rstr, 7u8, pclk1, ppre1),
+    TIM14: (tim14, tim14_with_config, apb1enr, apb1rstr, 8u8, pclk1, ppre1),
 );
 
 #[cfg(feature = "stm32f410")]
 pwm_tim5_f410!(
-    TIM5: (tim5, apb1enr, apb1rstr, 3u8, pclk1, ppre1),
+    TIM5: (tim5, tim5_with_config, apb1enr, apb1rstr, 3u8, pclk1, ppre1),
+);
+
+// Complementary outputs of the advanced-control timers TIM1 and TIM8
+//
+// The break input of the STM32F4 has no digital filter, glitches on BKIN have to be filtered
+// externally.
+
+// TIMx_BDTR bits
+const BDTR_DTG: u32 = 0xff;
+const BDTR_LOCK: u32 = 0b11 << 8;
+const BDTR_OSSI: u32 = 1 << 10;
+const BDTR_OSSR: u32 = 1 << 11;
+const BDTR_BKE: u32 = 1 << 12;
+const BDTR_BKP: u32 = 1 << 13;
+const BDTR_AOE: u32 = 1 << 14;
+const BDTR_MOE: u32 = 1 << 15;
+
+// Break interrupt flag and enable, same position in TIMx_SR and TIMx_DIER
+const BIF: u8 = 7;
+
+/// Complementary output pins for the channels `P` of a `Pins` set
+pub trait ComplementaryPins<TIM, P> {
+    type Channels;
+}
+
+/// A PWM channel driving both the CHx and the CHxN output
+pub struct ComplementaryPwmChannels<TIM, CHANNELS> {
+    _channel: PhantomData<CHANNELS>,
+    _tim: PhantomData<TIM>,
+}
+
+macro_rules! complementary_pins_impl {
+    ( $( ( $($PINX:ident),+ ), ( $($TRAIT:ident),+ ), ( $($ENCHX:ident),* ); )+ ) => {
+        $(
+            #[allow(unused_parens)]
+            impl<TIM, $($PINX,)+> ComplementaryPins<TIM, ($($ENCHX),+)> for ($($PINX),+)
+            where
+                $($PINX: $TRAIT<TIM>,)+
+            {
+                type Channels = ($(ComplementaryPwmChannels<TIM, $ENCHX>),+);
+            }
+        )+
+    };
+}
+
+complementary_pins_impl!(
+    (P1, P2, P3), (PinC1N, PinC2N, PinC3N), (C1, C2, C3);
+    (P2, P3), (PinC2N, PinC3N), (C2, C3);
+    (P1, P3), (PinC1N, PinC3N), (C1, C3);
+    (P1, P2), (PinC1N, PinC2N), (C1, C2);
+    (P1), (PinC1N), (C1);
+    (P2), (PinC2N), (C2);
+    (P3), (PinC3N), (C3);
+);
+
+/// Active level of the break input
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BreakPolarity {
+    ActiveLow,
+    ActiveHigh,
+}
+
+/// Write protection of the timer configuration, see `BreakDeadTime::lock`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LockLevel {
+    Off = 0,
+    /// Locks dead time, break, AOE and idle states
+    Level1 = 1,
+    /// Level 1 plus OSSI/OSSR and the output polarities
+    Level2 = 2,
+    /// Level 2 plus the output compare modes and preloads
+    Level3 = 3,
+}
+
+/// Configuration of the complementary outputs
+#[derive(Clone, Copy, Debug)]
+pub struct ComplementaryConfig {
+    /// Delay between switching off one output of a pair and switching on the other one
+    pub dead_time: NanoSeconds,
+    /// Level of the CHx outputs while the main output is disabled (OISx)
+    pub idle_high: bool,
+    /// Level of the CHxN outputs while the main output is disabled (OISxN)
+    pub idle_high_n: bool,
+    /// Drive the idle levels instead of releasing the outputs while the main output is
+    /// disabled (OSSI)
+    pub off_state_idle: bool,
+    /// Drive the inactive level instead of releasing a disabled output of a running channel
+    /// (OSSR)
+    pub off_state_run: bool,
+    /// Re-enable the main output at the next update event after a break (AOE)
+    pub automatic_output: bool,
+    /// Alignment, mode and polarity, the polarity applies to both outputs of a channel
+    pub pwm: PwmConfig,
+}
+
+impl ComplementaryConfig {
+    pub fn dead_time(mut self, dead_time: NanoSeconds) -> Self {
+        self.dead_time = dead_time;
+        self
+    }
+
+    pub fn idle_high(mut self, idle_high: bool) -> Self {
+        self.idle_high = idle_high;
+        self
+    }
+
+    pub fn idle_high_n(mut self, idle_high_n: bool) -> Self {
+        self.idle_high_n = idle_high_n;
+        self
+    }
+
+    pub fn off_state_idle(mut self, off_state_idle: bool) -> Self {
+        self.off_state_idle = off_state_idle;
+        self
+    }
+
+    pub fn off_state_run(mut self, off_state_run: bool) -> Self {
+        self.off_state_run = off_state_run;
+        self
+    }
+
+    pub fn automatic_output(mut self, automatic_output: bool) -> Self {
+        self.automatic_output = automatic_output;
+        self
+    }
+
+    pub fn pwm(mut self, pwm: PwmConfig) -> Self {
+        self.pwm = pwm;
+        self
+    }
+}
+
+impl Default for ComplementaryConfig {
+    fn default() -> Self {
+        ComplementaryConfig {
+            dead_time: NanoSeconds(0),
+            idle_high: false,
+            idle_high_n: false,
+            off_state_idle: false,
+            off_state_run: false,
+            automatic_output: false,
+            pwm: PwmConfig::default(),
+        }
+    }
+}
+
+/// The dead time is too long for the timer clock
+#[derive(Debug)]
+pub struct InvalidConfig;
+
+/// Break and dead-time control of an advanced-control timer
+pub struct BreakDeadTime<TIM> {
+    _tim: PhantomData<TIM>,
+    clk: Hertz,
+}
+
+/// Dead time in timer clock cycles, rounded up
+fn dead_time_ticks(clk: u32, dead_time: NanoSeconds) -> u32 {
+    ((u64::from(dead_time.0) * u64::from(clk) + 999_999_999) / 1_000_000_000) as u32
+}
+
+/// Returns the clock division (CKD) and the dead-time generator setup (DTG) for at least
+/// `ticks` timer clock cycles, if the dead-time generator can reach it
+fn dead_time_bits(ticks: u32) -> Result<(u8, u8), InvalidConfig> {
+    for &ckd in [0u8, 1, 2].iter() {
+        // In dead-time clock cycles, which are 1, 2 or 4 timer clock cycles
+        let t = (ticks + (1 << ckd) - 1) >> ckd;
+        let dtg = match t {
+            0..=127 => t,
+            128..=254 => 0b1000_0000 | ((t + 1) / 2 - 64),
+            255..=504 => 0b1100_0000 | ((t + 7) / 8 - 32),
+            505..=1008 => 0b1110_0000 | ((t + 15) / 16 - 32),
+            _ => continue,
+        };
+        return Ok((ckd, dtg as u8));
+    }
+    Err(InvalidConfig)
+}
+
+macro_rules! pwm_complementary {
+    ($($TIMX:ident: ($timX:ident, $apbenr:ident, $apbrstr:ident, $bit:expr, $pclk:ident, $ppre:ident),)+) => {
+        $(
+            /// Configures PWM on channels 1 to 3 with complementary outputs
+            ///
+            /// The main output (MOE) is enabled, the channels are enabled with `PwmPin::enable`.
+            /// Returns `InvalidConfig` if the dead time is too long for the timer clock.
+            pub fn $timX<P, PINS, NPINS, T>(
+                tim: $TIMX,
+                _pins: PINS,
+                _npins: NPINS,
+                clocks: Clocks,
+                freq: T,
+                config: ComplementaryConfig,
+            ) -> Result<(NPINS::Channels, BreakDeadTime<$TIMX>), InvalidConfig>
+            where
+                PINS: Pins<$TIMX, P>,
+                NPINS: ComplementaryPins<$TIMX, P>,
+                T: Into<Hertz>,
+            {
+                let clk = clocks.$pclk().0 * if clocks.$ppre() == 1 { 1 } else { 2 };
+                let (ckd, dtg) = dead_time_bits(dead_time_ticks(clk, config.dead_time))?;
+
+                unsafe {
+                    //NOTE(unsafe) this reference will only be used for atomic writes with no side effects
+                    let rcc = &(*RCC::ptr());
+                    // Enable and reset the timer peripheral, it's the same bit position for both registers
+                    bb::set(&rcc.$apbenr, $bit);
+                    bb::set(&rcc.$apbrstr, $bit);
+                    bb::clear(&rcc.$apbrstr, $bit);
+                }
+
+                if PINS::C1 {
+                    tim.ccmr1_output().modify(|_, w| {
+                        let w = w.oc1pe().set_bit();
+                        match config.pwm.mode {
+                            Mode::Pwm1 => w.oc1m().pwm_mode1(),
+                            Mode::Pwm2 => w.oc1m().pwm_mode2(),
+                        }
+                    });
+                }
+                if PINS::C2 {
+                    tim.ccmr1_output().modify(|_, w| {
+                        let w = w.oc2pe().set_bit();
+                        match config.pwm.mode {
+                            Mode::Pwm1 => w.oc2m().pwm_mode1(),
+                            Mode::Pwm2 => w.oc2m().pwm_mode2(),
+                        }
+                    });
+                }
+                if PINS::C3 {
+                    tim.ccmr2_output().modify(|_, w| {
+                        let w = w.oc3pe().set_bit();
+                        match config.pwm.mode {
+                            Mode::Pwm1 => w.oc3m().pwm_mode1(),
+                            Mode::Pwm2 => w.oc3m().pwm_mode2(),
+                        }
+                    });
+                }
+
+                tim.cr1.modify(|_, w| w.arpe().set_bit());
+
+                let (psc, arr) = timing(clk, freq.into(), config.pwm.alignment != Alignment::Edge);
+                tim.psc.write(|w| w.psc().bits(psc) );
+                tim.arr.write(|w| unsafe { w.bits(u32(arr)) });
+
+                // Trigger update event to load the registers
+                tim.cr1.modify(|_, w| w.urs().set_bit());
+                tim.egr.write(|w| w.ug().set_bit());
+                tim.cr1.modify(|_, w| w.urs().clear_bit());
+
+                // Idle states OISx and OISxN
+                let mut cr2 = 0;
+                for (i, &used) in [PINS::C1, PINS::C2, PINS::C3].iter().enumerate() {
+                    if used && config.idle_high {
+                        cr2 |= 1 << (8 + 2 * i);
+                    }
+                    if used && config.idle_high_n {
+                        cr2 |= 1 << (9 + 2 * i);
+                    }
+                }
+                tim.cr2.modify(|r, w| unsafe { w.bits(r.bits() | cr2) });
+
+                let mut bdtr = u32::from(dtg) | BDTR_MOE;
+                if config.off_state_idle {
+                    bdtr |= BDTR_OSSI;
+                }
+                if config.off_state_run {
+                    bdtr |= BDTR_OSSR;
+                }
+                if config.automatic_output {
+                    bdtr |= BDTR_AOE;
+                }
+                tim.bdtr.write(|w| unsafe { w.bits(bdtr) });
+
+                // Same polarity for CHx and CHxN
+                let polarity = polarity_bits([PINS::C1, PINS::C2, PINS::C3, false], config.pwm.polarity);
+                tim.ccer.modify(|r, w| unsafe { w.bits(r.bits() | polarity | polarity << 2) });
+
+                let cms = (config.pwm.alignment as u32) << 5;
+                tim.cr1.modify(|r, w| unsafe {
+                    w.bits((r.bits() & !(CR1_CKD | CR1_CMS)) | u32::from(ckd) << 8 | cms | CR1_CEN)
+                });
+
+                //NOTE(unsafe) `NPINS::Channels` is a ZST
+                Ok((
+                    unsafe { MaybeUninit::uninit().assume_init() },
+                    BreakDeadTime { _tim: PhantomData, clk: Hertz(clk) },
+                ))
+            }
+
+            pwm_frequency_impl!(ComplementaryPwmChannels, $TIMX, $pclk, $ppre);
+
+            impl BreakDeadTime<$TIMX> {
+                /// Changes the dead time, not possible at lock level 1 and above
+                ///
+                /// Returns `InvalidConfig` and keeps the current dead time if `dead_time` is too
+                /// long for the timer clock.
+                pub fn set_dead_time(
+                    &mut self,
+                    dead_time: NanoSeconds,
+                ) -> Result<(), InvalidConfig> {
+                    let (ckd, dtg) = dead_time_bits(dead_time_ticks(self.clk.0, dead_time))?;
+                    let tim = unsafe { &*$TIMX::ptr() };
+                    tim.cr1.modify(|r, w| unsafe {
+                        w.bits((r.bits() & !CR1_CKD) | u32::from(ckd) << 8)
+                    });
+                    tim.bdtr.modify(|r, w| unsafe {
+                        w.bits((r.bits() & !BDTR_DTG) | u32::from(dtg))
+                    });
+                    Ok(())
+                }
+
+                /// Enables the break input on `pin`. An active break disables the main output
+                /// asynchronously.
+                pub fn enable_break<PIN>(&mut self, _pin: PIN, polarity: BreakPolarity)
+                where
+                    PIN: PinBkin<$TIMX>,
+                {
+                    let bkp = match polarity {
+                        BreakPolarity::ActiveLow => 0,
+                        BreakPolarity::ActiveHigh => BDTR_BKP,
+                    };
+                    let tim = unsafe { &*$TIMX::ptr() };
+                    tim.bdtr.modify(|r, w| unsafe {
+                        w.bits((r.bits() & !BDTR_BKP) | BDTR_BKE | bkp)
+                    });
+                }
+
+                /// Disables the break input
+                pub fn disable_break(&mut self) {
+                    let tim = unsafe { &*$TIMX::ptr() };
+                    tim.bdtr.modify(|r, w| unsafe { w.bits(r.bits() & !BDTR_BKE) });
+                }
+
+                /// Write protects parts of the timer configuration until the next reset. The lock
+                /// level can only be written once.
+                pub fn lock(&mut self, level: LockLevel) {
+                    let tim = unsafe { &*$TIMX::ptr() };
+                    tim.bdtr.modify(|r, w| unsafe {
+                        w.bits((r.bits() & !BDTR_LOCK) | (level as u32) << 8)
+                    });
+                }
+
+                /// Enables the main output (MOE), e.g. after a break
+                pub fn enable_outputs(&mut self) {
+                    let tim = unsafe { &*$TIMX::ptr() };
+                    tim.bdtr.modify(|r, w| unsafe { w.bits(r.bits() | BDTR_MOE) });
+                }
+
+                /// Disables the main output (MOE), the outputs go to their idle state
+                pub fn disable_outputs(&mut self) {
+                    let tim = unsafe { &*$TIMX::ptr() };
+                    tim.bdtr.modify(|r, w| unsafe { w.bits(r.bits() & !BDTR_MOE) });
+                }
+
+                /// Returns `true` if the main output is enabled
+                pub fn outputs_enabled(&self) -> bool {
+                    unsafe { (*$TIMX::ptr()).bdtr.read().bits() & BDTR_MOE != 0 }
+                }
+
+                /// Returns `true` if a break occurred since the flag was last cleared
+                pub fn is_break_flag_set(&self) -> bool {
+                    unsafe { (*$TIMX::ptr()).sr.read().bits() & (1 << BIF) != 0 }
+                }
+
+                /// Clears the break flag
+                pub fn clear_break_flag(&mut self) {
+                    unsafe { (*$TIMX::ptr()).sr.write(|w| w.bits(!(1 << BIF))) }
+                }
+
+                /// Enables the break interrupt
+                pub fn listen_break(&mut self) {
+                    //NOTE(unsafe) atomic write with no side effects
+                    unsafe { bb::set(&(*$TIMX::ptr()).dier, BIF) }
+                }
+
+                /// Disables the break interrupt
+                pub fn unlisten_break(&mut self) {
+                    //NOTE(unsafe) atomic write with no side effects
+                    unsafe { bb::clear(&(*$TIMX::ptr()).dier, BIF) }
+                }
+            }
+
+            impl hal::PwmPin for ComplementaryPwmChannels<$TIMX, C1> {
+                type Duty = u16;
+
+                //NOTE(unsafe) atomic writes with no side effects
+                fn disable(&mut self) {
+                    unsafe {
+                        bb::clear(&(*$TIMX::ptr()).ccer, 0);
+                        bb::clear(&(*$TIMX::ptr()).ccer, 2);
+                    }
+                }
+
+                //NOTE(unsafe) atomic writes with no side effects
+                fn enable(&mut self) {
+                    unsafe {
+                        bb::set(&(*$TIMX::ptr()).ccer, 0);
+                        bb::set(&(*$TIMX::ptr()).ccer, 2);
+                    }
+                }
+
+                //NOTE(unsafe) atomic read with no side effects
+                fn get_duty(&self) -> u16 {
+                    unsafe { (*$TIMX::ptr()).ccr1.read().ccr().bits() as u16 }
+                }
+
+                //NOTE(unsafe) atomic read with no side effects
+                fn get_max_duty(&self) -> u16 {
+                    unsafe { (*$TIMX::ptr()).arr.read().arr().bits() as u16 }
+                }
+
+                //NOTE(unsafe) atomic write with no side effects
+                fn set_duty(&mut self, duty: u16) {
+                    unsafe { (*$TIMX::ptr()).ccr1.write(|w| w.ccr().bits(duty.into())) }
+                }
+            }
+
+            impl hal::PwmPin for ComplementaryPwmChannels<$TIMX, C2> {
+                type Duty = u16;
+
+                //NOTE(unsafe) atomic writes with no side effects
+                fn disable(&mut self) {
+                    unsafe {
+                        bb::clear(&(*$TIMX::ptr()).ccer, 4);
+                        bb::clear(&(*$TIMX::ptr()).ccer, 6);
+                    }
+                }
+
+                //NOTE(unsafe) atomic writes with no side effects
+                fn enable(&mut self) {
+                    unsafe {
+                        bb::set(&(*$TIMX::ptr()).ccer, 4);
+                        bb::set(&(*$TIMX::ptr()).ccer, 6);
+                    }
+                }
+
+                //NOTE(unsafe) atomic read with no side effects
+                fn get_duty(&self) -> u16 {
+                    unsafe { (*$TIMX::ptr()).ccr2.read().ccr().bits() as u16 }
+                }
+
+                //NOTE(unsafe) atomic read with no side effects
+                fn get_max_duty(&self) -> u16 {
+                    unsafe { (*$TIMX::ptr()).arr.read().arr().bits() as u16 }
+                }
+
+                //NOTE(unsafe) atomic write with no side effects
+                fn set_duty(&mut self, duty: u16) {
+                    unsafe { (*$TIMX::ptr()).ccr2.write(|w| w.ccr().bits(duty.into())) }
+                }
+            }
+
+            impl hal::PwmPin for ComplementaryPwmChannels<$TIMX, C3> {
+                type Duty = u16;
+
+                //NOTE(unsafe) atomic writes with no side effects
+                fn disable(&mut self) {
+                    unsafe {
+                        bb::clear(&(*$TIMX::ptr()).ccer, 8);
+                        bb::clear(&(*$TIMX::ptr()).ccer, 10);
+                    }
+                }
+
+                //NOTE(unsafe) atomic writes with no side effects
+                fn enable(&mut self) {
+                    unsafe {
+                        bb::set(&(*$TIMX::ptr()).ccer, 8);
+                        bb::set(&(*$TIMX::ptr()).ccer, 10);
+                    }
+                }
+
+                //NOTE(unsafe) atomic read with no side effects
+                fn get_duty(&self) -> u16 {
+                    unsafe { (*$TIMX::ptr()).ccr3.read().ccr().bits() as u16 }
+                }
+
+                //NOTE(unsafe) atomic read with no side effects
+                fn get_max_duty(&self) -> u16 {
+                    unsafe { (*$TIMX::ptr()).arr.read().arr().bits() as u16 }
+                }
+
+                //NOTE(unsafe) atomic write with no side effects
+                fn set_duty(&mut self, duty: u16) {
+                    unsafe { (*$TIMX::ptr()).ccr3.write(|w| w.ccr().bits(duty.into())) }
+                }
+            }
+        )+
+    };
+}
+
+#[cfg(any(
+    feature = "stm32f401",
+    feature = "stm32f405",
+    feature = "stm32f407",
+    feature = "stm32f410",
+    feature = "stm32f411",
+    feature = "stm32f412",
+    feature = "stm32f413",
+    feature = "stm32f415",
+    feature = "stm32f417",
+    feature = "stm32f423",
+    feature = "stm32f427",
+    feature = "stm32f429",
+    feature = "stm32f437",
+    feature = "stm32f439",
+    feature = "stm32f446",
+    feature = "stm32f469",
+    feature = "stm32f479"
+))]
+pwm_complementary!(
+    TIM1: (tim1_complementary, apb2enr, apb2rstr, 0u8, pclk2, ppre2),
+);
+
+#[cfg(any(
+    feature = "stm32f405",
+    feature = "stm32f407",
+    feature = "stm32f412",
+    feature = "stm32f413",
+    feature = "stm32f415",
+    feature = "stm32f417",
+    feature = "stm32f423",
+    feature = "stm32f427",
+    feature = "stm32f429",
+    feature = "stm32f437",
+    feature = "stm32f439",
+    feature = "stm32f446",
+    feature = "stm32f469",
+    feature = "stm32f479"
+))]
+pwm_complementary!(
+    TIM8: (tim8_complementary, apb2enr, apb2rstr, 1u8, pclk2, ppre2),
 );
@@ -0,0 +1,241 @@
+//! Power control and low-power modes
+//!
+//! In Stop mode all clocks of the 1.2 V domain are stopped and the PLLs and HSE are switched
+//! off. On wakeup the system runs from HSI, so [`Pwr::stop`](struct.Pwr.html#method.stop)
+//! restores the oscillators, PLLs and system clock switch that were active before entering
+//! Stop mode. The prescalers and PLL factors keep their values during Stop mode.
+//!
+//! Waking up from Standby mode is a reset, so [`Pwr::standby`](struct.Pwr.html#method.standby)
+//! never returns. After reset the SBF flag tells whether the device came out of Standby.
+
+use cortex_m::peripheral::SCB;
+
+use crate::bb;
+use crate::stm32::{PWR, RCC};
+
+// PWR_CR bits
+const CR_LPDS: u32 = 1 << 0;
+const CR_PDDS: u32 = 1 << 1;
+const CR_CWUF: u32 = 1 << 2;
+const CR_CSBF: u32 = 1 << 3;
+const CR_FPDS: u32 = 1 << 9;
+
+// PWR_CSR bits
+const CSR_WUF: u32 = 1 << 0;
+const CSR_SBF: u32 = 1 << 1;
+const CSR_EWUP: u32 = 1 << 8;
+
+// RCC_CR bits
+const RCC_CR_HSEON: u32 = 1 << 16;
+const RCC_CR_HSERDY: u32 = 1 << 17;
+const RCC_CR_PLLON: u32 = 1 << 24;
+const RCC_CR_PLLRDY: u32 = 1 << 25;
+const RCC_CR_PLLI2SON: u32 = 1 << 26;
+const RCC_CR_PLLI2SRDY: u32 = 1 << 27;
+
+/// Voltage regulator state during Stop mode
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Regulator {
+    /// Main regulator stays on, faster wakeup
+    Main,
+    /// Low-power regulator, lower consumption but longer wakeup time
+    LowPower,
+}
+
+/// Stop mode configuration
+#[derive(Clone, Copy, Debug)]
+pub struct StopMode {
+    pub regulator: Regulator,
+    /// Power down the flash memory, which further reduces consumption at the cost of a longer
+    /// wakeup time
+    pub flash_power_down: bool,
+}
+
+impl Default for StopMode {
+    fn default() -> Self {
+        StopMode {
+            regulator: Regulator::LowPower,
+            flash_power_down: false,
+        }
+    }
+}
+
+/// Constrained PWR peripheral
+pub struct Pwr {
+    pwr: PWR,
+}
+
+impl Pwr {
+    /// Enables the PWR clock and takes ownership of the peripheral
+    pub fn new(pwr: PWR) -> Self {
+        unsafe {
+            const EN_BIT: u8 = 28;
+            // NOTE(unsafe) this reference will only be used for atomic writes with no side effects.
+            let rcc = &(*RCC::ptr());
+
+            // Enable clock.
+            bb::set(&rcc.apb1enr, EN_BIT);
+        }
+
+        Pwr { pwr }
+    }
+
+    /// Enters Sleep mode until the next interrupt. Only the core clock is stopped.
+    ///
+    /// If `sleep_on_exit` is set, the core goes back to sleep after returning from the
+    /// interrupt handler, so this returns only after the flag is cleared by an interrupt
+    /// handler.
+    pub fn sleep(&mut self, scb: &mut SCB, sleep_on_exit: bool) {
+        scb.clear_sleepdeep();
+        if sleep_on_exit {
+            scb.set_sleeponexit();
+        } else {
+            scb.clear_sleeponexit();
+        }
+
+        cortex_m::asm::wfi();
+    }
+
+    /// Enters Stop mode until an EXTI line interrupt or event occurs and restores the clock
+    /// configuration that was active before afterwards.
+    ///
+    /// Pending EXTI lines have to be cleared before, otherwise the device does not enter Stop
+    /// mode.
+    pub fn stop(&mut self, scb: &mut SCB, mode: StopMode) {
+        let rcc = unsafe { &*RCC::ptr() };
+
+        // Clock state to restore after wakeup
+        let rcc_cr = rcc.cr.read().bits();
+        let sw = rcc.cfgr.read().bits() & 0b11;
+        #[cfg(any(
+            feature = "stm32f427",
+            feature = "stm32f429",
+            feature = "stm32f437",
+            feature = "stm32f439",
+            feature = "stm32f446",
+            feature = "stm32f469",
+            feature = "stm32f479"
+        ))]
+        let over_drive = self.pwr.cr.read().oden().bit_is_set();
+
+        let lpds = match mode.regulator {
+            Regulator::Main => 0,
+            Regulator::LowPower => CR_LPDS,
+        };
+        let fpds = if mode.flash_power_down { CR_FPDS } else { 0 };
+        self.pwr.cr.modify(|r, w| unsafe {
+            w.bits((r.bits() & !(CR_PDDS | CR_LPDS | CR_FPDS)) | lpds | fpds | CR_CWUF)
+        });
+
+        scb.set_sleepdeep();
+        cortex_m::asm::wfi();
+        scb.clear_sleepdeep();
+
+        // Running from HSI now, bring back the oscillators and PLLs in the order used by
+        // `CFGR::freeze`
+        if rcc_cr & RCC_CR_HSEON != 0 {
+            rcc.cr
+                .modify(|r, w| unsafe { w.bits(r.bits() | RCC_CR_HSEON) });
+            while rcc.cr.read().bits() & RCC_CR_HSERDY == 0 {}
+        }
+
+        if rcc_cr & RCC_CR_PLLON != 0 {
+            rcc.cr
+                .modify(|r, w| unsafe { w.bits(r.bits() | RCC_CR_PLLON) });
+
+            // The over-drive mode is switched off in Stop mode
+            #[cfg(any(
+                feature = "stm32f427",
+                feature = "stm32f429",
+                feature = "stm32f437",
+                feature = "stm32f439",
+                feature = "stm32f446",
+                feature = "stm32f469",
+                feature = "stm32f479"
+            ))]
+            if over_drive {
+                self.pwr.cr.modify(|_, w| w.oden().set_bit());
+                while self.pwr.csr.read().odrdy().bit_is_clear() {}
+                self.pwr.cr.modify(|_, w| w.odswen().set_bit());
+                while self.pwr.csr.read().odswrdy().bit_is_clear() {}
+            }
+
+            while rcc.cr.read().bits() & RCC_CR_PLLRDY == 0 {}
+        }
+
+        if rcc_cr & RCC_CR_PLLI2SON != 0 {
+            rcc.cr
+                .modify(|r, w| unsafe { w.bits(r.bits() | RCC_CR_PLLI2SON) });
+            while rcc.cr.read().bits() & RCC_CR_PLLI2SRDY == 0 {}
+        }
+
+        // Switch back to the previous system clock and wait until the switch took effect
+        rcc.cfgr
+            .modify(|r, w| unsafe { w.bits((r.bits() & !0b11) | sw) });
+        while (rcc.cfgr.read().bits() >> 2) & 0b11 != sw {}
+    }
+
+    /// Enters Standby mode. The device is reset on wakeup by the wakeup pin, an RTC event,
+    /// the independent watchdog or an external reset.
+    pub fn standby(&mut self, scb: &mut SCB) -> ! {
+        // A set wakeup flag would prevent entering Standby mode
+        self.pwr
+            .cr
+            .modify(|r, w| unsafe { w.bits(r.bits() | CR_PDDS | CR_CWUF) });
+        scb.set_sleepdeep();
+
+        loop {
+            cortex_m::asm::wfi();
+        }
+    }
+
+    /// Enables wakeup from Standby mode by a rising edge on the WKUP pin (PA0). The pin is
+    /// forced to input pull-down while enabled.
+    pub fn enable_wakeup_pin(&mut self) {
+        self.pwr
+            .csr
+            .modify(|r, w| unsafe { w.bits(r.bits() | CSR_EWUP) });
+    }
+
+    /// Releases the WKUP pin for general purpose use
+    pub fn disable_wakeup_pin(&mut self) {
+        self.pwr
+            .csr
+            .modify(|r, w| unsafe { w.bits(r.bits() & !CSR_EWUP) });
+    }
+
+    /// Returns `true` if a wakeup event (WKUP pin, RTC alarm, wakeup or timestamp) was
+    /// received (WUF)
+    pub fn is_wakeup_flag_set(&self) -> bool {
+        self.pwr.csr.read().bits() & CSR_WUF != 0
+    }
+
+    /// Clears the wakeup flag (WUF)
+    pub fn clear_wakeup_flag(&mut self) {
+        self.pwr
+            .cr
+            .modify(|r, w| unsafe { w.bits(r.bits() | CR_CWUF) });
+    }
+
+    /// Returns `true` if the device has been in Standby mode (SBF), i.e. the last reset was
+    /// a wakeup from Standby
+    pub fn is_standby_flag_set(&self) -> bool {
+        self.pwr.csr.read().bits() & CSR_SBF != 0
+    }
+
+    /// Clears the standby flag (SBF)
+    pub fn clear_standby_flag(&mut self) {
+        self.pwr
+            .cr
+            .modify(|r, w| unsafe { w.bits(r.bits() | CR_CSBF) });
+    }
+
+    /// Gives access to the PWR registers, e.g. for `rtc::Rtc::new`
+    pub fn regs(&mut self) -> &mut PWR {
+        &mut self.pwr
+    }
+
+    pub fn free(self) -> PWR {
+        self.pwr
+    }
+}
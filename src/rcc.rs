@@ -1,16 +1,39 @@
+use cortex_m::peripheral::SCB;
+
 use crate::stm32::rcc::cfgr::{HPRE_A, SW_A};
 use crate::stm32::RCC;
 
 use crate::time::Hertz;
 
+#[cfg(any(
+    feature = "stm32f405",
+    feature = "stm32f407",
+    feature = "stm32f415",
+    feature = "stm32f417",
+    feature = "stm32f427",
+    feature = "stm32f429",
+    feature = "stm32f437",
+    feature = "stm32f439",
+    feature = "stm32f446",
+    feature = "stm32f469",
+    feature = "stm32f479"
+))]
+use crate::backup::BkpSram;
+
 /// Extension trait that constrains the `RCC` peripheral
 pub trait RccExt {
     /// Constrains the `RCC` peripheral so it plays nicely with the other abstractions
+    ///
+    /// This also reads and clears the reset flags, see `Rcc::reset_flags`.
     fn constrain(self) -> Rcc;
 }
 
 impl RccExt for RCC {
     fn constrain(self) -> Rcc {
+        let reset_flags = ResetFlags::read(&self);
+        // Clear the flags, so that the flags read after the next reset only show its cause
+        self.csr.modify(|_, w| w.rmvf().set_bit());
+
         Rcc {
             cfgr: CFGR {
                 hse: None,
@@ -20,6 +43,21 @@ impl RccExt for RCC {
                 sysclk: None,
                 pll48clk: false,
             },
+            reset_flags,
+            #[cfg(any(
+                feature = "stm32f405",
+                feature = "stm32f407",
+                feature = "stm32f415",
+                feature = "stm32f417",
+                feature = "stm32f427",
+                feature = "stm32f429",
+                feature = "stm32f437",
+                feature = "stm32f439",
+                feature = "stm32f446",
+                feature = "stm32f469",
+                feature = "stm32f479"
+            ))]
+            bkpsram: BkpSram::new(),
         }
     }
 }
@@ -27,6 +65,113 @@ impl RccExt for RCC {
 /// Constrained RCC peripheral
 pub struct Rcc {
     pub cfgr: CFGR,
+    /// Reset flags that were set when the RCC was constrained
+    pub reset_flags: ResetFlags,
+    /// Ownership token of the backup SRAM
+    #[cfg(any(
+        feature = "stm32f405",
+        feature = "stm32f407",
+        feature = "stm32f415",
+        feature = "stm32f417",
+        feature = "stm32f427",
+        feature = "stm32f429",
+        feature = "stm32f437",
+        feature = "stm32f439",
+        feature = "stm32f446",
+        feature = "stm32f469",
+        feature = "stm32f479"
+    ))]
+    pub bkpsram: BkpSram,
+}
+
+// RCC_CSR bits
+const CSR_BORRSTF: u32 = 1 << 25;
+const CSR_PINRSTF: u32 = 1 << 26;
+const CSR_PORRSTF: u32 = 1 << 27;
+const CSR_SFTRSTF: u32 = 1 << 28;
+const CSR_IWDGRSTF: u32 = 1 << 29;
+const CSR_WWDGRSTF: u32 = 1 << 30;
+const CSR_LPWRRSTF: u32 = 1 << 31;
+
+/// Cause of the last reset
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ResetReason {
+    /// Entering Standby or Stop mode caused a reset instead, as configured by the option
+    /// bytes (nRST_STDBY, nRST_STOP)
+    LowPower,
+    /// The window watchdog timed out or was fed too early
+    WindowWatchdog,
+    /// The independent watchdog timed out
+    IndependentWatchdog,
+    /// Software reset, e.g. by `system_reset`
+    Software,
+    /// Power-on or power-down reset
+    PowerOn,
+    /// The supply voltage dropped below the brown-out threshold
+    BrownOut,
+    /// The NRST pin was pulled low externally
+    Pin,
+    /// No reset flag was set, i.e. they were cleared since the last reset
+    Unknown,
+}
+
+/// Reset flags of `RCC_CSR`
+///
+/// The flags are only cleared by software, `RccExt::constrain` clears them after reading.
+/// Every reset also sets the pin flag, as the NRST pin is driven low internally.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ResetFlags {
+    pub low_power: bool,
+    pub window_watchdog: bool,
+    pub independent_watchdog: bool,
+    pub software: bool,
+    pub power_on: bool,
+    pub brown_out: bool,
+    pub pin: bool,
+}
+
+impl ResetFlags {
+    /// Reads the reset flags
+    fn read(rcc: &RCC) -> Self {
+        let csr = rcc.csr.read().bits();
+
+        ResetFlags {
+            low_power: csr & CSR_LPWRRSTF != 0,
+            window_watchdog: csr & CSR_WWDGRSTF != 0,
+            independent_watchdog: csr & CSR_IWDGRSTF != 0,
+            software: csr & CSR_SFTRSTF != 0,
+            power_on: csr & CSR_PORRSTF != 0,
+            brown_out: csr & CSR_BORRSTF != 0,
+            pin: csr & CSR_PINRSTF != 0,
+        }
+    }
+
+    /// Returns the most specific cause of the reset. A power-on reset also sets the brown-out
+    /// flag, so flags are checked from the most to the least specific.
+    pub fn reason(&self) -> ResetReason {
+        if self.low_power {
+            ResetReason::LowPower
+        } else if self.window_watchdog {
+            ResetReason::WindowWatchdog
+        } else if self.independent_watchdog {
+            ResetReason::IndependentWatchdog
+        } else if self.software {
+            ResetReason::Software
+        } else if self.power_on {
+            ResetReason::PowerOn
+        } else if self.brown_out {
+            ResetReason::BrownOut
+        } else if self.pin {
+            ResetReason::Pin
+        } else {
+            ResetReason::Unknown
+        }
+    }
+}
+
+/// Requests a system reset, which is reported as `ResetReason::Software` afterwards
+pub fn system_reset() -> ! {
+    SCB::sys_reset()
 }
 
 /// Built-in high speed clock frequency
@@ -248,6 +393,24 @@ impl CFGR {
         (true, sysclk_on_pll, real_sysclk, Some(Hertz(pll48clk)))
     }
 
+    /// Returns the input frequency of the PLLs after the shared PLLM divider. If the main PLL
+    /// is unused the divider is set up here for PLLI2S, aiming for a 2 MHz input to limit
+    /// jitter.
+    fn pll_input_setup(&self, use_pll: bool) -> Hertz {
+        let rcc = unsafe { &*RCC::ptr() };
+        let pllsrcclk = self.hse.unwrap_or(HSI);
+
+        if !use_pll {
+            let pllm = (pllsrcclk + 1_999_999) / 2_000_000;
+            rcc.pllcfgr.modify(|_, w| unsafe {
+                w.pllm().bits(pllm as u8);
+                w.pllsrc().bit(self.hse.is_some())
+            });
+        }
+        let pllm = u32::from(rcc.pllcfgr.read().pllm().bits());
+        Hertz(pllsrcclk / pllm)
+    }
+
     fn flash_setup(sysclk: u32) {
         use crate::stm32::FLASH;
 
@@ -380,6 +543,8 @@ impl CFGR {
             while rcc.cr.read().pllrdy().bit_is_clear() {}
         }
 
+        let pll_in = self.pll_input_setup(use_pll);
+
         // Set scaling factors
         rcc.cfgr.modify(|_, w| unsafe {
             w.ppre2()
@@ -413,6 +578,7 @@ impl CFGR {
             ppre2,
             sysclk: Hertz(sysclk),
             pll48clk,
+            pll_in,
         };
 
         if self.pll48clk {
@@ -435,6 +601,7 @@ pub struct Clocks {
     ppre2: u8,
     sysclk: Hertz,
     pll48clk: Option<Hertz>,
+    pll_in: Hertz,
 }
 
 impl Clocks {
@@ -473,6 +640,11 @@ impl Clocks {
         self.pll48clk
     }
 
+    /// Returns the input frequency of the main PLL and PLLI2S
+    pub fn pll_in(&self) -> Hertz {
+        self.pll_in
+    }
+
     /// Returns true if the PLL48 clock is within USB
     /// specifications. It is required to use the USB functionality.
     pub fn is_pll48clk_valid(&self) -> bool {
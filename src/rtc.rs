@@ -0,0 +1,737 @@
+//! Real-time clock (RTC)
+//!
+//! The RTC lives in the backup domain. It keeps counting through system resets and, if VBAT is
+//! supplied, while the rest of the MCU is powered off. Calendar values are stored as BCD in the
+//! peripheral, this driver converts them from and to plain binary numbers.
+
+use crate::{
+    backup::BackupRegisters,
+    bb,
+    pac::{EXTI, PWR, RCC, RTC},
+    time::MilliSeconds,
+};
+
+/// Frequency of the external low speed oscillator
+pub const LSE: u32 = 32_768; // Hz
+
+/// Nominal frequency of the internal low speed RC oscillator
+pub const LSI: u32 = 32_000; // Hz
+
+// EXTI lines internally connected to the RTC
+const EXTI_LINE_ALARM: u8 = 17;
+const EXTI_LINE_TIMESTAMP: u8 = 21;
+const EXTI_LINE_WAKEUP: u8 = 22;
+
+const WPR_KEY1: u8 = 0xCA;
+const WPR_KEY2: u8 = 0x53;
+const WPR_LOCK: u8 = 0xFF;
+
+// RTC_ISR bits, only for clearing the `rc_w0` flags which the field API can't do without
+// clearing flags that are set between the read and the write of a `modify`
+const ISR_RSF: u32 = 1 << 5;
+const ISR_INIT: u32 = 1 << 7;
+const ISR_ALRAF: u32 = 1 << 8;
+const ISR_ALRBF: u32 = 1 << 9;
+const ISR_WUTF: u32 = 1 << 10;
+const ISR_TSF: u32 = 1 << 11;
+
+/// RTC clock source
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ClockSource {
+    /// 32.768 kHz crystal connected to OSC32_IN/OSC32_OUT
+    Lse,
+    /// 32.768 kHz external clock fed into OSC32_IN
+    LseBypass,
+    /// Internal low speed RC oscillator
+    Lsi,
+}
+
+impl ClockSource {
+    fn rtcsel(self) -> u8 {
+        match self {
+            ClockSource::Lse | ClockSource::LseBypass => 0b01,
+            ClockSource::Lsi => 0b10,
+        }
+    }
+
+    fn frequency(self) -> u32 {
+        match self {
+            ClockSource::Lse | ClockSource::LseBypass => LSE,
+            ClockSource::Lsi => LSI,
+        }
+    }
+}
+
+/// RTC error
+#[derive(Debug, PartialEq)]
+pub enum Error {
+    /// A date, time or period value was out of range
+    InvalidInputData,
+    #[doc(hidden)]
+    _Extensible,
+}
+
+/// Interrupt events
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Event {
+    /// Alarm A matched (EXTI line 17)
+    AlarmA,
+    /// Alarm B matched (EXTI line 17)
+    AlarmB,
+    /// Periodic wakeup timer elapsed (EXTI line 22)
+    Wakeup,
+    /// Timestamp event (EXTI line 21)
+    Timestamp,
+}
+
+impl Event {
+    fn exti_line(self) -> u8 {
+        match self {
+            Event::AlarmA | Event::AlarmB => EXTI_LINE_ALARM,
+            Event::Wakeup => EXTI_LINE_WAKEUP,
+            Event::Timestamp => EXTI_LINE_TIMESTAMP,
+        }
+    }
+
+    fn set_interrupt(self, regs: &RTC, enable: bool) {
+        regs.cr.modify(|_, w| match self {
+            Event::AlarmA => w.alraie().bit(enable),
+            Event::AlarmB => w.alrbie().bit(enable),
+            Event::Wakeup => w.wutie().bit(enable),
+            Event::Timestamp => w.tsie().bit(enable),
+        });
+    }
+
+    fn isr_bit(self) -> u32 {
+        match self {
+            Event::AlarmA => ISR_ALRAF,
+            Event::AlarmB => ISR_ALRBF,
+            Event::Wakeup => ISR_WUTF,
+            Event::Timestamp => ISR_TSF,
+        }
+    }
+}
+
+/// One of the two RTC alarms
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Alarm {
+    A,
+    B,
+}
+
+/// Time of day in 24 hour format
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Time {
+    /// 0-23
+    pub hours: u8,
+    /// 0-59
+    pub minutes: u8,
+    /// 0-59
+    pub seconds: u8,
+}
+
+impl Time {
+    pub fn new(hours: u8, minutes: u8, seconds: u8) -> Self {
+        Time {
+            hours,
+            minutes,
+            seconds,
+        }
+    }
+
+    fn is_valid(&self) -> bool {
+        self.hours < 24 && self.minutes < 60 && self.seconds < 60
+    }
+
+    fn bits(&self) -> u32 {
+        (bcd2_encode(self.hours) << 16)
+            | (bcd2_encode(self.minutes) << 8)
+            | bcd2_encode(self.seconds)
+    }
+
+    fn from_bits(tr: u32) -> Self {
+        Time {
+            hours: bcd2_decode((tr >> 16) & 0x3f),
+            minutes: bcd2_decode((tr >> 8) & 0x7f),
+            seconds: bcd2_decode(tr & 0x7f),
+        }
+    }
+}
+
+/// Calendar date
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Date {
+    /// 2000-2099
+    pub year: u16,
+    /// 1-12
+    pub month: u8,
+    /// 1-31
+    pub day: u8,
+    /// 1 (Monday) - 7 (Sunday)
+    pub weekday: u8,
+}
+
+impl Date {
+    pub fn new(year: u16, month: u8, day: u8, weekday: u8) -> Self {
+        Date {
+            year,
+            month,
+            day,
+            weekday,
+        }
+    }
+
+    fn is_valid(&self) -> bool {
+        (2000..=2099).contains(&self.year)
+            && (1..=12).contains(&self.month)
+            && self.day >= 1
+            && self.day <= self.days_in_month()
+            && (1..=7).contains(&self.weekday)
+    }
+
+    fn days_in_month(&self) -> u8 {
+        match self.month {
+            // Every fourth year is a leap year between 2000 and 2099, including 2000
+            2 if self.year % 4 == 0 => 29,
+            2 => 28,
+            4 | 6 | 9 | 11 => 30,
+            _ => 31,
+        }
+    }
+
+    fn bits(&self) -> u32 {
+        (bcd2_encode((self.year - 2000) as u8) << 16)
+            | (u32::from(self.weekday) << 13)
+            | (bcd2_encode(self.month) << 8)
+            | bcd2_encode(self.day)
+    }
+
+    fn from_bits(dr: u32) -> Self {
+        Date {
+            year: 2000 + u16::from(bcd2_decode((dr >> 16) & 0xff)),
+            month: bcd2_decode((dr >> 8) & 0x1f),
+            day: bcd2_decode(dr & 0x3f),
+            weekday: ((dr >> 13) & 0x7) as u8,
+        }
+    }
+}
+
+/// Day part of an alarm
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum AlarmDay {
+    /// Day of the month, 1-31
+    Date(u8),
+    /// Day of the week, 1 (Monday) - 7 (Sunday)
+    Weekday(u8),
+    /// Every day (the day is masked out)
+    EveryDay,
+}
+
+/// Selects which time fields are ignored when comparing against the alarm
+///
+/// A masked field is "don't care", e.g. masking everything but the seconds yields an alarm once
+/// every minute.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct AlarmMask {
+    pub hours: bool,
+    pub minutes: bool,
+    pub seconds: bool,
+}
+
+/// Clock driving the periodic wakeup timer
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum WakeupClock {
+    /// RTCCLK / 16
+    RtcDiv16,
+    /// RTCCLK / 8
+    RtcDiv8,
+    /// RTCCLK / 4
+    RtcDiv4,
+    /// RTCCLK / 2
+    RtcDiv2,
+    /// 1 Hz calendar clock (ck_spre)
+    CkSpre,
+    /// 1 Hz calendar clock with 2^16 added to the counter value
+    CkSpreExtended,
+}
+
+impl WakeupClock {
+    fn wucksel(self) -> u8 {
+        match self {
+            WakeupClock::RtcDiv16 => 0b000,
+            WakeupClock::RtcDiv8 => 0b001,
+            WakeupClock::RtcDiv4 => 0b010,
+            WakeupClock::RtcDiv2 => 0b011,
+            WakeupClock::CkSpre => 0b100,
+            WakeupClock::CkSpreExtended => 0b110,
+        }
+    }
+}
+
+/// Smooth calibration cycle period
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CalibrationPeriod {
+    /// 32 second cycle, CALM resolution of 0.954 ppm
+    Seconds32,
+    /// 16 second cycle, CALM\[0\] is ignored
+    Seconds16,
+    /// 8 second cycle, CALM\[1:0\] are ignored
+    Seconds8,
+}
+
+/// Real-time clock peripheral
+pub struct Rtc {
+    regs: RTC,
+    clock_source: ClockSource,
+    prediv_s: u16,
+}
+
+impl Rtc {
+    /// Enables the RTC using the given clock source and configures the prescalers for a 1 Hz
+    /// calendar clock.
+    ///
+    /// Backup domain write protection is disabled through `PWR_CR.DBP` and stays disabled so
+    /// that the RTC registers can be changed later on. If the RTC is already running from
+    /// `clock_source`, e.g. after a system reset, the calendar is left untouched. Otherwise the
+    /// backup domain is reset, which also clears the backup registers.
+    pub fn new(regs: RTC, clock_source: ClockSource, pwr: &mut PWR) -> Self {
+        unsafe {
+            // NOTE(unsafe) this reference will only be used for atomic writes with no side effects.
+            let rcc = &(*RCC::ptr());
+
+            // Enable the PWR clock
+            bb::set(&rcc.apb1enr, 28);
+        }
+
+        // Disable backup domain write protection
+        pwr.cr.modify(|_, w| w.dbp().set_bit());
+        while pwr.cr.read().dbp().bit_is_clear() {}
+
+        // NOTE(unsafe) BDCR and the LSI bits in CSR are not used by the `rcc` module, the
+        // read-modify-writes below can't race with it
+        let rcc = unsafe { &*RCC::ptr() };
+        let bdcr = rcc.bdcr.read();
+        let running = bdcr.rtcen().bit_is_set() && bdcr.rtcsel().bits() == clock_source.rtcsel();
+
+        if !running {
+            // Reset the backup domain, the clock selection can only be changed after a reset
+            rcc.bdcr.modify(|_, w| w.bdrst().set_bit());
+            rcc.bdcr.modify(|_, w| w.bdrst().clear_bit());
+
+            match clock_source {
+                ClockSource::Lse | ClockSource::LseBypass => {
+                    rcc.bdcr.modify(|_, w| {
+                        w.lsebyp()
+                            .bit(clock_source == ClockSource::LseBypass)
+                            .lseon()
+                            .set_bit()
+                    });
+                    while rcc.bdcr.read().lserdy().bit_is_clear() {}
+                }
+                ClockSource::Lsi => {
+                    rcc.csr.modify(|_, w| w.lsion().set_bit());
+                    while rcc.csr.read().lsirdy().bit_is_clear() {}
+                }
+            }
+
+            // Select the clock source and enable the RTC
+            rcc.bdcr.modify(|_, w| {
+                match clock_source {
+                    ClockSource::Lse | ClockSource::LseBypass => w.rtcsel().lse(),
+                    ClockSource::Lsi => w.rtcsel().lsi(),
+                };
+                w.rtcen().set_bit()
+            });
+        } else if clock_source == ClockSource::Lsi {
+            // LSI is in the VCORE domain and is stopped by a system reset
+            rcc.csr.modify(|_, w| w.lsion().set_bit());
+            while rcc.csr.read().lsirdy().bit_is_clear() {}
+        }
+
+        // ck_spre = RTCCLK / ((PREDIV_A + 1) * (PREDIV_S + 1)) = 1 Hz
+        let prediv_a = 127;
+        let prediv_s = (clock_source.frequency() / (u32::from(prediv_a) + 1) - 1) as u16;
+
+        let mut rtc = Rtc {
+            regs,
+            clock_source,
+            prediv_s,
+        };
+
+        if !running || rtc.regs.isr.read().inits().bit_is_clear() {
+            rtc.modify(|regs| {
+                regs.prer
+                    .write(|w| unsafe { w.prediv_a().bits(prediv_a).prediv_s().bits(prediv_s) });
+                // 24 hour format
+                regs.cr.modify(|_, w| w.fmt().clear_bit());
+            });
+        }
+
+        rtc
+    }
+
+    /// Returns the clock source the RTC is running from
+    pub fn clock_source(&self) -> ClockSource {
+        self.clock_source
+    }
+
+    /// Sets the time of day
+    pub fn set_time(&mut self, time: &Time) -> Result<(), Error> {
+        if !time.is_valid() {
+            return Err(Error::InvalidInputData);
+        }
+        self.modify(|regs| regs.tr.write(|w| unsafe { w.bits(time.bits()) }));
+        Ok(())
+    }
+
+    /// Sets the calendar date
+    pub fn set_date(&mut self, date: &Date) -> Result<(), Error> {
+        if !date.is_valid() {
+            return Err(Error::InvalidInputData);
+        }
+        self.modify(|regs| regs.dr.write(|w| unsafe { w.bits(date.bits()) }));
+        Ok(())
+    }
+
+    /// Sets both date and time in one initialization sequence
+    pub fn set_datetime(&mut self, date: &Date, time: &Time) -> Result<(), Error> {
+        if !date.is_valid() || !time.is_valid() {
+            return Err(Error::InvalidInputData);
+        }
+        self.modify(|regs| {
+            regs.tr.write(|w| unsafe { w.bits(time.bits()) });
+            regs.dr.write(|w| unsafe { w.bits(date.bits()) });
+        });
+        Ok(())
+    }
+
+    /// Reads the time of day
+    pub fn get_time(&mut self) -> Time {
+        self.get_datetime().1
+    }
+
+    /// Reads the calendar date
+    pub fn get_date(&mut self) -> Date {
+        self.get_datetime().0
+    }
+
+    /// Reads a consistent date and time pair
+    pub fn get_datetime(&mut self) -> (Date, Time) {
+        self.wait_for_sync();
+        // Reading TR locks the shadow registers until DR is read
+        let tr = self.regs.tr.read().bits();
+        let dr = self.regs.dr.read().bits();
+        (Date::from_bits(dr), Time::from_bits(tr))
+    }
+
+    /// Reads the sub second counter
+    ///
+    /// The counter runs down from `prediv_s()` to 0 once every second, so the fraction of the
+    /// current second is `(prediv_s() - subseconds()) / (prediv_s() + 1)`.
+    pub fn subseconds(&mut self) -> u16 {
+        self.wait_for_sync();
+        let ss = self.regs.ssr.read().ss().bits();
+        // Reading SSR locks the shadow registers, unlock them again
+        let _ = self.regs.tr.read().bits();
+        let _ = self.regs.dr.read().bits();
+        ss
+    }
+
+    /// Returns the synchronous prescaler value, i.e. the sub second resolution minus one
+    pub fn prediv_s(&self) -> u16 {
+        self.prediv_s
+    }
+
+    /// Configures an alarm and enables it
+    ///
+    /// Fields set in `mask` are not compared, as is the day when `AlarmDay::EveryDay` is used.
+    pub fn set_alarm(
+        &mut self,
+        alarm: Alarm,
+        day: AlarmDay,
+        time: &Time,
+        mask: AlarmMask,
+    ) -> Result<(), Error> {
+        if !time.is_valid() {
+            return Err(Error::InvalidInputData);
+        }
+        let day_bits = match day {
+            AlarmDay::Date(date) if (1..=31).contains(&date) => bcd2_encode(date) << 24,
+            AlarmDay::Weekday(weekday) if (1..=7).contains(&weekday) => {
+                (1 << 30) | (u32::from(weekday) << 24)
+            }
+            AlarmDay::EveryDay => 1 << 31,
+            _ => return Err(Error::InvalidInputData),
+        };
+        let mut bits = day_bits | time.bits();
+        if mask.hours {
+            bits |= 1 << 23;
+        }
+        if mask.minutes {
+            bits |= 1 << 15;
+        }
+        if mask.seconds {
+            bits |= 1 << 7;
+        }
+
+        self.write_protected(|regs| {
+            set_alarm_enable(regs, alarm, false);
+            match alarm {
+                Alarm::A => regs.alrmar.write(|w| unsafe { w.bits(bits) }),
+                Alarm::B => regs.alrmbr.write(|w| unsafe { w.bits(bits) }),
+            }
+            set_alarm_enable(regs, alarm, true);
+        });
+        Ok(())
+    }
+
+    /// Sets the sub second part of an alarm
+    ///
+    /// Only the `compare_bits` least significant bits of `subseconds` are compared, 0 disables
+    /// the sub second comparison entirely. Should be called before `set_alarm`, which enables
+    /// the alarm.
+    pub fn set_alarm_subseconds(
+        &mut self,
+        alarm: Alarm,
+        subseconds: u16,
+        compare_bits: u8,
+    ) -> Result<(), Error> {
+        if subseconds > 0x7fff || compare_bits > 15 {
+            return Err(Error::InvalidInputData);
+        }
+        self.write_protected(|regs| {
+            set_alarm_enable(regs, alarm, false);
+            match alarm {
+                Alarm::A => regs
+                    .alrmassr
+                    .write(|w| unsafe { w.maskss().bits(compare_bits).ss().bits(subseconds) }),
+                Alarm::B => regs
+                    .alrmbssr
+                    .write(|w| unsafe { w.maskss().bits(compare_bits).ss().bits(subseconds) }),
+            }
+        });
+        Ok(())
+    }
+
+    /// Disables an alarm
+    pub fn disable_alarm(&mut self, alarm: Alarm) {
+        self.write_protected(|regs| set_alarm_enable(regs, alarm, false));
+    }
+
+    /// Starts the periodic wakeup timer
+    ///
+    /// The wakeup flag is raised every `(reload + 1)` cycles of `clock`.
+    pub fn enable_wakeup_timer(&mut self, clock: WakeupClock, reload: u16) {
+        self.write_protected(|regs| {
+            regs.cr.modify(|_, w| w.wute().clear_bit());
+            while regs.isr.read().wutwf().bit_is_clear() {}
+            regs.wutr.write(|w| unsafe { w.wut().bits(reload) });
+            regs.cr
+                .modify(|_, w| unsafe { w.wucksel().bits(clock.wucksel()).wute().set_bit() });
+        });
+    }
+
+    /// Starts the periodic wakeup timer with the given period
+    ///
+    /// Periods up to 32 s (with LSE) use RTCCLK / 16 for the best resolution, longer periods
+    /// use the 1 Hz calendar clock and are rounded to whole seconds.
+    pub fn enable_wakeup<T>(&mut self, period: T) -> Result<(), Error>
+    where
+        T: Into<MilliSeconds>,
+    {
+        let ms = u64::from(period.into().0);
+        let ticks = ms * u64::from(self.clock_source.frequency() / 16) / 1_000;
+        let (clock, reload) = if (1..=0x1_0000).contains(&ticks) {
+            (WakeupClock::RtcDiv16, ticks - 1)
+        } else {
+            let seconds = (ms + 500) / 1_000;
+            match seconds {
+                1..=0x1_0000 => (WakeupClock::CkSpre, seconds - 1),
+                0x1_0001..=0x2_0000 => (WakeupClock::CkSpreExtended, seconds - 0x1_0001),
+                _ => return Err(Error::InvalidInputData),
+            }
+        };
+        self.enable_wakeup_timer(clock, reload as u16);
+        Ok(())
+    }
+
+    /// Stops the periodic wakeup timer
+    pub fn disable_wakeup(&mut self) {
+        self.write_protected(|regs| regs.cr.modify(|_, w| w.wute().clear_bit()));
+    }
+
+    /// Configures smooth digital calibration
+    ///
+    /// `calm` pulses of RTCCLK are masked out every calibration cycle. Setting `calp` inserts
+    /// one extra pulse every 2^11 cycles (+488.5 ppm) on top of that, which allows speeding the
+    /// clock up. Blocks until a pending recalibration has finished.
+    pub fn set_smooth_calibration(
+        &mut self,
+        calp: bool,
+        calm: u16,
+        period: CalibrationPeriod,
+    ) -> Result<(), Error> {
+        if calm > 0x1ff {
+            return Err(Error::InvalidInputData);
+        }
+
+        self.write_protected(|regs| {
+            while regs.isr.read().recalpf().bit_is_set() {}
+            regs.calr.write(|w| unsafe {
+                w.calp()
+                    .bit(calp)
+                    .calw16()
+                    .bit(period == CalibrationPeriod::Seconds16)
+                    .calw8()
+                    .bit(period == CalibrationPeriod::Seconds8)
+                    .calm()
+                    .bits(calm)
+            });
+        });
+        Ok(())
+    }
+
+    /// Shifts the clock by a fraction of a second to synchronize it with a remote clock
+    ///
+    /// The clock is delayed by `subfs / (prediv_s() + 1)` seconds, and advanced by a full
+    /// second first if `add_one_second` is set.
+    pub fn shift_subseconds(&mut self, add_one_second: bool, subfs: u16) -> Result<(), Error> {
+        if subfs > 0x7fff {
+            return Err(Error::InvalidInputData);
+        }
+
+        self.write_protected(|regs| {
+            while regs.isr.read().shpf().bit_is_set() {}
+            regs.shiftr
+                .write(|w| unsafe { w.add1s().bit(add_one_second).subfs().bits(subfs) });
+        });
+        self.wait_for_sync();
+        Ok(())
+    }
+
+    /// Starts listening for an interrupt event
+    ///
+    /// This enables the event in the RTC and configures the corresponding EXTI line to trigger
+    /// on a rising edge. You will also have to enable the `RTC_ALARM`, `RTC_WKUP` or
+    /// `TAMP_STAMP` interrupt in the NVIC to start receiving events.
+    pub fn listen(&mut self, exti: &mut EXTI, event: Event) {
+        let line = event.exti_line();
+        exti.rtsr
+            .modify(|r, w| unsafe { w.bits(r.bits() | (1 << line)) });
+        exti.ftsr
+            .modify(|r, w| unsafe { w.bits(r.bits() & !(1 << line)) });
+        exti.imr
+            .modify(|r, w| unsafe { w.bits(r.bits() | (1 << line)) });
+
+        self.write_protected(|regs| event.set_interrupt(regs, true));
+    }
+
+    /// Stops listening for an interrupt event
+    pub fn unlisten(&mut self, exti: &mut EXTI, event: Event) {
+        self.write_protected(|regs| event.set_interrupt(regs, false));
+
+        // Both alarms share one EXTI line
+        let line_in_use = match event {
+            Event::AlarmA => self.regs.cr.read().alrbie().bit_is_set(),
+            Event::AlarmB => self.regs.cr.read().alraie().bit_is_set(),
+            _ => false,
+        };
+        if !line_in_use {
+            let line = event.exti_line();
+            exti.imr
+                .modify(|r, w| unsafe { w.bits(r.bits() & !(1 << line)) });
+        }
+    }
+
+    /// Returns `true` if the flag of the given event is set
+    pub fn is_pending(&self, event: Event) -> bool {
+        let isr = self.regs.isr.read();
+        match event {
+            Event::AlarmA => isr.alraf().bit_is_set(),
+            Event::AlarmB => isr.alrbf().bit_is_set(),
+            Event::Wakeup => isr.wutf().bit_is_set(),
+            Event::Timestamp => isr.tsf().bit_is_set(),
+        }
+    }
+
+    /// Clears the RTC flag and the EXTI pending bit of the given event
+    ///
+    /// If the interrupt is not cleared, it will immediately retrigger after the ISR has
+    /// finished.
+    pub fn clear_interrupt(&mut self, event: Event) {
+        clear_flags(&self.regs, event.isr_bit());
+        unsafe { (*EXTI::ptr()).pr.write(|w| w.bits(1 << event.exti_line())) };
+    }
+
+    /// Gives access to the backup registers, which are part of the RTC register block
+    pub fn backup_registers(&mut self) -> BackupRegisters<'_> {
+        BackupRegisters::new_unchecked()
+    }
+
+    /// Releases the RTC peripheral, the RTC keeps running
+    pub fn release(self) -> RTC {
+        self.regs
+    }
+
+    /// Waits until the calendar shadow registers are synchronized
+    fn wait_for_sync(&mut self) {
+        // Unlike the event flags, RSF is write protected
+        self.write_protected(|regs| clear_flags(regs, ISR_RSF));
+        while self.regs.isr.read().rsf().bit_is_clear() {}
+    }
+
+    /// Runs `f` with the register write protection removed
+    fn write_protected<F: FnMut(&RTC)>(&mut self, mut f: F) {
+        self.regs.wpr.write(|w| unsafe { w.key().bits(WPR_KEY1) });
+        self.regs.wpr.write(|w| unsafe { w.key().bits(WPR_KEY2) });
+
+        f(&self.regs);
+
+        self.regs.wpr.write(|w| unsafe { w.key().bits(WPR_LOCK) });
+    }
+
+    /// Runs `f` in initialization mode, which stops the calendar counter
+    fn modify<F: FnMut(&RTC)>(&mut self, mut f: F) {
+        self.write_protected(|regs| {
+            regs.isr.modify(|_, w| w.init().set_bit());
+            while regs.isr.read().initf().bit_is_clear() {}
+
+            f(regs);
+
+            regs.isr.modify(|_, w| w.init().clear_bit());
+        });
+        self.wait_for_sync();
+    }
+}
+
+/// Clears `rc_w0` flags in the ISR without touching the INIT bit
+fn clear_flags(regs: &RTC, mask: u32) {
+    regs.isr
+        .modify(|r, w| unsafe { w.bits(!(mask | ISR_INIT) | (r.bits() & ISR_INIT)) });
+}
+
+/// Enables or disables an alarm, waits until the alarm registers are writable when disabling
+fn set_alarm_enable(regs: &RTC, alarm: Alarm, enable: bool) {
+    match alarm {
+        Alarm::A => {
+            regs.cr.modify(|_, w| w.alrae().bit(enable));
+            if !enable {
+                while regs.isr.read().alrawf().bit_is_clear() {}
+            }
+        }
+        Alarm::B => {
+            regs.cr.modify(|_, w| w.alrbe().bit(enable));
+            if !enable {
+                while regs.isr.read().alrbwf().bit_is_clear() {}
+            }
+        }
+    }
+}
+
+fn bcd2_encode(value: u8) -> u32 {
+    u32::from(((value / 10) << 4) | (value % 10))
+}
+
+fn bcd2_decode(bcd: u32) -> u8 {
+    ((bcd >> 4) * 10 + (bcd & 0xf)) as u8
+}
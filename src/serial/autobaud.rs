@@ -0,0 +1,106 @@
+//! Software baud rate detection
+//!
+//! The F4 USART has no automatic baud rate detection, so the host has to send a `0x55` (`U`)
+//! character with 8 data bits and 1 stop bit, which toggles the line at every bit boundary.
+//! A timer capture channel on the RX pin, configured for both edges, timestamps the ten edges
+//! from the falling edge of the start bit to the rising edge of the stop bit, which are nine
+//! bit times apart.
+//!
+//! The RX pin can't be in the USART and the timer alternate function at the same time, so
+//! either measure on the RX pin before it's handed to `Serial`, or wire a second timer pin to
+//! RX. The capture resolution should be at least 16 times the highest expected baud rate.
+
+use embedded_hal::Capture;
+use nb::block;
+
+use crate::capture;
+use crate::rcc::Clocks;
+use crate::time::{Bps, Hertz};
+
+use super::{brr, Instance, Serial};
+
+/// Number of bit times between the first and the last edge of `0x55`
+const BITS: u32 = 9;
+
+/// Auto-baud error
+#[derive(Debug)]
+pub enum Error {
+    /// The capture channel missed an edge
+    Capture(capture::Error),
+    /// The edges were not equally spaced, i.e. the received character was not `0x55` or the
+    /// measurement started in the middle of a character
+    Pattern,
+}
+
+impl From<capture::Error> for Error {
+    fn from(e: capture::Error) -> Self {
+        Error::Capture(e)
+    }
+}
+
+/// Ticks between two captures, for 16 and 32 bit counters
+fn elapsed(from: u32, to: u32) -> u32 {
+    let ticks = to.wrapping_sub(from);
+    // A 16 bit counter wrapped around
+    if ticks > 0xffff_0000 {
+        ticks.wrapping_add(0x1_0000)
+    } else {
+        ticks
+    }
+}
+
+/// Waits for a `0x55` character on `channel` and returns its baud rate
+///
+/// The channel must capture both edges (`capture::Edge::Both`). A capture that is pending
+/// when this is called is dropped.
+pub fn measure<C>(capture: &mut C, channel: C::Channel) -> Result<Bps, Error>
+where
+    C: Capture<Error = capture::Error, Capture = u32, Time = Hertz>,
+    C::Channel: Copy,
+{
+    // Drop a stale capture
+    let _ = capture.capture(channel);
+
+    let first = block!(capture.capture(channel))?;
+    let mut last = first;
+    let mut bit_times = [0; BITS as usize];
+    for bit_time in bit_times.iter_mut() {
+        let edge = block!(capture.capture(channel))?;
+        *bit_time = elapsed(last, edge);
+        last = edge;
+    }
+
+    let total = elapsed(first, last);
+    let bit = total / BITS;
+    // Every bit must be within a quarter bit of the average
+    if bit == 0
+        || bit_times
+            .iter()
+            .any(|&t| (t as i32 - bit as i32).abs() > (bit / 4) as i32)
+    {
+        return Err(Error::Pattern);
+    }
+
+    let resolution = capture.get_resolution();
+    let baudrate =
+        (u64::from(resolution.0) * u64::from(BITS) + u64::from(total) / 2) / u64::from(total);
+    Ok(Bps(baudrate as u32))
+}
+
+/// Measures the baud rate with [`measure`](fn.measure.html) and applies it to `serial`
+pub fn detect<USART, PINS, C>(
+    serial: &mut Serial<USART, PINS>,
+    capture: &mut C,
+    channel: C::Channel,
+    clocks: &Clocks,
+) -> Result<Bps, Error>
+where
+    USART: Instance,
+    C: Capture<Error = capture::Error, Capture = u32, Time = Hertz>,
+    C::Channel: Copy,
+{
+    let baudrate = measure(capture, channel)?;
+    let div = brr(USART::clock(clocks), baudrate);
+    serial.usart.brr.write(|w| unsafe { w.bits(div) });
+    Ok(baudrate)
+}
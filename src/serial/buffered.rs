@@ -0,0 +1,269 @@
+//! Interrupt driven serial with ring buffers
+//!
+//! [`Buffered`](struct.Buffered.html) moves received bytes into a receive ring buffer and
+//! transmits from a transmit ring buffer in
+//! [`on_interrupt`](struct.Buffered.html#method.on_interrupt), which has to be called from the
+//! USART interrupt handler. The usual way to share it between the handler and the application
+//! is a `cortex_m::interrupt::Mutex<RefCell<Option<Buffered<..>>>>`.
+//!
+//! The receive buffer keeps the error flags next to each byte, so it is a `[u16]`. Errors are
+//! reported by `read` in the position of the affected byte. If the receive buffer is full the
+//! newest byte is dropped and the last buffered one reports `Error::Overrun`.
+//!
+//! The blocking `write` and `fmt::Write` implementations also service the USART while they
+//! wait, so they make progress inside critical sections.
+
+use core::fmt;
+use core::ops::Deref;
+use core::ptr;
+
+use embedded_hal::blocking;
+use embedded_hal::serial;
+
+use crate::stm32::usart1;
+
+use super::{Error, Serial};
+
+// Error flags stored next to the received bytes
+const PARITY: u16 = 1 << 8;
+const FRAMING: u16 = 1 << 9;
+const NOISE: u16 = 1 << 10;
+const OVERRUN: u16 = 1 << 11;
+
+struct Ring<T: 'static> {
+    buf: &'static mut [T],
+    head: usize,
+    len: usize,
+}
+
+impl<T: Copy> Ring<T> {
+    fn new(buf: &'static mut [T]) -> Self {
+        Ring {
+            buf,
+            head: 0,
+            len: 0,
+        }
+    }
+
+    fn is_full(&self) -> bool {
+        self.len == self.buf.len()
+    }
+
+    fn push(&mut self, value: T) -> bool {
+        if self.is_full() {
+            return false;
+        }
+        let idx = (self.head + self.len) % self.buf.len();
+        self.buf[idx] = value;
+        self.len += 1;
+        true
+    }
+
+    fn pop(&mut self) -> Option<T> {
+        if self.len == 0 {
+            return None;
+        }
+        let value = self.buf[self.head];
+        self.head = (self.head + 1) % self.buf.len();
+        self.len -= 1;
+        Some(value)
+    }
+
+    /// Returns the most recently pushed value
+    fn last_mut(&mut self) -> Option<&mut T> {
+        if self.len == 0 {
+            return None;
+        }
+        let idx = (self.head + self.len - 1) % self.buf.len();
+        Some(&mut self.buf[idx])
+    }
+}
+
+/// Serial port with interrupt driven ring buffers
+pub struct Buffered<USART, PINS> {
+    serial: Serial<USART, PINS>,
+    rx: Ring<u16>,
+    tx: Ring<u8>,
+}
+
+impl<USART, PINS> Buffered<USART, PINS>
+where
+    USART: Deref<Target = usart1::RegisterBlock>,
+{
+    /// Takes over `serial` and enables the RXNE interrupt. The USART interrupt still has to be
+    /// unmasked in the NVIC.
+    ///
+    /// # Panics
+    ///
+    /// Panics if one of the buffers is empty.
+    pub fn new(
+        serial: Serial<USART, PINS>,
+        rx_buf: &'static mut [u16],
+        tx_buf: &'static mut [u8],
+    ) -> Self {
+        assert!(!rx_buf.is_empty() && !tx_buf.is_empty());
+
+        serial.usart.cr1.modify(|_, w| w.rxneie().set_bit());
+
+        Buffered {
+            serial,
+            rx: Ring::new(rx_buf),
+            tx: Ring::new(tx_buf),
+        }
+    }
+
+    /// Moves received bytes into the receive buffer and the next byte of the transmit buffer
+    /// into the USART. Call this from the USART interrupt handler.
+    pub fn on_interrupt(&mut self) {
+        let usart = &self.serial.usart;
+        let sr = usart.sr.read();
+
+        if sr.rxne().bit_is_set() || sr.ore().bit_is_set() {
+            // Reading the data register after the status register clears the error flags
+            // NOTE(read_volatile) 8-bit read that's not possible through the svd2rust API
+            let byte = unsafe { ptr::read_volatile(&usart.dr as *const _ as *const u8) };
+
+            let mut entry = u16::from(byte);
+            if sr.pe().bit_is_set() {
+                entry |= PARITY;
+            }
+            if sr.fe().bit_is_set() {
+                entry |= FRAMING;
+            }
+            if sr.nf().bit_is_set() {
+                entry |= NOISE;
+            }
+            if sr.ore().bit_is_set() {
+                entry |= OVERRUN;
+            }
+
+            if !self.rx.push(entry) {
+                if let Some(last) = self.rx.last_mut() {
+                    *last |= OVERRUN;
+                }
+            }
+        }
+
+        if sr.txe().bit_is_set() && usart.cr1.read().txeie().bit_is_set() {
+            match self.tx.pop() {
+                // NOTE(write_volatile) 8-bit write that's not possible through the svd2rust API
+                Some(byte) => unsafe {
+                    ptr::write_volatile(&usart.dr as *const _ as *mut u8, byte)
+                },
+                None => usart.cr1.modify(|_, w| w.txeie().clear_bit()),
+            }
+        }
+    }
+
+    /// Returns the number of received bytes in the buffer, including erroneous ones
+    pub fn received(&self) -> usize {
+        self.rx.len
+    }
+
+    /// Returns `true` if all buffered bytes have been passed to the USART
+    pub fn is_tx_empty(&self) -> bool {
+        self.tx.len == 0
+    }
+
+    /// Drops all received bytes
+    pub fn clear_rx(&mut self) {
+        self.rx.head = 0;
+        self.rx.len = 0;
+    }
+
+    /// Disables the interrupts and returns the serial port. Unsent bytes are dropped.
+    pub fn release(self) -> Serial<USART, PINS> {
+        self.serial
+            .usart
+            .cr1
+            .modify(|_, w| w.rxneie().clear_bit().txeie().clear_bit());
+        self.serial
+    }
+}
+
+impl<USART, PINS> serial::Read<u8> for Buffered<USART, PINS>
+where
+    USART: Deref<Target = usart1::RegisterBlock>,
+{
+    type Error = Error;
+
+    fn read(&mut self) -> nb::Result<u8, Error> {
+        let entry = self.rx.pop().ok_or(nb::Error::WouldBlock)?;
+
+        Err(if entry & PARITY != 0 {
+            nb::Error::Other(Error::Parity)
+        } else if entry & FRAMING != 0 {
+            nb::Error::Other(Error::Framing)
+        } else if entry & NOISE != 0 {
+            nb::Error::Other(Error::Noise)
+        } else if entry & OVERRUN != 0 {
+            nb::Error::Other(Error::Overrun)
+        } else {
+            return Ok(entry as u8);
+        })
+    }
+}
+
+impl<USART, PINS> serial::Write<u8> for Buffered<USART, PINS>
+where
+    USART: Deref<Target = usart1::RegisterBlock>,
+{
+    type Error = Error;
+
+    /// Queues `byte`, returns `WouldBlock` while the transmit buffer is full
+    fn write(&mut self, byte: u8) -> nb::Result<(), Self::Error> {
+        if !self.tx.push(byte) {
+            return Err(nb::Error::WouldBlock);
+        }
+        self.serial.usart.cr1.modify(|_, w| w.txeie().set_bit());
+        Ok(())
+    }
+
+    /// Returns `Ok` once the transmit buffer is empty and the last byte has been sent
+    fn flush(&mut self) -> nb::Result<(), Self::Error> {
+        if self.tx.len == 0 && self.serial.usart.sr.read().tc().bit_is_set() {
+            Ok(())
+        } else {
+            Err(nb::Error::WouldBlock)
+        }
+    }
+}
+
+impl<USART, PINS> blocking::serial::Write<u8> for Buffered<USART, PINS>
+where
+    USART: Deref<Target = usart1::RegisterBlock>,
+{
+    type Error = Error;
+
+    fn bwrite_all(&mut self, bytes: &[u8]) -> Result<(), Self::Error> {
+        for &b in bytes {
+            loop {
+                match serial::Write::write(self, b) {
+                    Err(nb::Error::WouldBlock) => self.on_interrupt(),
+                    Err(nb::Error::Other(err)) => return Err(err),
+                    Ok(()) => break,
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn bflush(&mut self) -> Result<(), Self::Error> {
+        loop {
+            match serial::Write::flush(self) {
+                Ok(()) => return Ok(()),
+                Err(nb::Error::WouldBlock) => self.on_interrupt(),
+                Err(nb::Error::Other(err)) => return Err(err),
+            }
+        }
+    }
+}
+
+impl<USART, PINS> fmt::Write for Buffered<USART, PINS>
+where
+    USART: Deref<Target = usart1::RegisterBlock>,
+{
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        blocking::serial::Write::bwrite_all(self, s.as_bytes()).map_err(|_| fmt::Error)
+    }
+}
@@ -0,0 +1,181 @@
+//! DMA receive of variable-length frames with idle-line detection
+//!
+//! [`IdleRx`](struct.IdleRx.html) lets a DMA stream fill a buffer and ends a frame when the
+//! USART detects an idle line (or the buffer is full). The filled buffer is handed out as a
+//! [`Frame`](struct.Frame.html) without copying, while reception continues in a spare buffer.
+//! Once the frame is processed, its buffer is given back with
+//! [`recycle`](struct.IdleRx.html#method.recycle) and becomes the next spare.
+//!
+//! Without a spare buffer, reception continues in the current buffer and the frame is
+//! delivered at the next idle line together with the following data. If the buffer fills up
+//! without a spare buffer, or on an overrun, the received data is dropped and reception starts
+//! over at the beginning of the buffer.
+//!
+//! [`on_interrupt`](struct.IdleRx.html#method.on_interrupt) has to be called from both the
+//! USART and the DMA stream interrupt handlers.
+
+use core::ptr;
+use core::slice;
+
+use embedded_dma::{ReadBuffer, WriteBuffer};
+
+use crate::dma::config::DmaConfig;
+use crate::dma::traits::{Channel, DMASet, PeriAddress, Stream};
+use crate::dma::{PeripheralToMemory, Transfer};
+
+use super::{Error, Instance, Rx};
+
+/// Received frame
+pub struct Frame<BUF> {
+    buf: BUF,
+    len: usize,
+}
+
+impl<BUF> Frame<BUF>
+where
+    BUF: ReadBuffer<Word = u8>,
+{
+    /// Returns the received bytes
+    pub fn as_slice(&self) -> &[u8] {
+        // NOTE(unsafe) the DMA no longer writes to this buffer and `len` is within its bounds
+        unsafe {
+            let (ptr, _) = self.buf.read_buffer();
+            slice::from_raw_parts(ptr, self.len)
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the buffer, e.g. for `IdleRx::recycle`
+    pub fn into_buffer(self) -> BUF {
+        self.buf
+    }
+}
+
+/// DMA receiver that splits the received data into frames at idle lines
+pub struct IdleRx<STREAM, CHANNEL, USART, BUF>
+where
+    STREAM: Stream,
+    Rx<USART>: PeriAddress<MemSize = u8>,
+    BUF: WriteBuffer<Word = u8> + 'static,
+{
+    transfer: Transfer<STREAM, CHANNEL, Rx<USART>, PeripheralToMemory, BUF>,
+    /// Length of the buffer the DMA writes to
+    len: usize,
+    spare: Option<BUF>,
+}
+
+impl<STREAM, CHANNEL, USART, BUF> IdleRx<STREAM, CHANNEL, USART, BUF>
+where
+    STREAM: Stream,
+    CHANNEL: Channel,
+    USART: Instance,
+    Rx<USART>: PeriAddress<MemSize = u8>,
+    BUF: WriteBuffer<Word = u8> + 'static,
+    (STREAM, CHANNEL, Rx<USART>, PeripheralToMemory): DMASet,
+{
+    /// Starts receiving into `buf` and enables the USART idle line and the DMA transfer
+    /// complete interrupts. Both still have to be unmasked in the NVIC.
+    pub fn new(stream: STREAM, rx: Rx<USART>, mut buf: BUF, spare: BUF) -> Self {
+        let usart = unsafe { &*USART::ptr() };
+        usart.cr3.modify(|_, w| w.dmar().enabled());
+        usart.cr1.modify(|_, w| w.idleie().set_bit());
+
+        // NOTE(unsafe) only the length is used, the buffer is handed over to the DMA below
+        let len = unsafe { buf.write_buffer().1 };
+
+        let config = DmaConfig::default()
+            .memory_increment(true)
+            .transfer_complete_interrupt(true);
+        let mut transfer = Transfer::init(stream, rx, buf, None, config);
+        transfer.start(|_| {});
+
+        IdleRx {
+            transfer,
+            len,
+            spare: Some(spare),
+        }
+    }
+
+    /// Returns a frame when the line went idle or the buffer is full, and `Error::Overrun` when
+    /// data got lost and the partial frame was dropped, also when the buffer is full and there
+    /// is no spare buffer. Call this from the USART and the DMA stream interrupt handlers.
+    pub fn on_interrupt(&mut self) -> nb::Result<Frame<BUF>, Error> {
+        let usart = unsafe { &*USART::ptr() };
+        let sr = usart.sr.read();
+        let full = STREAM::get_transfer_complete_flag();
+
+        if sr.idle().bit_is_set() || sr.ore().bit_is_set() {
+            // Reading the data register after the status register clears IDLE and ORE
+            unsafe { ptr::read_volatile(&usart.dr as *const _ as *const u8) };
+        }
+
+        if sr.ore().bit_is_set() {
+            // Start over with the same buffer
+            // NOTE(unsafe) no double buffering
+            let _ = unsafe { self.transfer.next_transfer_with(|buf, _| (buf, ())) };
+            return Err(nb::Error::Other(Error::Overrun));
+        }
+
+        if !sr.idle().bit_is_set() && !full {
+            return Err(nb::Error::WouldBlock);
+        }
+
+        if self.len == usize::from(STREAM::get_number_of_transfers()) {
+            // Idle line after a frame that filled the whole buffer
+            self.transfer.clear_transfer_complete_interrupt();
+            return Err(nb::Error::WouldBlock);
+        }
+
+        let mut spare = match self.spare.take() {
+            Some(spare) => spare,
+            None if full => {
+                // Nowhere to continue, drop the data and start over with the same buffer
+                // NOTE(unsafe) no double buffering
+                let _ = unsafe { self.transfer.next_transfer_with(|buf, _| (buf, ())) };
+                return Err(nb::Error::Other(Error::Overrun));
+            }
+            // Keep receiving into the current buffer
+            None => return Err(nb::Error::WouldBlock),
+        };
+        // NOTE(unsafe) only the length is used, the buffer is handed over to the DMA below
+        let len = unsafe { spare.write_buffer().1 };
+
+        let current_len = self.len;
+        // NOTE(unsafe) no double buffering
+        let next = unsafe {
+            self.transfer.next_transfer_with(|buf, _| {
+                // The stream is disabled at this point, no more bytes end up in `buf`
+                let received = current_len - usize::from(STREAM::get_number_of_transfers());
+                (spare, (buf, received))
+            })
+        };
+        match next {
+            Ok((buf, received)) => {
+                self.len = len;
+                Ok(Frame { buf, len: received })
+            }
+            Err(_) => Err(nb::Error::WouldBlock),
+        }
+    }
+
+    /// Gives back the buffer of a processed frame to be used as the next spare buffer
+    pub fn recycle(&mut self, buf: BUF) {
+        self.spare = Some(buf);
+    }
+
+    /// Stops receiving and returns the stream, the receiver, the current and the spare buffer
+    pub fn release(self) -> (STREAM, Rx<USART>, BUF, Option<BUF>) {
+        let usart = unsafe { &*USART::ptr() };
+        usart.cr1.modify(|_, w| w.idleie().clear_bit());
+
+        let (stream, rx, buf, _) = self.transfer.free();
+        (stream, rx, buf, self.spare)
+    }
+}
@@ -1,5 +1,6 @@
 use core::fmt;
 use core::marker::PhantomData;
+use core::ops::Deref;
 use core::ptr;
 
 use embedded_hal::blocking;
@@ -374,9 +375,17 @@ use crate::gpio::gpiog::{PG14, PG9};
 use crate::gpio::AF11;
 use crate::gpio::{Alternate, AF7, AF8};
 use crate::rcc::Clocks;
+use crate::stm32::usart1;
+use crate::time::{Bps, Hertz};
 
 use crate::dma::traits::PeriAddress;
 
+pub mod autobaud;
+pub mod buffered;
+pub mod idle_rx;
+pub mod rs485;
+pub mod synchronous;
+
 /// Serial error
 #[derive(Debug)]
 pub enum Error {
@@ -400,8 +409,40 @@ pub enum Event {
     Txe,
     /// Idle line state detected
     Idle,
+    /// The CTS line changed, only on USART1/2/3/6
+    Cts,
+    /// Transmission complete, the last stop bit has been sent
+    Tc,
+    /// LIN break detected
+    LinBreak,
 }
 
+// CR1 bits
+const CR1_SBK: u32 = 1 << 0;
+
+// CR2 bits
+const CR2_LBDL: u32 = 1 << 5;
+const CR2_LBDIE: u32 = 1 << 6;
+const CR2_CLKEN: u32 = 1 << 11;
+const CR2_LINEN: u32 = 1 << 14;
+
+// CR3 bits
+const CR3_IREN: u32 = 1 << 1;
+const CR3_IRLP: u32 = 1 << 2;
+const CR3_HDSEL: u32 = 1 << 3;
+const CR3_NACK: u32 = 1 << 4;
+const CR3_SCEN: u32 = 1 << 5;
+const CR3_RTSE: u32 = 1 << 8;
+const CR3_CTSE: u32 = 1 << 9;
+const CR3_CTSIE: u32 = 1 << 10;
+
+// SR bits
+const SR_LBD: u32 = 1 << 8;
+const SR_CTS: u32 = 1 << 9;
+
+/// Offset of GTPR, which the PAC doesn't provide for all UARTs
+const GTPR_OFFSET: usize = 0x18;
+
 pub mod config {
     use crate::time::Bps;
     use crate::time::U32Ext;
@@ -435,12 +476,58 @@ pub mod config {
         TxRx,
     }
 
+    /// Operating mode
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    pub enum Mode {
+        /// Asynchronous full-duplex
+        FullDuplex,
+        /// Single-wire half-duplex on the TX pin, which has to be configured as open drain.
+        /// Pass `NoRx` as RX pin.
+        HalfDuplex,
+        /// LIN with 8 data bits and 1 stop bit
+        Lin(LinBreakLength),
+        /// IrDA SIR with 1 stop bit. In low-power mode the pulse width is derived from the
+        /// peripheral clock divided by `prescaler`, which must not be 0. In normal mode
+        /// `prescaler` must be 1.
+        IrDA { low_power: bool, prescaler: u8 },
+        /// ISO 7816 smartcard on the TX pin configured as open drain, only on USART1/2/3/6,
+        /// the UARTs return `InvalidConfig`.
+        /// Needs 9 bit words with parity. The clock output on CK runs at
+        /// pclk / (2 * `prescaler`) with `prescaler` in 1..=31, `guard_time` is in baud clocks
+        /// and `nack` enables sending a NACK on parity errors.
+        Smartcard {
+            prescaler: u8,
+            guard_time: u8,
+            nack: bool,
+        },
+    }
+
+    /// Length of the LIN break that is detected
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    pub enum LinBreakLength {
+        Bits10,
+        Bits11,
+    }
+
+    /// Hardware flow control, only on USART1/2/3/6
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    pub enum FlowControl {
+        None,
+        /// Receiver deasserts RTS while the data register is full
+        Rts,
+        /// Transmitter waits for CTS before sending
+        Cts,
+        RtsCts,
+    }
+
     pub struct Config {
         pub baudrate: Bps,
         pub wordlength: WordLength,
         pub parity: Parity,
         pub stopbits: StopBits,
         pub dma: DmaConfig,
+        pub flow_control: FlowControl,
+        pub mode: Mode,
     }
 
     impl Config {
@@ -478,6 +565,18 @@ pub mod config {
             self.stopbits = stopbits;
             self
         }
+
+        /// Enables hardware flow control, which needs the RTS and/or CTS pin passed to the
+        /// constructor
+        pub fn flow_control(mut self, flow_control: FlowControl) -> Self {
+            self.flow_control = flow_control;
+            self
+        }
+
+        pub fn mode(mut self, mode: Mode) -> Self {
+            self.mode = mode;
+            self
+        }
     }
 
     #[derive(Debug)]
@@ -492,14 +591,30 @@ pub mod config {
                 parity: Parity::ParityNone,
                 stopbits: StopBits::STOP1,
                 dma: DmaConfig::None,
+                flow_control: FlowControl::None,
+                mode: Mode::FullDuplex,
             }
         }
     }
 }
 
-pub trait Pins<USART> {}
+pub trait Pins<USART> {
+    #[doc(hidden)]
+    const HAS_RTS: bool = false;
+    #[doc(hidden)]
+    const HAS_CTS: bool = false;
+}
 pub trait PinTx<USART> {}
 pub trait PinRx<USART> {}
+pub trait PinRts<USART> {
+    #[doc(hidden)]
+    const CONNECTED: bool = true;
+}
+pub trait PinCts<USART> {
+    #[doc(hidden)]
+    const CONNECTED: bool = true;
+}
+pub trait PinCk<USART> {}
 
 impl<USART, TX, RX> Pins<USART> for (TX, RX)
 where
@@ -508,10 +623,33 @@ where
 {
 }
 
+/// `(tx, rx, rts, cts)` for hardware flow control
+impl<USART, TX, RX, RTS, CTS> Pins<USART> for (TX, RX, RTS, CTS)
+where
+    TX: PinTx<USART>,
+    RX: PinRx<USART>,
+    RTS: PinRts<USART>,
+    CTS: PinCts<USART>,
+{
+    const HAS_RTS: bool = RTS::CONNECTED;
+    const HAS_CTS: bool = CTS::CONNECTED;
+}
+
 /// A filler type for when the Tx pin is unnecessary
 pub struct NoTx;
 /// A filler type for when the Rx pin is unnecessary
 pub struct NoRx;
+/// A filler type for when the Rts pin is unnecessary
+pub struct NoRts;
+/// A filler type for when the Cts pin is unnecessary
+pub struct NoCts;
+
+impl<USART> PinRts<USART> for NoRts {
+    const CONNECTED: bool = false;
+}
+impl<USART> PinCts<USART> for NoCts {
+    const CONNECTED: bool = false;
+}
 
 #[cfg(any(
     feature = "stm32f401",
@@ -1382,6 +1520,411 @@ impl PinTx<UART10> for PG12<Alternate<AF11>> {}
 #[cfg(any(feature = "stm32f413", feature = "stm32f423"))]
 impl PinRx<UART10> for PG11<Alternate<AF11>> {}
 
+// Hardware flow control pins
+#[cfg(any(
+    feature = "stm32f401",
+    feature = "stm32f405",
+    feature = "stm32f407",
+    feature = "stm32f410",
+    feature = "stm32f411",
+    feature = "stm32f412",
+    feature = "stm32f413",
+    feature = "stm32f415",
+    feature = "stm32f417",
+    feature = "stm32f423",
+    feature = "stm32f427",
+    feature = "stm32f429",
+    feature = "stm32f437",
+    feature = "stm32f439",
+    feature = "stm32f446",
+    feature = "stm32f469",
+    feature = "stm32f479"
+))]
+impl PinCts<USART1> for crate::gpio::gpioa::PA11<Alternate<AF7>> {}
+#[cfg(any(
+    feature = "stm32f401",
+    feature = "stm32f405",
+    feature = "stm32f407",
+    feature = "stm32f410",
+    feature = "stm32f411",
+    feature = "stm32f412",
+    feature = "stm32f413",
+    feature = "stm32f415",
+    feature = "stm32f417",
+    feature = "stm32f423",
+    feature = "stm32f427",
+    feature = "stm32f429",
+    feature = "stm32f437",
+    feature = "stm32f439",
+    feature = "stm32f446",
+    feature = "stm32f469",
+    feature = "stm32f479"
+))]
+impl PinRts<USART1> for crate::gpio::gpioa::PA12<Alternate<AF7>> {}
+#[cfg(any(
+    feature = "stm32f401",
+    feature = "stm32f405",
+    feature = "stm32f407",
+    feature = "stm32f410",
+    feature = "stm32f411",
+    feature = "stm32f412",
+    feature = "stm32f413",
+    feature = "stm32f415",
+    feature = "stm32f417",
+    feature = "stm32f423",
+    feature = "stm32f427",
+    feature = "stm32f429",
+    feature = "stm32f437",
+    feature = "stm32f439",
+    feature = "stm32f446",
+    feature = "stm32f469",
+    feature = "stm32f479"
+))]
+impl PinCts<USART2> for crate::gpio::gpioa::PA0<Alternate<AF7>> {}
+#[cfg(any(
+    feature = "stm32f401",
+    feature = "stm32f405",
+    feature = "stm32f407",
+    feature = "stm32f410",
+    feature = "stm32f411",
+    feature = "stm32f412",
+    feature = "stm32f413",
+    feature = "stm32f415",
+    feature = "stm32f417",
+    feature = "stm32f423",
+    feature = "stm32f427",
+    feature = "stm32f429",
+    feature = "stm32f437",
+    feature = "stm32f439",
+    feature = "stm32f446",
+    feature = "stm32f469",
+    feature = "stm32f479"
+))]
+impl PinRts<USART2> for crate::gpio::gpioa::PA1<Alternate<AF7>> {}
+#[cfg(any(
+    feature = "stm32f401",
+    feature = "stm32f405",
+    feature = "stm32f407",
+    feature = "stm32f411",
+    feature = "stm32f412",
+    feature = "stm32f413",
+    feature = "stm32f415",
+    feature = "stm32f417",
+    feature = "stm32f423",
+    feature = "stm32f427",
+    feature = "stm32f429",
+    feature = "stm32f437",
+    feature = "stm32f439",
+    feature = "stm32f446",
+    feature = "stm32f469",
+    feature = "stm32f479"
+))]
+impl PinCts<USART2> for crate::gpio::gpiod::PD3<Alternate<AF7>> {}
+#[cfg(any(
+    feature = "stm32f401",
+    feature = "stm32f405",
+    feature = "stm32f407",
+    feature = "stm32f411",
+    feature = "stm32f412",
+    feature = "stm32f413",
+    feature = "stm32f415",
+    feature = "stm32f417",
+    feature = "stm32f423",
+    feature = "stm32f427",
+    feature = "stm32f429",
+    feature = "stm32f437",
+    feature = "stm32f439",
+    feature = "stm32f446",
+    feature = "stm32f469",
+    feature = "stm32f479"
+))]
+impl PinRts<USART2> for crate::gpio::gpiod::PD4<Alternate<AF7>> {}
+#[cfg(any(
+    feature = "stm32f405",
+    feature = "stm32f407",
+    feature = "stm32f412",
+    feature = "stm32f413",
+    feature = "stm32f415",
+    feature = "stm32f417",
+    feature = "stm32f423",
+    feature = "stm32f427",
+    feature = "stm32f429",
+    feature = "stm32f437",
+    feature = "stm32f439",
+    feature = "stm32f446",
+    feature = "stm32f469",
+    feature = "stm32f479"
+))]
+impl PinCts<USART3> for crate::gpio::gpiob::PB13<Alternate<AF7>> {}
+#[cfg(any(
+    feature = "stm32f405",
+    feature = "stm32f407",
+    feature = "stm32f412",
+    feature = "stm32f413",
+    feature = "stm32f415",
+    feature = "stm32f417",
+    feature = "stm32f423",
+    feature = "stm32f427",
+    feature = "stm32f429",
+    feature = "stm32f437",
+    feature = "stm32f439",
+    feature = "stm32f446",
+    feature = "stm32f469",
+    feature = "stm32f479"
+))]
+impl PinRts<USART3> for crate::gpio::gpiob::PB14<Alternate<AF7>> {}
+#[cfg(any(
+    feature = "stm32f405",
+    feature = "stm32f407",
+    feature = "stm32f412",
+    feature = "stm32f413",
+    feature = "stm32f415",
+    feature = "stm32f417",
+    feature = "stm32f423",
+    feature = "stm32f427",
+    feature = "stm32f429",
+    feature = "stm32f437",
+    feature = "stm32f439",
+    feature = "stm32f446",
+    feature = "stm32f469",
+    feature = "stm32f479"
+))]
+impl PinCts<USART3> for crate::gpio::gpiod::PD11<Alternate<AF7>> {}
+#[cfg(any(
+    feature = "stm32f405",
+    feature = "stm32f407",
+    feature = "stm32f412",
+    feature = "stm32f413",
+    feature = "stm32f415",
+    feature = "stm32f417",
+    feature = "stm32f423",
+    feature = "stm32f427",
+    feature = "stm32f429",
+    feature = "stm32f437",
+    feature = "stm32f439",
+    feature = "stm32f446",
+    feature = "stm32f469",
+    feature = "stm32f479"
+))]
+impl PinRts<USART3> for crate::gpio::gpiod::PD12<Alternate<AF7>> {}
+#[cfg(any(
+    feature = "stm32f405",
+    feature = "stm32f407",
+    feature = "stm32f412",
+    feature = "stm32f413",
+    feature = "stm32f415",
+    feature = "stm32f417",
+    feature = "stm32f423",
+    feature = "stm32f427",
+    feature = "stm32f429",
+    feature = "stm32f437",
+    feature = "stm32f439",
+    feature = "stm32f446",
+    feature = "stm32f469",
+    feature = "stm32f479"
+))]
+impl PinRts<USART6> for crate::gpio::gpiog::PG8<Alternate<AF8>> {}
+#[cfg(any(
+    feature = "stm32f405",
+    feature = "stm32f407",
+    feature = "stm32f412",
+    feature = "stm32f413",
+    feature = "stm32f415",
+    feature = "stm32f417",
+    feature = "stm32f423",
+    feature = "stm32f427",
+    feature = "stm32f429",
+    feature = "stm32f437",
+    feature = "stm32f439",
+    feature = "stm32f446",
+    feature = "stm32f469",
+    feature = "stm32f479"
+))]
+impl PinRts<USART6> for crate::gpio::gpiog::PG12<Alternate<AF8>> {}
+#[cfg(any(
+    feature = "stm32f405",
+    feature = "stm32f407",
+    feature = "stm32f412",
+    feature = "stm32f413",
+    feature = "stm32f415",
+    feature = "stm32f417",
+    feature = "stm32f423",
+    feature = "stm32f427",
+    feature = "stm32f429",
+    feature = "stm32f437",
+    feature = "stm32f439",
+    feature = "stm32f446",
+    feature = "stm32f469",
+    feature = "stm32f479"
+))]
+impl PinCts<USART6> for crate::gpio::gpiog::PG13<Alternate<AF8>> {}
+#[cfg(any(
+    feature = "stm32f405",
+    feature = "stm32f407",
+    feature = "stm32f412",
+    feature = "stm32f413",
+    feature = "stm32f415",
+    feature = "stm32f417",
+    feature = "stm32f423",
+    feature = "stm32f427",
+    feature = "stm32f429",
+    feature = "stm32f437",
+    feature = "stm32f439",
+    feature = "stm32f446",
+    feature = "stm32f469",
+    feature = "stm32f479"
+))]
+impl PinCts<USART6> for crate::gpio::gpiog::PG15<Alternate<AF8>> {}
+
+// Clock pins for synchronous and smartcard mode
+#[cfg(any(
+    feature = "stm32f401",
+    feature = "stm32f405",
+    feature = "stm32f407",
+    feature = "stm32f410",
+    feature = "stm32f411",
+    feature = "stm32f412",
+    feature = "stm32f413",
+    feature = "stm32f415",
+    feature = "stm32f417",
+    feature = "stm32f423",
+    feature = "stm32f427",
+    feature = "stm32f429",
+    feature = "stm32f437",
+    feature = "stm32f439",
+    feature = "stm32f446",
+    feature = "stm32f469",
+    feature = "stm32f479"
+))]
+impl PinCk<USART1> for crate::gpio::gpioa::PA8<Alternate<AF7>> {}
+#[cfg(any(
+    feature = "stm32f401",
+    feature = "stm32f405",
+    feature = "stm32f407",
+    feature = "stm32f410",
+    feature = "stm32f411",
+    feature = "stm32f412",
+    feature = "stm32f413",
+    feature = "stm32f415",
+    feature = "stm32f417",
+    feature = "stm32f423",
+    feature = "stm32f427",
+    feature = "stm32f429",
+    feature = "stm32f437",
+    feature = "stm32f439",
+    feature = "stm32f446",
+    feature = "stm32f469",
+    feature = "stm32f479"
+))]
+impl PinCk<USART2> for crate::gpio::gpioa::PA4<Alternate<AF7>> {}
+#[cfg(any(
+    feature = "stm32f401",
+    feature = "stm32f405",
+    feature = "stm32f407",
+    feature = "stm32f411",
+    feature = "stm32f412",
+    feature = "stm32f413",
+    feature = "stm32f415",
+    feature = "stm32f417",
+    feature = "stm32f423",
+    feature = "stm32f427",
+    feature = "stm32f429",
+    feature = "stm32f437",
+    feature = "stm32f439",
+    feature = "stm32f446",
+    feature = "stm32f469",
+    feature = "stm32f479"
+))]
+impl PinCk<USART2> for crate::gpio::gpiod::PD7<Alternate<AF7>> {}
+#[cfg(any(
+    feature = "stm32f405",
+    feature = "stm32f407",
+    feature = "stm32f412",
+    feature = "stm32f413",
+    feature = "stm32f415",
+    feature = "stm32f417",
+    feature = "stm32f423",
+    feature = "stm32f427",
+    feature = "stm32f429",
+    feature = "stm32f437",
+    feature = "stm32f439",
+    feature = "stm32f446",
+    feature = "stm32f469",
+    feature = "stm32f479"
+))]
+impl PinCk<USART3> for crate::gpio::gpiob::PB12<Alternate<AF7>> {}
+#[cfg(any(
+    feature = "stm32f405",
+    feature = "stm32f407",
+    feature = "stm32f412",
+    feature = "stm32f413",
+    feature = "stm32f415",
+    feature = "stm32f417",
+    feature = "stm32f423",
+    feature = "stm32f427",
+    feature = "stm32f429",
+    feature = "stm32f437",
+    feature = "stm32f439",
+    feature = "stm32f446",
+    feature = "stm32f469",
+    feature = "stm32f479"
+))]
+impl PinCk<USART3> for crate::gpio::gpioc::PC12<Alternate<AF7>> {}
+#[cfg(any(
+    feature = "stm32f405",
+    feature = "stm32f407",
+    feature = "stm32f412",
+    feature = "stm32f413",
+    feature = "stm32f415",
+    feature = "stm32f417",
+    feature = "stm32f423",
+    feature = "stm32f427",
+    feature = "stm32f429",
+    feature = "stm32f437",
+    feature = "stm32f439",
+    feature = "stm32f446",
+    feature = "stm32f469",
+    feature = "stm32f479"
+))]
+impl PinCk<USART3> for crate::gpio::gpiod::PD10<Alternate<AF7>> {}
+#[cfg(any(
+    feature = "stm32f401",
+    feature = "stm32f405",
+    feature = "stm32f407",
+    feature = "stm32f410",
+    feature = "stm32f411",
+    feature = "stm32f412",
+    feature = "stm32f413",
+    feature = "stm32f415",
+    feature = "stm32f417",
+    feature = "stm32f423",
+    feature = "stm32f427",
+    feature = "stm32f429",
+    feature = "stm32f437",
+    feature = "stm32f439",
+    feature = "stm32f446",
+    feature = "stm32f469",
+    feature = "stm32f479"
+))]
+impl PinCk<USART6> for crate::gpio::gpioc::PC8<Alternate<AF8>> {}
+#[cfg(any(
+    feature = "stm32f405",
+    feature = "stm32f407",
+    feature = "stm32f412",
+    feature = "stm32f413",
+    feature = "stm32f415",
+    feature = "stm32f417",
+    feature = "stm32f423",
+    feature = "stm32f427",
+    feature = "stm32f429",
+    feature = "stm32f437",
+    feature = "stm32f439",
+    feature = "stm32f446",
+    feature = "stm32f469",
+    feature = "stm32f479"
+))]
+impl PinCk<USART6> for crate::gpio::gpiog::PG7<Alternate<AF8>> {}
+
 /// Serial abstraction
 pub struct Serial<USART, PINS> {
     usart: USART,
@@ -1398,6 +1941,93 @@ pub struct Tx<USART> {
     _usart: PhantomData<USART>,
 }
 
+/// USART with the common register block, for code that is generic over the instance
+pub trait Instance: Deref<Target = usart1::RegisterBlock> {
+    /// Returns a pointer to the register block
+    fn ptr() -> *const usart1::RegisterBlock;
+
+    /// Returns the clock of the bus the USART is connected to
+    fn clock(clocks: &Clocks) -> Hertz;
+}
+
+macro_rules! instance {
+    ($($USARTX:ident: $pclkX:ident),+) => {
+        $(
+            impl Instance for $USARTX {
+                fn ptr() -> *const usart1::RegisterBlock {
+                    $USARTX::ptr()
+                }
+
+                fn clock(clocks: &Clocks) -> Hertz {
+                    clocks.$pclkX()
+                }
+            }
+        )+
+    };
+}
+
+/// Baud rate divisor for BRR, rounded to the nearest value
+fn brr(pclk: Hertz, baudrate: Bps) -> u32 {
+    (pclk.0 + baudrate.0 / 2) / baudrate.0
+}
+
+#[cfg(any(
+    feature = "stm32f401",
+    feature = "stm32f405",
+    feature = "stm32f407",
+    feature = "stm32f410",
+    feature = "stm32f411",
+    feature = "stm32f412",
+    feature = "stm32f413",
+    feature = "stm32f415",
+    feature = "stm32f417",
+    feature = "stm32f423",
+    feature = "stm32f427",
+    feature = "stm32f429",
+    feature = "stm32f437",
+    feature = "stm32f439",
+    feature = "stm32f446",
+    feature = "stm32f469",
+    feature = "stm32f479"
+))]
+instance!(USART1: pclk2, USART2: pclk1, USART6: pclk2);
+#[cfg(any(
+    feature = "stm32f405",
+    feature = "stm32f407",
+    feature = "stm32f412",
+    feature = "stm32f413",
+    feature = "stm32f415",
+    feature = "stm32f417",
+    feature = "stm32f423",
+    feature = "stm32f427",
+    feature = "stm32f429",
+    feature = "stm32f437",
+    feature = "stm32f439",
+    feature = "stm32f446",
+    feature = "stm32f469",
+    feature = "stm32f479"
+))]
+instance!(USART3: pclk1);
+
+/// Only the USARTs have the smartcard mode, the UARTs don't
+macro_rules! has_smartcard {
+    (USART1) => {
+        true
+    };
+    (USART2) => {
+        true
+    };
+    (USART3) => {
+        true
+    };
+    (USART6) => {
+        true
+    };
+    ($_other:ident) => {
+        false
+    };
+}
+
 macro_rules! halUsartImpl {
     ($(
         $USARTX:ident: ($usartX:ident, $apbXenr:ident, $rcc_bit:expr, $usartXen:ident, $pclkX:ident),
@@ -1416,6 +2046,54 @@ macro_rules! halUsartImpl {
                     use self::config::*;
                     use crate::bb;
 
+                    let (rts, cts) = match config.flow_control {
+                        FlowControl::None => (false, false),
+                        FlowControl::Rts => (true, false),
+                        FlowControl::Cts => (false, true),
+                        FlowControl::RtsCts => (true, true),
+                    };
+                    if (rts && !PINS::HAS_RTS) || (cts && !PINS::HAS_CTS) {
+                        return Err(InvalidConfig);
+                    }
+
+                    let stop1 = match config.stopbits {
+                        StopBits::STOP1 => true,
+                        _ => false,
+                    };
+                    let valid_mode = match config.mode {
+                        Mode::FullDuplex | Mode::HalfDuplex => true,
+                        Mode::Lin(_) => match config.wordlength {
+                            WordLength::DataBits8 => stop1,
+                            WordLength::DataBits9 => false,
+                        },
+                        Mode::IrDA { low_power, prescaler } => {
+                            let valid_prescaler = if low_power {
+                                prescaler != 0
+                            } else {
+                                prescaler == 1
+                            };
+                            valid_prescaler && stop1
+                        }
+                        Mode::Smartcard { prescaler, .. } => {
+                            let parity = match config.parity {
+                                Parity::ParityNone => false,
+                                _ => true,
+                            };
+                            let nine_bits = match config.wordlength {
+                                WordLength::DataBits8 => false,
+                                WordLength::DataBits9 => true,
+                            };
+                            has_smartcard!($USARTX)
+                                && prescaler != 0
+                                && prescaler < 32
+                                && parity
+                                && nine_bits
+                        }
+                    };
+                    if !valid_mode {
+                        return Err(InvalidConfig);
+                    }
+
                     unsafe {
                         // NOTE(unsafe) this reference will only be used for atomic writes with no side effects.
                         let rcc = &(*RCC::ptr());
@@ -1425,8 +2103,7 @@ macro_rules! halUsartImpl {
                     }
 
                     // Calculate correct baudrate divisor on the fly
-                    let div = (clocks.$pclkX().0 + config.baudrate.0 / 2)
-                        / config.baudrate.0;
+                    let div = brr(clocks.$pclkX(), config.baudrate);
                     usart.brr.write(|w| unsafe { w.bits(div) });
 
                     // Reset other registers to disable advanced USART features
@@ -1478,7 +2155,47 @@ macro_rules! halUsartImpl {
                         DmaConfig::None => {}
                     }
 
-                    Ok(Serial { usart, pins }.config_stop(config))
+                    let flow = if rts { CR3_RTSE } else { 0 } | if cts { CR3_CTSE } else { 0 };
+                    usart.cr3.modify(|r, w| unsafe { w.bits(r.bits() | flow) });
+
+                    let mode = config.mode;
+                    let serial = Serial { usart, pins }.config_stop(config);
+                    serial.config_mode(mode);
+                    Ok(serial)
+                }
+
+                fn config_mode(&self, mode: config::Mode) {
+                    use self::config::*;
+
+                    let (cr2, cr3, gtpr) = match mode {
+                        Mode::FullDuplex => (0, 0, None),
+                        Mode::HalfDuplex => (0, CR3_HDSEL, None),
+                        Mode::Lin(length) => {
+                            let lbdl = match length {
+                                LinBreakLength::Bits10 => 0,
+                                LinBreakLength::Bits11 => CR2_LBDL,
+                            };
+                            (CR2_LINEN | lbdl, 0, None)
+                        }
+                        Mode::IrDA { low_power, prescaler } => {
+                            let irlp = if low_power { CR3_IRLP } else { 0 };
+                            (0, CR3_IREN | irlp, Some(u32::from(prescaler)))
+                        }
+                        Mode::Smartcard { prescaler, guard_time, nack } => {
+                            let nack = if nack { CR3_NACK } else { 0 };
+                            let gtpr = (u32::from(guard_time) << 8) | u32::from(prescaler);
+                            (CR2_CLKEN, CR3_SCEN | nack, Some(gtpr))
+                        }
+                    };
+
+                    if let Some(gtpr) = gtpr {
+                        // NOTE(unsafe) GTPR is part of the owned register block
+                        unsafe {
+                            ptr::write_volatile(($USARTX::ptr() as usize + GTPR_OFFSET) as *mut u32, gtpr)
+                        };
+                    }
+                    self.usart.cr2.modify(|r, w| unsafe { w.bits(r.bits() | cr2) });
+                    self.usart.cr3.modify(|r, w| unsafe { w.bits(r.bits() | cr3) });
                 }
 
                 /// Starts listening for an interrupt event
@@ -1496,6 +2213,15 @@ macro_rules! halUsartImpl {
                         Event::Idle => {
                             self.usart.cr1.modify(|_, w| w.idleie().set_bit())
                         },
+                        Event::Cts => {
+                            self.usart.cr3.modify(|r, w| unsafe { w.bits(r.bits() | CR3_CTSIE) })
+                        },
+                        Event::Tc => {
+                            self.usart.cr1.modify(|_, w| w.tcie().set_bit())
+                        },
+                        Event::LinBreak => {
+                            self.usart.cr2.modify(|r, w| unsafe { w.bits(r.bits() | CR2_LBDIE) })
+                        },
                     }
                 }
 
@@ -1511,6 +2237,15 @@ macro_rules! halUsartImpl {
                         Event::Idle => {
                             self.usart.cr1.modify(|_, w| w.idleie().clear_bit())
                         },
+                        Event::Cts => {
+                            self.usart.cr3.modify(|r, w| unsafe { w.bits(r.bits() & !CR3_CTSIE) })
+                        },
+                        Event::Tc => {
+                            self.usart.cr1.modify(|_, w| w.tcie().clear_bit())
+                        },
+                        Event::LinBreak => {
+                            self.usart.cr2.modify(|r, w| unsafe { w.bits(r.bits() & !CR2_LBDIE) })
+                        },
                     }
                 }
 
@@ -1529,6 +2264,39 @@ macro_rules! halUsartImpl {
                     unsafe { (*$USARTX::ptr()).sr.read().rxne().bit_is_set() }
                 }
 
+                /// Return true if the CTS line changed since the flag was cleared
+                pub fn is_cts_changed(& self) -> bool {
+                    unsafe { (*$USARTX::ptr()).sr.read().bits() & SR_CTS != 0 }
+                }
+
+                /// Clears the CTS change flag
+                pub fn clear_cts_changed(&mut self) {
+                    // The other flags are not affected by writing 1
+                    self.usart.sr.write(|w| unsafe { w.bits(!SR_CTS & 0x3ff) });
+                }
+
+                /// Changes the baud rate. Must not be called during a transfer.
+                pub fn set_baudrate(&mut self, baudrate: Bps, clocks: &Clocks) {
+                    let div = brr(clocks.$pclkX(), baudrate);
+                    self.usart.brr.write(|w| unsafe { w.bits(div) });
+                }
+
+                /// Sends a break character after the current transmission, e.g. the LIN break
+                pub fn send_break(&mut self) {
+                    self.usart.cr1.modify(|r, w| unsafe { w.bits(r.bits() | CR1_SBK) });
+                }
+
+                /// Return true if a LIN break has been detected
+                pub fn is_lin_break(&self) -> bool {
+                    unsafe { (*$USARTX::ptr()).sr.read().bits() & SR_LBD != 0 }
+                }
+
+                /// Clears the LIN break detection flag
+                pub fn clear_lin_break(&mut self) {
+                    // The other flags are not affected by writing 1
+                    self.usart.sr.write(|w| unsafe { w.bits(!SR_LBD & 0x3ff) });
+                }
+
                 pub fn split(self) -> (Tx<$USARTX>, Rx<$USARTX>) {
                     (
                         Tx {
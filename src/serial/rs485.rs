@@ -0,0 +1,239 @@
+//! RS-485 driver enable handling
+//!
+//! The F4 USART has no driver enable output, so [`Rs485`](struct.Rs485.html) drives a GPIO
+//! pin around transmissions. The pin is asserted before the first byte is written and released
+//! once the transmission complete (TC) flag is set, i.e. after the stop bit of the last byte
+//! left the shift register.
+//!
+//! The `serial::Write` and `blocking::serial::Write` implementations handle the pin on their
+//! own. For interrupt driven transmissions call
+//! [`start_transmission`](struct.Rs485.html#method.start_transmission), write the data from
+//! the TXE interrupt, listen for `Event::Tc` and call
+//! [`finish_transmission`](struct.Rs485.html#method.finish_transmission) from the interrupt
+//! handler. For DMA, call `start_transmission` and then pass the `Rs485` to `dma::Transfer`
+//! instead of the transmitter it wraps; after the transfer completes, take it back with
+//! `Transfer::free` and call `finish_transmission`.
+//!
+//! The pre- and post-transmission delays are busy-waits. With non-zero delays
+//! `start_transmission` and `finish_transmission` block for the delay, so they must not be
+//! called from interrupt handlers; leave the delays at zero for interrupt driven transmissions.
+
+use embedded_hal::blocking;
+use embedded_hal::digital::v2::OutputPin;
+use embedded_hal::serial;
+use nb::block;
+
+use crate::dma::traits::{DMASet, PeriAddress};
+use crate::rcc::Clocks;
+use crate::time::{MicroSeconds, U32Ext};
+
+use super::Error as SerialError;
+
+/// RS-485 transmission error
+#[derive(Debug)]
+pub enum Error<E> {
+    /// Error of the wrapped transmitter
+    Serial(SerialError),
+    /// Error of the driver enable pin
+    DriverEnable(E),
+}
+
+impl<E> From<SerialError> for Error<E> {
+    fn from(error: SerialError) -> Self {
+        Error::Serial(error)
+    }
+}
+
+fn serial_error<E>(error: nb::Error<SerialError>) -> nb::Error<Error<E>> {
+    match error {
+        nb::Error::WouldBlock => nb::Error::WouldBlock,
+        nb::Error::Other(error) => nb::Error::Other(Error::Serial(error)),
+    }
+}
+
+/// Active level of the driver enable pin
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Polarity {
+    ActiveHigh,
+    ActiveLow,
+}
+
+/// RS-485 configuration
+#[derive(Clone, Copy, Debug)]
+pub struct Config {
+    pub polarity: Polarity,
+    /// Delay between asserting the driver enable pin and the first start bit
+    pub pre_delay: MicroSeconds,
+    /// Delay between the last stop bit and releasing the driver enable pin
+    pub post_delay: MicroSeconds,
+}
+
+impl Config {
+    pub fn polarity(mut self, polarity: Polarity) -> Self {
+        self.polarity = polarity;
+        self
+    }
+
+    pub fn pre_delay(mut self, pre_delay: MicroSeconds) -> Self {
+        self.pre_delay = pre_delay;
+        self
+    }
+
+    pub fn post_delay(mut self, post_delay: MicroSeconds) -> Self {
+        self.post_delay = post_delay;
+        self
+    }
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            polarity: Polarity::ActiveHigh,
+            pre_delay: 0.us(),
+            post_delay: 0.us(),
+        }
+    }
+}
+
+/// Serial transmitter with RS-485 driver enable pin
+pub struct Rs485<TX, DE> {
+    tx: TX,
+    de: DE,
+    polarity: Polarity,
+    pre_cycles: u32,
+    post_cycles: u32,
+    driving: bool,
+}
+
+impl<TX, DE> Rs485<TX, DE>
+where
+    TX: serial::Write<u8, Error = SerialError>,
+    DE: OutputPin,
+{
+    /// Wraps a transmitter, e.g. a `serial::Tx` or `serial::Serial`, and releases the driver
+    /// enable pin
+    pub fn new(tx: TX, de: DE, config: Config, clocks: &Clocks) -> Result<Self, DE::Error> {
+        let cycles = |delay: MicroSeconds| {
+            (u64::from(clocks.sysclk().0) * u64::from(delay.0) / 1_000_000) as u32
+        };
+
+        let mut rs485 = Rs485 {
+            tx,
+            de,
+            polarity: config.polarity,
+            pre_cycles: cycles(config.pre_delay),
+            post_cycles: cycles(config.post_delay),
+            driving: true,
+        };
+        rs485.set_driver(false)?;
+        Ok(rs485)
+    }
+
+    fn set_driver(&mut self, enable: bool) -> Result<(), DE::Error> {
+        if enable == (self.polarity == Polarity::ActiveHigh) {
+            self.de.set_high()?;
+        } else {
+            self.de.set_low()?;
+        }
+        self.driving = enable;
+        Ok(())
+    }
+
+    /// Asserts the driver enable pin and busy-waits for the pre-transmission delay. Does
+    /// nothing if the pin is already asserted.
+    pub fn start_transmission(&mut self) -> Result<(), Error<DE::Error>> {
+        if !self.driving {
+            self.set_driver(true).map_err(Error::DriverEnable)?;
+            if self.pre_cycles != 0 {
+                cortex_m::asm::delay(self.pre_cycles);
+            }
+        }
+        Ok(())
+    }
+
+    /// Releases the driver enable pin after the transmission completed and the
+    /// post-transmission delay passed, which is busy-waited. Returns `WouldBlock` while the
+    /// last byte is still sent.
+    pub fn finish_transmission(&mut self) -> nb::Result<(), Error<DE::Error>> {
+        self.tx.flush().map_err(serial_error)?;
+        if self.driving {
+            if self.post_cycles != 0 {
+                cortex_m::asm::delay(self.post_cycles);
+            }
+            self.set_driver(false).map_err(Error::DriverEnable)?;
+        }
+        Ok(())
+    }
+
+    /// Returns `true` while the driver enable pin is asserted
+    pub fn is_driving(&self) -> bool {
+        self.driving
+    }
+
+    /// Gives access to the wrapped transmitter, e.g. to listen for events
+    pub fn tx(&mut self) -> &mut TX {
+        &mut self.tx
+    }
+
+    /// Releases the transmitter and the driver enable pin
+    pub fn release(self) -> (TX, DE) {
+        (self.tx, self.de)
+    }
+}
+
+impl<TX, DE> serial::Write<u8> for Rs485<TX, DE>
+where
+    TX: serial::Write<u8, Error = SerialError>,
+    DE: OutputPin,
+{
+    type Error = Error<DE::Error>;
+
+    fn write(&mut self, byte: u8) -> nb::Result<(), Self::Error> {
+        self.start_transmission()?;
+        self.tx.write(byte).map_err(serial_error)
+    }
+
+    fn flush(&mut self) -> nb::Result<(), Self::Error> {
+        self.finish_transmission()
+    }
+}
+
+impl<TX, DE> blocking::serial::Write<u8> for Rs485<TX, DE>
+where
+    TX: serial::Write<u8, Error = SerialError>,
+    DE: OutputPin,
+{
+    type Error = Error<DE::Error>;
+
+    /// Sends `bytes` and waits until the transmission completed to release the bus
+    fn bwrite_all(&mut self, bytes: &[u8]) -> Result<(), Self::Error> {
+        self.start_transmission()?;
+        for &b in bytes {
+            block!(self.tx.write(b))?;
+        }
+        block!(self.finish_transmission())
+    }
+
+    fn bflush(&mut self) -> Result<(), Self::Error> {
+        block!(self.finish_transmission())
+    }
+}
+
+unsafe impl<TX, DE> PeriAddress for Rs485<TX, DE>
+where
+    TX: PeriAddress,
+{
+    #[inline(always)]
+    fn address(&self) -> u32 {
+        self.tx.address()
+    }
+
+    type MemSize = TX::MemSize;
+
+    const REQUEST: bool = TX::REQUEST;
+}
+
+unsafe impl<STREAM, CHANNEL, TX, DE, DIR> DMASet for (STREAM, CHANNEL, Rs485<TX, DE>, DIR) where
+    (STREAM, CHANNEL, TX, DIR): DMASet
+{
+}
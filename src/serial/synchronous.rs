@@ -0,0 +1,322 @@
+//! Synchronous USART mode
+//!
+//! USART1/2/3/6 can act as an SPI master that drives the clock on the CK pin. Clock pulses are
+//! only generated for the data bits, the start and stop bits on TX are ignored by SPI slaves.
+//! The USART always shifts out the least significant bit first, so for the usual MSB first
+//! devices the bytes are reversed in software.
+//!
+//! There is no slave select, use a GPIO pin for that.
+
+use core::ops::Deref;
+use core::ptr;
+
+use embedded_hal::spi;
+pub use embedded_hal::spi::{Mode, Phase, Polarity};
+
+#[cfg(any(
+    feature = "stm32f401",
+    feature = "stm32f405",
+    feature = "stm32f407",
+    feature = "stm32f410",
+    feature = "stm32f411",
+    feature = "stm32f412",
+    feature = "stm32f413",
+    feature = "stm32f415",
+    feature = "stm32f417",
+    feature = "stm32f423",
+    feature = "stm32f427",
+    feature = "stm32f429",
+    feature = "stm32f437",
+    feature = "stm32f439",
+    feature = "stm32f446",
+    feature = "stm32f469",
+    feature = "stm32f479"
+))]
+use crate::stm32::{USART1, USART2, USART6};
+
+#[cfg(any(
+    feature = "stm32f405",
+    feature = "stm32f407",
+    feature = "stm32f412",
+    feature = "stm32f413",
+    feature = "stm32f415",
+    feature = "stm32f417",
+    feature = "stm32f423",
+    feature = "stm32f427",
+    feature = "stm32f429",
+    feature = "stm32f437",
+    feature = "stm32f439",
+    feature = "stm32f446",
+    feature = "stm32f469",
+    feature = "stm32f479"
+))]
+use crate::stm32::USART3;
+
+use crate::bb;
+use crate::rcc::Clocks;
+use crate::stm32::{usart1, RCC};
+use crate::time::{Bps, U32Ext};
+
+use super::config::InvalidConfig;
+use super::{brr, Error, PinCk, PinRx, PinTx};
+
+// CR2 bits
+const CR2_LBCL: u32 = 1 << 8;
+const CR2_CPHA: u32 = 1 << 9;
+const CR2_CPOL: u32 = 1 << 10;
+const CR2_CLKEN: u32 = 1 << 11;
+
+/// Order in which the bits of a byte are transferred
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BitOrder {
+    MsbFirst,
+    LsbFirst,
+}
+
+/// Synchronous mode configuration
+#[derive(Clone, Copy, Debug)]
+pub struct Config {
+    pub baudrate: Bps,
+    pub mode: Mode,
+    /// Generate the clock pulse of the last data bit, which SPI slaves need
+    pub last_bit_clock: bool,
+    pub bit_order: BitOrder,
+}
+
+impl Config {
+    pub fn baudrate(mut self, baudrate: Bps) -> Self {
+        self.baudrate = baudrate;
+        self
+    }
+
+    pub fn mode(mut self, mode: Mode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    pub fn last_bit_clock(mut self, last_bit_clock: bool) -> Self {
+        self.last_bit_clock = last_bit_clock;
+        self
+    }
+
+    pub fn bit_order(mut self, bit_order: BitOrder) -> Self {
+        self.bit_order = bit_order;
+        self
+    }
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            baudrate: 1_000_000_u32.bps(),
+            mode: spi::MODE_0,
+            last_bit_clock: true,
+            bit_order: BitOrder::MsbFirst,
+        }
+    }
+}
+
+pub trait Pins<USART> {}
+
+impl<USART, CK, TX, RX> Pins<USART> for (CK, TX, RX)
+where
+    CK: PinCk<USART>,
+    TX: PinTx<USART>,
+    RX: PinRx<USART>,
+{
+}
+
+/// USART in synchronous master mode
+pub struct Synchronous<USART, PINS> {
+    usart: USART,
+    pins: PINS,
+    bit_order: BitOrder,
+}
+
+macro_rules! synchronous {
+    ($(
+        $USARTX:ident: ($usartX:ident, $apbXenr:ident, $rcc_bit:expr, $pclkX:ident),
+    )+) => {
+        $(
+            impl<PINS> Synchronous<$USARTX, PINS> {
+                /// Configures the USART as synchronous master. Returns `InvalidConfig` if the
+                /// baud rate is 0 or higher than the peripheral clock divided by 16.
+                pub fn $usartX(
+                    usart: $USARTX,
+                    pins: PINS,
+                    config: Config,
+                    clocks: Clocks,
+                ) -> Result<Self, InvalidConfig>
+                where
+                    PINS: Pins<$USARTX>,
+                {
+                    let pclk = clocks.$pclkX();
+                    if config.baudrate.0 == 0 || config.baudrate.0 > pclk.0 / 16 {
+                        return Err(InvalidConfig);
+                    }
+
+                    unsafe {
+                        // NOTE(unsafe) this reference will only be used for atomic writes with no side effects.
+                        let rcc = &(*RCC::ptr());
+
+                        // Enable clock.
+                        bb::set(&rcc.$apbXenr, $rcc_bit);
+                    }
+
+                    usart.brr.write(|w| unsafe { w.bits(brr(pclk, config.baudrate)) });
+
+                    usart.cr3.reset();
+
+                    let cpol = match config.mode.polarity {
+                        Polarity::IdleLow => 0,
+                        Polarity::IdleHigh => CR2_CPOL,
+                    };
+                    let cpha = match config.mode.phase {
+                        Phase::CaptureOnFirstTransition => 0,
+                        Phase::CaptureOnSecondTransition => CR2_CPHA,
+                    };
+                    let lbcl = if config.last_bit_clock { CR2_LBCL } else { 0 };
+                    usart.cr2.write(|w| unsafe { w.bits(CR2_CLKEN | cpol | cpha | lbcl) });
+
+                    // 8 data bits without parity
+                    usart.cr1.write(|w| {
+                        w.ue()
+                            .set_bit()
+                            .te()
+                            .set_bit()
+                            .re()
+                            .set_bit()
+                    });
+
+                    Ok(Synchronous {
+                        usart,
+                        pins,
+                        bit_order: config.bit_order,
+                    })
+                }
+            }
+        )+
+    }
+}
+
+impl<USART, PINS> Synchronous<USART, PINS>
+where
+    USART: Deref<Target = usart1::RegisterBlock>,
+{
+    fn ordered(&self, byte: u8) -> u8 {
+        match self.bit_order {
+            BitOrder::MsbFirst => byte.reverse_bits(),
+            BitOrder::LsbFirst => byte,
+        }
+    }
+
+    /// Return true if the tx register is empty (and can accept data)
+    pub fn is_txe(&self) -> bool {
+        self.usart.sr.read().txe().bit_is_set()
+    }
+
+    /// Return true if the rx register is not empty (and can be read)
+    pub fn is_rxne(&self) -> bool {
+        self.usart.sr.read().rxne().bit_is_set()
+    }
+
+    /// Return true if the last transfer completed and the clock stopped
+    pub fn is_tc(&self) -> bool {
+        self.usart.sr.read().tc().bit_is_set()
+    }
+
+    pub fn free(self) -> (USART, PINS) {
+        self.usart.cr1.reset();
+        self.usart.cr2.reset();
+        (self.usart, self.pins)
+    }
+}
+
+impl<USART, PINS> spi::FullDuplex<u8> for Synchronous<USART, PINS>
+where
+    USART: Deref<Target = usart1::RegisterBlock>,
+{
+    type Error = Error;
+
+    fn read(&mut self) -> nb::Result<u8, Error> {
+        let sr = self.usart.sr.read();
+
+        Err(if sr.ore().bit_is_set() {
+            // Reading the data register after the status register clears the flag
+            unsafe { ptr::read_volatile(&self.usart.dr as *const _ as *const u8) };
+            nb::Error::Other(Error::Overrun)
+        } else if sr.rxne().bit_is_set() {
+            // NOTE(read_volatile) see `write_volatile` below
+            let byte = unsafe { ptr::read_volatile(&self.usart.dr as *const _ as *const u8) };
+            return Ok(self.ordered(byte));
+        } else {
+            nb::Error::WouldBlock
+        })
+    }
+
+    fn send(&mut self, byte: u8) -> nb::Result<(), Error> {
+        if self.usart.sr.read().txe().bit_is_set() {
+            // NOTE(write_volatile) 8-bit write that's not possible through the svd2rust API
+            let byte = self.ordered(byte);
+            unsafe { ptr::write_volatile(&self.usart.dr as *const _ as *mut u8, byte) }
+            Ok(())
+        } else {
+            Err(nb::Error::WouldBlock)
+        }
+    }
+}
+
+impl<USART, PINS> embedded_hal::blocking::spi::transfer::Default<u8> for Synchronous<USART, PINS> where
+    USART: Deref<Target = usart1::RegisterBlock>
+{
+}
+
+impl<USART, PINS> embedded_hal::blocking::spi::write::Default<u8> for Synchronous<USART, PINS> where
+    USART: Deref<Target = usart1::RegisterBlock>
+{
+}
+
+#[cfg(any(
+    feature = "stm32f401",
+    feature = "stm32f405",
+    feature = "stm32f407",
+    feature = "stm32f410",
+    feature = "stm32f411",
+    feature = "stm32f412",
+    feature = "stm32f413",
+    feature = "stm32f415",
+    feature = "stm32f417",
+    feature = "stm32f423",
+    feature = "stm32f427",
+    feature = "stm32f429",
+    feature = "stm32f437",
+    feature = "stm32f439",
+    feature = "stm32f446",
+    feature = "stm32f469",
+    feature = "stm32f479"
+))]
+synchronous! {
+    USART1: (usart1, apb2enr, 4, pclk2),
+    USART2: (usart2, apb1enr, 17, pclk1),
+    USART6: (usart6, apb2enr, 5, pclk2),
+}
+
+#[cfg(any(
+    feature = "stm32f405",
+    feature = "stm32f407",
+    feature = "stm32f412",
+    feature = "stm32f413",
+    feature = "stm32f415",
+    feature = "stm32f417",
+    feature = "stm32f423",
+    feature = "stm32f427",
+    feature = "stm32f429",
+    feature = "stm32f437",
+    feature = "stm32f439",
+    feature = "stm32f446",
+    feature = "stm32f469",
+    feature = "stm32f479"
+))]
+synchronous! {
+    USART3: (usart3, apb1enr, 18, pclk1),
+}
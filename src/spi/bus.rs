@@ -0,0 +1,108 @@
+//! SPI bus shared by several devices with GPIO chip selects
+//!
+//! [`SpiBus`](struct.SpiBus.html) owns the `Spi` and hands out a
+//! [`SpiDevice`](struct.SpiDevice.html) for every chip select pin. A device asserts its chip
+//! select (low) for the duration of each blocking `transfer` or `write` and releases it after
+//! the last frame left the shift register, so the device handles can be passed to independent
+//! drivers.
+//!
+//! The bus is borrowed through a `RefCell`, so the devices can only be used from one context.
+//! Using a device from an interrupt handler while another one is in a transfer panics.
+
+use core::cell::RefCell;
+use core::convert::Infallible;
+use core::ops::Deref;
+
+use embedded_hal::blocking;
+use embedded_hal::digital::v2::OutputPin;
+
+use crate::stm32::spi1;
+
+use super::{Error, Spi};
+
+/// Shared SPI bus
+pub struct SpiBus<SPI, PINS> {
+    spi: RefCell<Spi<SPI, PINS>>,
+}
+
+impl<SPI, PINS> SpiBus<SPI, PINS>
+where
+    SPI: Deref<Target = spi1::RegisterBlock>,
+{
+    pub fn new(spi: Spi<SPI, PINS>) -> Self {
+        SpiBus {
+            spi: RefCell::new(spi),
+        }
+    }
+
+    /// Creates a device handle that uses `cs` as chip select and releases it
+    pub fn device<CS>(&self, mut cs: CS) -> SpiDevice<'_, SPI, PINS, CS>
+    where
+        CS: OutputPin<Error = Infallible>,
+    {
+        let _ = cs.set_high();
+        SpiDevice { bus: &self.spi, cs }
+    }
+
+    /// Returns the `Spi`, all device handles have to be dropped first
+    pub fn free(self) -> Spi<SPI, PINS> {
+        self.spi.into_inner()
+    }
+}
+
+/// Device on a shared SPI bus
+pub struct SpiDevice<'a, SPI, PINS, CS> {
+    bus: &'a RefCell<Spi<SPI, PINS>>,
+    cs: CS,
+}
+
+impl<'a, SPI, PINS, CS> SpiDevice<'a, SPI, PINS, CS>
+where
+    SPI: Deref<Target = spi1::RegisterBlock>,
+    CS: OutputPin<Error = Infallible>,
+{
+    /// Runs `f` on the bus with the chip select asserted, e.g. for a command followed by a
+    /// read in one transaction
+    pub fn transaction<R>(&mut self, f: impl FnOnce(&mut Spi<SPI, PINS>) -> R) -> R {
+        let mut spi = self.bus.borrow_mut();
+        let _ = self.cs.set_low();
+        let result = f(&mut spi);
+        spi.wait_idle();
+        let _ = self.cs.set_high();
+        result
+    }
+
+    /// Returns the chip select pin
+    pub fn release(self) -> CS {
+        self.cs
+    }
+}
+
+impl<'a, SPI, PINS, CS, W> blocking::spi::Transfer<W> for SpiDevice<'a, SPI, PINS, CS>
+where
+    SPI: Deref<Target = spi1::RegisterBlock>,
+    Spi<SPI, PINS>: blocking::spi::Transfer<W, Error = Error>,
+    CS: OutputPin<Error = Infallible>,
+{
+    type Error = Error;
+
+    fn transfer<'w>(&mut self, words: &'w mut [W]) -> Result<&'w [W], Self::Error> {
+        self.transaction(|spi| {
+            blocking::spi::Transfer::<W>::transfer(spi, &mut *words).map(|_| ())
+        })?;
+        Ok(&*words)
+    }
+}
+
+impl<'a, SPI, PINS, CS, W> blocking::spi::Write<W> for SpiDevice<'a, SPI, PINS, CS>
+where
+    SPI: Deref<Target = spi1::RegisterBlock>,
+    Spi<SPI, PINS>: blocking::spi::Write<W, Error = Error>,
+    CS: OutputPin<Error = Infallible>,
+{
+    type Error = Error;
+
+    fn write(&mut self, words: &[W]) -> Result<(), Self::Error> {
+        self.transaction(|spi| blocking::spi::Write::<W>::write(spi, words))
+    }
+}
@@ -0,0 +1,167 @@
+//! Full-duplex SPI block transfers with DMA
+//!
+//! [`Spi::transfer_dma`](../struct.Spi.html#method.transfer_dma) starts a receive and a
+//! transmit stream that move a whole buffer through the SPI without the CPU. The returned
+//! [`DmaTransfer`](struct.DmaTransfer.html) hands back the `Spi`, the streams and both buffers
+//! once the last frame is received, or reports why the transfer couldn't finish. Only 8 bit
+//! frames are supported.
+
+use core::marker::PhantomData;
+
+use embedded_dma::WriteBuffer;
+
+use crate::dma::config::DmaConfig;
+use crate::dma::traits::{Channel, DMASet, Stream};
+use crate::dma::{MemoryToPeripheral, PeripheralToMemory, Transfer};
+
+use super::{Error, Instance, Rx, Spi, Tx};
+
+/// Full-duplex DMA transfer in progress
+pub struct DmaTransfer<SPI, PINS, RXSTREAM, RXCHANNEL, TXSTREAM, TXCHANNEL, BUF>
+where
+    SPI: Instance,
+    RXSTREAM: Stream,
+    TXSTREAM: Stream,
+    BUF: WriteBuffer<Word = u8> + 'static,
+{
+    spi: Spi<SPI, PINS>,
+    rx: Transfer<RXSTREAM, RXCHANNEL, Rx<SPI>, PeripheralToMemory, BUF>,
+    tx: Transfer<TXSTREAM, TXCHANNEL, Tx<SPI>, MemoryToPeripheral, BUF>,
+}
+
+impl<SPI, PINS> Spi<SPI, PINS>
+where
+    SPI: Instance,
+{
+    /// Sends `tx_buf` and receives into `rx_buf` at the same time, with `rx_stream` and
+    /// `tx_stream` moving the data. The transfer complete interrupt of the receive stream
+    /// signals the end of the transfer.
+    ///
+    /// Returns everything together with `Error::BufferLength` if the buffers differ in length,
+    /// or with `Error::FrameSize` if the SPI is configured for 16 bit frames.
+    #[allow(clippy::type_complexity)]
+    pub fn transfer_dma<RXSTREAM, RXCHANNEL, TXSTREAM, TXCHANNEL, BUF>(
+        self,
+        rx_stream: RXSTREAM,
+        tx_stream: TXSTREAM,
+        mut rx_buf: BUF,
+        mut tx_buf: BUF,
+    ) -> Result<
+        DmaTransfer<SPI, PINS, RXSTREAM, RXCHANNEL, TXSTREAM, TXCHANNEL, BUF>,
+        (Error, Self, RXSTREAM, TXSTREAM, BUF, BUF),
+    >
+    where
+        RXSTREAM: Stream,
+        RXCHANNEL: Channel,
+        TXSTREAM: Stream,
+        TXCHANNEL: Channel,
+        BUF: WriteBuffer<Word = u8> + 'static,
+        (RXSTREAM, RXCHANNEL, Rx<SPI>, PeripheralToMemory): DMASet,
+        (TXSTREAM, TXCHANNEL, Tx<SPI>, MemoryToPeripheral): DMASet,
+    {
+        // NOTE(unsafe) only the lengths are used, the buffers are handed over to the DMA below
+        let (rx_len, tx_len) = unsafe { (rx_buf.write_buffer().1, tx_buf.write_buffer().1) };
+        let error = if rx_len != tx_len {
+            Some(Error::BufferLength)
+        } else if self.spi.cr1.read().dff().bit_is_set() {
+            Some(Error::FrameSize)
+        } else {
+            None
+        };
+        if let Some(e) = error {
+            return Err((e, self, rx_stream, tx_stream, rx_buf, tx_buf));
+        }
+
+        let rx = Rx { _spi: PhantomData };
+        let tx = Tx { _spi: PhantomData };
+        let mut rx = Transfer::init(
+            rx_stream,
+            rx,
+            rx_buf,
+            None,
+            DmaConfig::default()
+                .memory_increment(true)
+                .transfer_complete_interrupt(true),
+        );
+        let mut tx = Transfer::init(
+            tx_stream,
+            tx,
+            tx_buf,
+            None,
+            DmaConfig::default().memory_increment(true),
+        );
+
+        // Drop stale data
+        while self.spi.sr.read().rxne().bit_is_set() {
+            self.spi.dr.read();
+        }
+
+        // The receive request is enabled before the transmit request starts the transfer, as
+        // described in the reference manual ("Communication using DMA")
+        self.spi.cr2.modify(|_, w| w.rxdmaen().set_bit());
+        rx.start(|_| {});
+        tx.start(|_| {});
+        self.spi.cr2.modify(|_, w| w.txdmaen().set_bit());
+
+        Ok(DmaTransfer { spi: self, rx, tx })
+    }
+}
+
+impl<SPI, PINS, RXSTREAM, RXCHANNEL, TXSTREAM, TXCHANNEL, BUF>
+    DmaTransfer<SPI, PINS, RXSTREAM, RXCHANNEL, TXSTREAM, TXCHANNEL, BUF>
+where
+    SPI: Instance,
+    RXSTREAM: Stream,
+    RXCHANNEL: Channel,
+    TXSTREAM: Stream,
+    TXCHANNEL: Channel,
+    BUF: WriteBuffer<Word = u8> + 'static,
+    (RXSTREAM, RXCHANNEL, Rx<SPI>, PeripheralToMemory): DMASet,
+    (TXSTREAM, TXCHANNEL, Tx<SPI>, MemoryToPeripheral): DMASet,
+{
+    /// Returns `true` once the last frame is received
+    pub fn is_complete(&self) -> bool {
+        RXSTREAM::get_transfer_complete_flag()
+    }
+
+    /// Waits for the transfer to complete and returns the `Spi`, the receive and transmit
+    /// streams, and the receive and transmit buffers.
+    ///
+    /// Stops the transfer and returns everything together with `Error::Dma` if a stream reports
+    /// a transfer error, or with `Error::Disabled` if the SPI is disabled, like it is outside of
+    /// a `transaction` with an NSS pin.
+    #[allow(clippy::type_complexity)]
+    pub fn wait(
+        self,
+    ) -> Result<
+        (Spi<SPI, PINS>, RXSTREAM, TXSTREAM, BUF, BUF),
+        (Error, Spi<SPI, PINS>, RXSTREAM, TXSTREAM, BUF, BUF),
+    > {
+        let result = loop {
+            if self.is_complete() {
+                break Ok(());
+            }
+            if RXSTREAM::get_transfer_error_flag() || TXSTREAM::get_transfer_error_flag() {
+                break Err(Error::Dma);
+            }
+            if self.spi.spi.cr1.read().spe().bit_is_clear() {
+                break Err(Error::Disabled);
+            }
+        };
+
+        let spi = self.spi;
+        if result.is_ok() {
+            spi.wait_idle();
+        }
+        spi.spi
+            .cr2
+            .modify(|_, w| w.rxdmaen().clear_bit().txdmaen().clear_bit());
+
+        let (rx_stream, _, rx_buf, _) = self.rx.free();
+        let (tx_stream, _, tx_buf, _) = self.tx.free();
+        match result {
+            Ok(()) => Ok((spi, rx_stream, tx_stream, rx_buf, tx_buf)),
+            Err(e) => Err((e, spi, rx_stream, tx_stream, rx_buf, tx_buf)),
+        }
+    }
+}
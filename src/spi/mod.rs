@@ -1,9 +1,11 @@
+use core::marker::PhantomData;
 use core::ops::Deref;
 use core::ptr;
 
 use crate::bb;
-use embedded_hal::spi;
 pub use embedded_hal::spi::{Mode, Phase, Polarity};
+use embedded_hal::{blocking, spi};
+use nb::block;
 
 #[cfg(any(
     feature = "stm32f401",
@@ -348,9 +350,120 @@ use crate::gpio::AF7;
 ))]
 use crate::gpio::{Alternate, AF5, AF6};
 
+use crate::dma::traits::{DMASet, PeriAddress};
 use crate::rcc::Clocks;
 use crate::time::Hertz;
 
+pub mod bus;
+pub mod dma;
+pub mod slave;
+
+pub mod config {
+    use super::Mode;
+    use crate::time::{Hertz, U32Ext};
+
+    /// Number of bits in a data frame
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    pub enum FrameSize {
+        Bits8,
+        Bits16,
+    }
+
+    /// Order in which the bits of a frame are transferred
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    pub enum BitOrder {
+        MsbFirst,
+        LsbFirst,
+    }
+
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    pub enum FrameFormat {
+        /// Motorola SPI, clock polarity and phase from `Mode`
+        Motorola,
+        /// TI synchronous serial, ignores the clock polarity and phase and pulses NSS for
+        /// every frame
+        Ti,
+    }
+
+    /// Data lines in use
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    pub enum Direction {
+        /// Two unidirectional lines, MISO and MOSI
+        FullDuplex,
+        /// Half-duplex on the MOSI pin (3-wire), pass `NoMiso`. Use `Spi::transmit` and
+        /// `Spi::receive` to switch the direction of the data line.
+        Bidirectional,
+        /// Receive on MISO only, pass `NoMosi`. The clock runs while the SPI is enabled, so
+        /// it's only enabled in `Spi::receive`.
+        ReceiveOnly,
+    }
+
+    #[derive(Clone, Copy, Debug)]
+    pub struct Config {
+        pub mode: Mode,
+        pub frequency: Hertz,
+        pub frame_size: FrameSize,
+        pub bit_order: BitOrder,
+        pub frame_format: FrameFormat,
+        pub direction: Direction,
+        /// Polynomial of the hardware CRC, `None` disables the CRC
+        pub crc_polynomial: Option<u16>,
+    }
+
+    impl Config {
+        pub fn mode(mut self, mode: Mode) -> Self {
+            self.mode = mode;
+            self
+        }
+
+        pub fn frequency(mut self, frequency: Hertz) -> Self {
+            self.frequency = frequency;
+            self
+        }
+
+        pub fn frame_size(mut self, frame_size: FrameSize) -> Self {
+            self.frame_size = frame_size;
+            self
+        }
+
+        pub fn bit_order(mut self, bit_order: BitOrder) -> Self {
+            self.bit_order = bit_order;
+            self
+        }
+
+        pub fn frame_format(mut self, frame_format: FrameFormat) -> Self {
+            self.frame_format = frame_format;
+            self
+        }
+
+        pub fn direction(mut self, direction: Direction) -> Self {
+            self.direction = direction;
+            self
+        }
+
+        /// Enables the hardware CRC with the given polynomial, e.g. `0x07` for CRC-8 or
+        /// `0x1021` for CRC-16-CCITT. The CRC width follows the frame size.
+        pub fn crc(mut self, polynomial: u16) -> Self {
+            self.crc_polynomial = Some(polynomial);
+            self
+        }
+    }
+
+    impl Default for Config {
+        fn default() -> Config {
+            Config {
+                mode: embedded_hal::spi::MODE_0,
+                frequency: 1.mhz().into(),
+                frame_size: FrameSize::Bits8,
+                bit_order: BitOrder::MsbFirst,
+                frame_format: FrameFormat::Motorola,
+                direction: Direction::FullDuplex,
+                crc_polynomial: None,
+            }
+        }
+    }
+}
+
 /// SPI error
 #[derive(Debug)]
 pub enum Error {
@@ -360,14 +473,31 @@ pub enum Error {
     ModeFault,
     /// CRC error
     Crc,
+    /// The SPI is disabled, with an NSS pin it's only enabled in a `transaction`
+    Disabled,
+    /// A DMA stream reported a transfer error
+    Dma,
+    /// The transfer isn't possible in the configured `Direction`
+    Direction,
+    /// The DMA buffers differ in length
+    BufferLength,
+    /// DMA transfers need 8 bit frames
+    FrameSize,
     #[doc(hidden)]
     _Extensible,
 }
 
-pub trait Pins<SPI> {}
+pub trait Pins<SPI> {
+    #[doc(hidden)]
+    const HAS_NSS: bool = false;
+}
 pub trait PinSck<SPI> {}
 pub trait PinMiso<SPI> {}
 pub trait PinMosi<SPI> {}
+pub trait PinNss<SPI> {
+    #[doc(hidden)]
+    const CONNECTED: bool = true;
+}
 
 impl<SPI, SCK, MISO, MOSI> Pins<SPI> for (SCK, MISO, MOSI)
 where
@@ -377,12 +507,29 @@ where
 {
 }
 
+/// `(sck, miso, mosi, nss)` to drive NSS from the SPI, see `Spi::transaction`
+impl<SPI, SCK, MISO, MOSI, NSS> Pins<SPI> for (SCK, MISO, MOSI, NSS)
+where
+    SCK: PinSck<SPI>,
+    MISO: PinMiso<SPI>,
+    MOSI: PinMosi<SPI>,
+    NSS: PinNss<SPI>,
+{
+    const HAS_NSS: bool = NSS::CONNECTED;
+}
+
 /// A filler type for when the SCK pin is unnecessary
 pub struct NoSck;
 /// A filler type for when the Miso pin is unnecessary
 pub struct NoMiso;
 /// A filler type for when the Mosi pin is unnecessary
 pub struct NoMosi;
+/// A filler type for when the Nss pin is unnecessary, NSS is then managed in software
+pub struct NoNss;
+
+impl<SPI> PinNss<SPI> for NoNss {
+    const CONNECTED: bool = false;
+}
 
 macro_rules! pins {
     ($($SPIX:ty: SCK: [$($SCK:ty),*] MISO: [$($MISO:ty),*] MOSI: [$($MOSI:ty),*])+) => {
@@ -703,6 +850,153 @@ pins! {
         MOSI: [PC1<Alternate<AF5>>]
 }
 
+macro_rules! nss_pins {
+    ($($SPIX:ty: [$($NSS:ty),*])+) => {
+        $(
+            $(
+                impl PinNss<$SPIX> for $NSS {}
+            )*
+        )+
+    }
+}
+
+#[cfg(any(
+    feature = "stm32f401",
+    feature = "stm32f405",
+    feature = "stm32f407",
+    feature = "stm32f410",
+    feature = "stm32f411",
+    feature = "stm32f412",
+    feature = "stm32f413",
+    feature = "stm32f415",
+    feature = "stm32f417",
+    feature = "stm32f423",
+    feature = "stm32f427",
+    feature = "stm32f429",
+    feature = "stm32f437",
+    feature = "stm32f439",
+    feature = "stm32f446",
+    feature = "stm32f469",
+    feature = "stm32f479"
+))]
+nss_pins! {
+    SPI1: [
+        crate::gpio::gpioa::PA4<Alternate<AF5>>,
+        crate::gpio::gpioa::PA15<Alternate<AF5>>
+    ]
+    SPI2: [
+        crate::gpio::gpiob::PB9<Alternate<AF5>>,
+        crate::gpio::gpiob::PB12<Alternate<AF5>>
+    ]
+}
+
+#[cfg(any(
+    feature = "stm32f401",
+    feature = "stm32f405",
+    feature = "stm32f407",
+    feature = "stm32f411",
+    feature = "stm32f412",
+    feature = "stm32f413",
+    feature = "stm32f415",
+    feature = "stm32f417",
+    feature = "stm32f423",
+    feature = "stm32f427",
+    feature = "stm32f429",
+    feature = "stm32f437",
+    feature = "stm32f439",
+    feature = "stm32f446",
+    feature = "stm32f469",
+    feature = "stm32f479"
+))]
+nss_pins! {
+    SPI3: [
+        crate::gpio::gpioa::PA4<Alternate<AF6>>,
+        crate::gpio::gpioa::PA15<Alternate<AF6>>
+    ]
+}
+
+#[cfg(any(
+    feature = "stm32f401",
+    feature = "stm32f411",
+    feature = "stm32f412",
+    feature = "stm32f413",
+    feature = "stm32f423",
+    feature = "stm32f427",
+    feature = "stm32f429",
+    feature = "stm32f437",
+    feature = "stm32f439",
+    feature = "stm32f446",
+    feature = "stm32f469",
+    feature = "stm32f479"
+))]
+nss_pins! {
+    SPI4: [
+        crate::gpio::gpioe::PE4<Alternate<AF5>>,
+        crate::gpio::gpioe::PE11<Alternate<AF5>>
+    ]
+}
+
+#[cfg(any(
+    feature = "stm32f405",
+    feature = "stm32f407",
+    feature = "stm32f415",
+    feature = "stm32f417",
+    feature = "stm32f427",
+    feature = "stm32f429",
+    feature = "stm32f437",
+    feature = "stm32f439",
+    feature = "stm32f469",
+    feature = "stm32f479"
+))]
+nss_pins! {
+    SPI2: [crate::gpio::gpioi::PI0<Alternate<AF5>>]
+}
+
+#[cfg(any(
+    feature = "stm32f410",
+    feature = "stm32f411",
+    feature = "stm32f412",
+    feature = "stm32f413",
+    feature = "stm32f423"
+))]
+nss_pins! {
+    SPI5: [crate::gpio::gpiob::PB1<Alternate<AF6>>]
+}
+
+#[cfg(any(
+    feature = "stm32f411",
+    feature = "stm32f412",
+    feature = "stm32f413",
+    feature = "stm32f423"
+))]
+nss_pins! {
+    SPI5: [
+        crate::gpio::gpioe::PE4<Alternate<AF6>>,
+        crate::gpio::gpioe::PE11<Alternate<AF6>>
+    ]
+}
+
+#[cfg(any(
+    feature = "stm32f427",
+    feature = "stm32f429",
+    feature = "stm32f437",
+    feature = "stm32f439",
+    feature = "stm32f469",
+    feature = "stm32f479"
+))]
+nss_pins! {
+    SPI5: [
+        crate::gpio::gpiof::PF6<Alternate<AF5>>,
+        crate::gpio::gpioh::PH5<Alternate<AF5>>
+    ]
+    SPI6: [crate::gpio::gpiog::PG8<Alternate<AF5>>]
+}
+
+#[cfg(feature = "stm32f446")]
+nss_pins! {
+    SPI4: [crate::gpio::gpiog::PG14<Alternate<AF6>>]
+}
+
 /// Interrupt events
 pub enum Event {
     /// New data has been received
@@ -719,6 +1013,207 @@ pub struct Spi<SPI, PINS> {
     pins: PINS,
 }
 
+/// SPI receive side of a DMA transfer, see `Spi::transfer_dma`
+pub struct Rx<SPI> {
+    _spi: PhantomData<SPI>,
+}
+
+/// SPI transmit side of a DMA transfer, see `Spi::transfer_dma`
+pub struct Tx<SPI> {
+    _spi: PhantomData<SPI>,
+}
+
+unsafe impl<SPI> PeriAddress for Rx<SPI>
+where
+    SPI: Instance,
+{
+    #[inline(always)]
+    fn address(&self) -> u32 {
+        &(unsafe { &(*SPI::ptr()) }.dr) as *const _ as u32
+    }
+
+    type MemSize = u8;
+}
+
+unsafe impl<SPI> PeriAddress for Tx<SPI>
+where
+    SPI: Instance,
+{
+    #[inline(always)]
+    fn address(&self) -> u32 {
+        &(unsafe { &(*SPI::ptr()) }.dr) as *const _ as u32
+    }
+
+    type MemSize = u8;
+}
+
+unsafe impl<SPI, PINS> PeriAddress for Spi<SPI, PINS>
+where
+    SPI: PeriAddress,
+{
+    #[inline(always)]
+    fn address(&self) -> u32 {
+        self.spi.address()
+    }
+
+    type MemSize = SPI::MemSize;
+
+    const REQUEST: bool = SPI::REQUEST;
+}
+
+unsafe impl<STREAM, CHANNEL, SPI, PINS, DIR> DMASet for (STREAM, CHANNEL, Spi<SPI, PINS>, DIR) where
+    (STREAM, CHANNEL, SPI, DIR): DMASet
+{
+}
+
+/// SPI peripheral
+pub trait Instance: Deref<Target = spi1::RegisterBlock> {
+    /// Returns a pointer to the register block
+    fn ptr() -> *const spi1::RegisterBlock;
+
+    /// Returns the clock of the bus the SPI is connected to
+    fn clock(clocks: &Clocks) -> Hertz;
+
+    /// Enables the peripheral clock
+    fn enable_clock();
+}
+
+macro_rules! instance {
+    ($($SPIX:ident: ($apbXenr:ident, $rcc_bit:expr, $pclkX:ident),)+) => {
+        $(
+            impl Instance for $SPIX {
+                fn ptr() -> *const spi1::RegisterBlock {
+                    $SPIX::ptr()
+                }
+
+                fn clock(clocks: &Clocks) -> Hertz {
+                    clocks.$pclkX()
+                }
+
+                fn enable_clock() {
+                    unsafe {
+                        // NOTE(unsafe) this reference will only be used for atomic writes with no side effects.
+                        let rcc = &(*RCC::ptr());
+
+                        // Enable clock.
+                        bb::set(&rcc.$apbXenr, $rcc_bit);
+                    }
+                }
+            }
+        )+
+    };
+}
+
+#[cfg(any(
+    feature = "stm32f401",
+    feature = "stm32f405",
+    feature = "stm32f407",
+    feature = "stm32f410",
+    feature = "stm32f411",
+    feature = "stm32f412",
+    feature = "stm32f413",
+    feature = "stm32f415",
+    feature = "stm32f417",
+    feature = "stm32f423",
+    feature = "stm32f427",
+    feature = "stm32f429",
+    feature = "stm32f437",
+    feature = "stm32f439",
+    feature = "stm32f446",
+    feature = "stm32f469",
+    feature = "stm32f479"
+))]
+instance! {
+    SPI1: (apb2enr, 12, pclk2),
+    SPI2: (apb1enr, 14, pclk1),
+}
+
+#[cfg(any(
+    feature = "stm32f401",
+    feature = "stm32f405",
+    feature = "stm32f407",
+    feature = "stm32f411",
+    feature = "stm32f412",
+    feature = "stm32f413",
+    feature = "stm32f415",
+    feature = "stm32f417",
+    feature = "stm32f423",
+    feature = "stm32f427",
+    feature = "stm32f429",
+    feature = "stm32f437",
+    feature = "stm32f439",
+    feature = "stm32f446",
+    feature = "stm32f469",
+    feature = "stm32f479"
+))]
+instance! {
+    SPI3: (apb1enr, 15, pclk1),
+}
+
+#[cfg(any(
+    feature = "stm32f401",
+    feature = "stm32f411",
+    feature = "stm32f412",
+    feature = "stm32f413",
+    feature = "stm32f423",
+    feature = "stm32f427",
+    feature = "stm32f429",
+    feature = "stm32f437",
+    feature = "stm32f439",
+    feature = "stm32f446",
+    feature = "stm32f469",
+    feature = "stm32f479"
+))]
+instance! {
+    SPI4: (apb2enr, 13, pclk2),
+}
+
+#[cfg(any(
+    feature = "stm32f410",
+    feature = "stm32f411",
+    feature = "stm32f412",
+    feature = "stm32f413",
+    feature = "stm32f423",
+    feature = "stm32f427",
+    feature = "stm32f429",
+    feature = "stm32f437",
+    feature = "stm32f439",
+    feature = "stm32f469",
+    feature = "stm32f479"
+))]
+instance! {
+    SPI5: (apb2enr, 20, pclk2),
+}
+
+#[cfg(any(
+    feature = "stm32f427",
+    feature = "stm32f429",
+    feature = "stm32f437",
+    feature = "stm32f439",
+    feature = "stm32f469",
+    feature = "stm32f479"
+))]
+instance! {
+    SPI6: (apb2enr, 21, pclk2),
+}
+
+impl<SPI, PINS> Spi<SPI, PINS>
+where
+    SPI: Instance,
+{
+    /// Enables the clock of `spi` and configures it as master
+    pub fn new(spi: SPI, pins: PINS, config: config::Config, clocks: Clocks) -> Self
+    where
+        PINS: Pins<SPI>,
+    {
+        SPI::enable_clock();
+
+        let mut spi = Spi { spi, pins };
+        spi.configure(config, SPI::clock(&clocks));
+        spi
+    }
+}
+
 #[cfg(any(
     feature = "stm32f401",
     feature = "stm32f405",
@@ -922,11 +1417,42 @@ impl<SPI, PINS> Spi<SPI, PINS>
 where
     SPI: Deref<Target = spi1::RegisterBlock>,
 {
-    pub fn init(self, mode: Mode, freq: Hertz, clock: Hertz) -> Self {
-        // disable SS output
-        self.spi.cr2.write(|w| w.ssoe().clear_bit());
+    pub fn init(mut self, mode: Mode, freq: Hertz, clock: Hertz) -> Self
+    where
+        PINS: Pins<SPI>,
+    {
+        self.configure(config::Config::default().mode(mode).frequency(freq), clock);
+        self
+    }
+
+    /// Applies `config`, `clock` is the clock of the bus the SPI is connected to. The SPI is
+    /// disabled while it's reconfigured, which also resets the CRC.
+    ///
+    /// With an NSS pin the SPI stays disabled until a [`transaction`](#method.transaction)
+    /// starts. The blocking `Transfer` and `Write` implementations run in a transaction on
+    /// their own, `FullDuplex` returns `Error::Disabled` outside of one.
+    pub fn configure(&mut self, config: config::Config, clock: Hertz)
+    where
+        PINS: Pins<SPI>,
+    {
+        // The frame format and the CRC can only be changed while the SPI is disabled
+        self.spi.cr1.modify(|_, w| w.spe().clear_bit());
+
+        // ssoe: drive NSS while the SPI is enabled if there's an NSS pin
+        self.spi.cr2.write(|w| {
+            w.ssoe()
+                .bit(PINS::HAS_NSS)
+                .frf()
+                .bit(config.frame_format == config::FrameFormat::Ti)
+        });
+
+        if let Some(polynomial) = config.crc_polynomial {
+            self.spi
+                .crcpr
+                .write(|w| unsafe { w.bits(u32::from(polynomial)) });
+        }
 
-        let br = match clock.0 / freq.0 {
+        let br = match clock.0 / config.frequency.0 {
             0 => unreachable!(),
             1..=2 => 0b000,
             3..=5 => 0b001,
@@ -939,12 +1465,14 @@ where
         };
 
         // mstr: master configuration
-        // lsbfirst: MSB first
-        // ssm: enable software slave management (NSS pin free for other uses)
+        // ssm: enable software slave management without an NSS pin (NSS pin free for other
+        //      uses)
         // ssi: set nss high = master mode
-        // dff: 8 bit frames
-        // bidimode: 2-line unidirectional
-        // spe: enable the SPI bus
+        // bidimode: 1-line bidirectional in 3-wire mode, starting as output
+        // spe: enable the SPI bus, only in a transaction with an NSS pin and never in
+        //      receive-only mode as that starts the clock
+        let mode = config.mode;
+        let enable = !PINS::HAS_NSS && config.direction != config::Direction::ReceiveOnly;
         self.spi.cr1.write(|w| {
             w.cpha()
                 .bit(mode.phase == Phase::CaptureOnSecondTransition)
@@ -955,22 +1483,221 @@ where
                 .br()
                 .bits(br)
                 .lsbfirst()
-                .clear_bit()
+                .bit(config.bit_order == config::BitOrder::LsbFirst)
                 .ssm()
-                .set_bit()
+                .bit(!PINS::HAS_NSS)
                 .ssi()
                 .set_bit()
                 .rxonly()
-                .clear_bit()
+                .bit(config.direction == config::Direction::ReceiveOnly)
+                .crcen()
+                .bit(config.crc_polynomial.is_some())
                 .dff()
-                .clear_bit()
+                .bit(config.frame_size == config::FrameSize::Bits16)
                 .bidimode()
-                .clear_bit()
-                .spe()
+                .bit(config.direction == config::Direction::Bidirectional)
+                .bidioe()
                 .set_bit()
+                .spe()
+                .bit(enable)
         });
+    }
 
-        self
+    /// Runs `f` with NSS asserted when there's an NSS pin: the SPI is enabled, which pulls NSS
+    /// low, and disabled again once the last frame is transferred, which releases NSS. NSS
+    /// floats while released, so it needs a pull-up. Without an NSS pin, or inside another
+    /// transaction, this just runs `f`.
+    pub fn transaction<R>(&mut self, f: impl FnOnce(&mut Self) -> R) -> R {
+        // `configure` sets SSOE only with an NSS pin
+        let nss = self.spi.cr2.read().ssoe().bit_is_set();
+        if !nss || self.spi.cr1.read().spe().bit_is_set() {
+            return f(self);
+        }
+
+        self.spi.cr1.modify(|_, w| w.spe().set_bit());
+        let result = f(self);
+        self.wait_idle();
+        self.spi.cr1.modify(|_, w| w.spe().clear_bit());
+        result
+    }
+
+    /// Waits until the last frame is transferred
+    fn wait_idle(&self) {
+        while self.spi.sr.read().txe().bit_is_clear() {}
+        while self.spi.sr.read().bsy().bit_is_set() {}
+    }
+
+    /// Sends `words` without reading the received data, in 3-wire mode on the data line.
+    /// Returns once the last frame is transferred, or `Error::Direction` in receive-only mode.
+    pub fn transmit(&mut self, words: &[u8]) -> Result<(), Error> {
+        let cr1 = self.spi.cr1.read();
+        if cr1.rxonly().bit_is_set() {
+            return Err(Error::Direction);
+        }
+        if cr1.spe().bit_is_clear() {
+            return Err(Error::Disabled);
+        }
+        for &word in words {
+            // The received data is never read, so OVR is expected here and only cleared at
+            // the end
+            loop {
+                let sr = self.spi.sr.read();
+                if sr.modf().bit_is_set() {
+                    return Err(Error::ModeFault);
+                }
+                if sr.txe().bit_is_set() {
+                    break;
+                }
+            }
+            // NOTE(write_volatile) 8-bit write that's not possible through the svd2rust API
+            unsafe { ptr::write_volatile(&self.spi.dr as *const _ as *mut u8, word) }
+        }
+        self.wait_idle();
+
+        // Reading the data register and then the status register clears the overrun caused
+        // by the unread data
+        unsafe { ptr::read_volatile(&self.spi.dr as *const _ as *const u8) };
+        self.spi.sr.read();
+        Ok(())
+    }
+
+    /// Receives `words` in receive-only mode or in 3-wire mode on the data line, the master
+    /// generates the clock for exactly `words.len()` frames.
+    ///
+    /// The clock runs as long as the SPI is enabled in receive direction, so it's disabled
+    /// one clock cycle into the last frame as described in the reference manual ("Disabling
+    /// the SPI"), which lets the last frame complete. Afterwards the data line is switched
+    /// back to output in 3-wire mode. `clocks` are used to time that.
+    ///
+    /// Returns `Error::Direction` in full-duplex mode, where the master only generates the
+    /// clock for sent frames.
+    pub fn receive(&mut self, words: &mut [u8], clocks: &Clocks) -> Result<(), Error>
+    where
+        SPI: Instance,
+    {
+        let cr1 = self.spi.cr1.read();
+        if cr1.rxonly().bit_is_clear() && cr1.bidimode().bit_is_clear() {
+            return Err(Error::Direction);
+        }
+        let restart = cr1.spe().bit_is_set() && cr1.rxonly().bit_is_clear();
+        // Core clock cycles of one SPI clock cycle
+        let cycles = (clocks.sysclk().0 / SPI::clock(clocks).0) << (cr1.br().bits() + 1);
+        let last = match words.len() {
+            0 => return Ok(()),
+            n => n - 1,
+        };
+
+        // Drop stale data
+        self.spi.cr1.modify(|_, w| w.spe().clear_bit());
+        while self.spi.sr.read().rxne().bit_is_set() {
+            unsafe { ptr::read_volatile(&self.spi.dr as *const _ as *const u8) };
+        }
+
+        // Enabling the SPI in receive direction starts the clock
+        self.spi
+            .cr1
+            .modify(|_, w| w.bidioe().clear_bit().spe().set_bit());
+
+        let mut result = Ok(());
+        for (i, word) in words.iter_mut().enumerate() {
+            if i == last {
+                // The last frame started with the previous RXNE, stop after it
+                cortex_m::asm::delay(cycles);
+                self.spi.cr1.modify(|_, w| w.spe().clear_bit());
+            }
+
+            loop {
+                let sr = self.spi.sr.read();
+                if sr.ovr().bit_is_set() {
+                    result = Err(Error::Overrun);
+                }
+                if sr.rxne().bit_is_set() {
+                    break;
+                }
+            }
+            // NOTE(read_volatile) read only 1 byte (the svd2rust API only allows
+            // reading a half-word)
+            *word = unsafe { ptr::read_volatile(&self.spi.dr as *const _ as *const u8) };
+        }
+        // Reading the status register after the data register clears a pending overrun
+        self.spi.sr.read();
+
+        self.spi.cr1.modify(|_, w| w.bidioe().set_bit());
+        if restart {
+            self.spi.cr1.modify(|_, w| w.spe().set_bit());
+        }
+        result
+    }
+
+    /// Sends the transmit CRC after the frame that's currently in the data register. Call this
+    /// right after writing the last data frame; the received CRC is then checked against the
+    /// receive CRC and a mismatch is reported by the next `read` as `Error::Crc`.
+    pub fn send_crc(&mut self) {
+        self.spi.cr1.modify(|_, w| w.crcnext().set_bit());
+    }
+
+    /// Returns the CRC calculated over the transmitted frames
+    pub fn tx_crc(&self) -> u16 {
+        self.spi.txcrcr.read().bits() as u16
+    }
+
+    /// Returns the CRC calculated over the received frames
+    pub fn rx_crc(&self) -> u16 {
+        self.spi.rxcrcr.read().bits() as u16
+    }
+
+    /// Resets both CRC calculations, only while no transfer is in progress. Does nothing if the
+    /// CRC isn't enabled.
+    pub fn reset_crc(&mut self) {
+        let cr1 = self.spi.cr1.read();
+        if cr1.crcen().bit_is_clear() {
+            return;
+        }
+        self.spi
+            .cr1
+            .modify(|_, w| w.spe().clear_bit().crcen().clear_bit());
+        self.spi
+            .cr1
+            .modify(|_, w| w.crcen().set_bit().spe().bit(cr1.spe().bit()));
+    }
+
+    /// Returns an error if one of the error flags is set, clears CRCERR
+    fn check_errors(&mut self) -> Result<(), Error> {
+        let sr = self.spi.sr.read();
+
+        if sr.ovr().bit_is_set() {
+            Err(Error::Overrun)
+        } else if sr.modf().bit_is_set() {
+            Err(Error::ModeFault)
+        } else if sr.crcerr().bit_is_set() {
+            self.spi.sr.modify(|_, w| w.crcerr().clear_bit());
+            Err(Error::Crc)
+        } else {
+            Ok(())
+        }
+    }
+
+    fn check_read(&mut self) -> nb::Result<(), Error> {
+        self.check_errors()?;
+        if self.spi.sr.read().rxne().bit_is_set() {
+            Ok(())
+        } else if self.spi.cr1.read().spe().bit_is_clear() {
+            // Nothing is going to be received
+            Err(nb::Error::Other(Error::Disabled))
+        } else {
+            Err(nb::Error::WouldBlock)
+        }
+    }
+
+    fn check_send(&mut self) -> nb::Result<(), Error> {
+        self.check_errors()?;
+        if self.spi.cr1.read().spe().bit_is_clear() {
+            Err(nb::Error::Other(Error::Disabled))
+        } else if self.spi.sr.read().txe().bit_is_set() {
+            Ok(())
+        } else {
+            Err(nb::Error::WouldBlock)
+        }
     }
 
     /// Enable interrupts for the given `event`:
@@ -1021,6 +1748,11 @@ where
         self.spi.sr.read().ovr().bit_is_set()
     }
 
+    /// Return `true` while a transfer is in progress
+    pub fn is_busy(&self) -> bool {
+        self.spi.sr.read().bsy().bit_is_set()
+    }
+
     pub fn free(self) -> (SPI, PINS) {
         (self.spi, self.pins)
     }
@@ -1033,48 +1765,80 @@ where
     type Error = Error;
 
     fn read(&mut self) -> nb::Result<u8, Error> {
-        let sr = self.spi.sr.read();
-
-        Err(if sr.ovr().bit_is_set() {
-            nb::Error::Other(Error::Overrun)
-        } else if sr.modf().bit_is_set() {
-            nb::Error::Other(Error::ModeFault)
-        } else if sr.crcerr().bit_is_set() {
-            nb::Error::Other(Error::Crc)
-        } else if sr.rxne().bit_is_set() {
-            // NOTE(read_volatile) read only 1 byte (the svd2rust API only allows
-            // reading a half-word)
-            return Ok(unsafe { ptr::read_volatile(&self.spi.dr as *const _ as *const u8) });
-        } else {
-            nb::Error::WouldBlock
-        })
+        self.check_read()?;
+        // NOTE(read_volatile) read only 1 byte (the svd2rust API only allows
+        // reading a half-word)
+        Ok(unsafe { ptr::read_volatile(&self.spi.dr as *const _ as *const u8) })
     }
 
     fn send(&mut self, byte: u8) -> nb::Result<(), Error> {
-        let sr = self.spi.sr.read();
-
-        Err(if sr.ovr().bit_is_set() {
-            nb::Error::Other(Error::Overrun)
-        } else if sr.modf().bit_is_set() {
-            nb::Error::Other(Error::ModeFault)
-        } else if sr.crcerr().bit_is_set() {
-            nb::Error::Other(Error::Crc)
-        } else if sr.txe().bit_is_set() {
-            // NOTE(write_volatile) see note above
-            unsafe { ptr::write_volatile(&self.spi.dr as *const _ as *mut u8, byte) }
-            return Ok(());
-        } else {
-            nb::Error::WouldBlock
-        })
+        self.check_send()?;
+        // NOTE(write_volatile) see note above
+        unsafe { ptr::write_volatile(&self.spi.dr as *const _ as *mut u8, byte) }
+        Ok(())
     }
 }
 
-impl<SPI, PINS> embedded_hal::blocking::spi::transfer::Default<u8> for Spi<SPI, PINS> where
-    SPI: Deref<Target = spi1::RegisterBlock>
+/// Needs 16 bit frames, see `config::Config::frame_size`
+impl<SPI, PINS> spi::FullDuplex<u16> for Spi<SPI, PINS>
+where
+    SPI: Deref<Target = spi1::RegisterBlock>,
 {
+    type Error = Error;
+
+    fn read(&mut self) -> nb::Result<u16, Error> {
+        self.check_read()?;
+        Ok(self.spi.dr.read().dr().bits())
+    }
+
+    fn send(&mut self, word: u16) -> nb::Result<(), Error> {
+        self.check_send()?;
+        self.spi.dr.write(|w| w.dr().bits(word));
+        Ok(())
+    }
 }
 
-impl<SPI, PINS> embedded_hal::blocking::spi::write::Default<u8> for Spi<SPI, PINS> where
-    SPI: Deref<Target = spi1::RegisterBlock>
-{
+macro_rules! blocking {
+    ($($W:ty),+) => {
+        $(
+            /// Runs in a `transaction`, so NSS is asserted during the transfer
+            impl<SPI, PINS> blocking::spi::Transfer<$W> for Spi<SPI, PINS>
+            where
+                SPI: Deref<Target = spi1::RegisterBlock>,
+            {
+                type Error = Error;
+
+                fn transfer<'w>(&mut self, words: &'w mut [$W]) -> Result<&'w [$W], Error> {
+                    self.transaction(|spi| -> Result<(), Error> {
+                        for word in words.iter_mut() {
+                            block!(spi::FullDuplex::<$W>::send(spi, *word))?;
+                            *word = block!(spi::FullDuplex::<$W>::read(spi))?;
+                        }
+                        Ok(())
+                    })?;
+                    Ok(words)
+                }
+            }
+
+            /// Runs in a `transaction`, so NSS is asserted during the transfer
+            impl<SPI, PINS> blocking::spi::Write<$W> for Spi<SPI, PINS>
+            where
+                SPI: Deref<Target = spi1::RegisterBlock>,
+            {
+                type Error = Error;
+
+                fn write(&mut self, words: &[$W]) -> Result<(), Error> {
+                    self.transaction(|spi| {
+                        for &word in words {
+                            block!(spi::FullDuplex::<$W>::send(spi, word))?;
+                            block!(spi::FullDuplex::<$W>::read(spi))?;
+                        }
+                        Ok(())
+                    })
+                }
+            }
+        )+
+    };
 }
+
+blocking!(u8, u16);
@@ -0,0 +1,418 @@
+//! SPI slave mode
+//!
+//! [`SpiSlave`](struct.SpiSlave.html) answers a master that drives the clock. With an NSS pin
+//! the SPI only shifts data while the master pulls NSS low. With [`NoNss`](../struct.NoNss.html)
+//! the slave select is managed in software through
+//! [`select`](struct.SpiSlave.html#method.select), and the slave starts out selected.
+//!
+//! The byte sent during the first clocks has to be in the data register before the master
+//! starts a transfer, write it with [`preload`](struct.SpiSlave.html#method.preload). Received
+//! bytes can be taken from the RXNE interrupt or moved by a DMA stream: pass the `SpiSlave` to
+//! `dma::Transfer` and enable the DMA requests with
+//! [`enable_dma`](struct.SpiSlave.html#method.enable_dma).
+
+use core::ops::Deref;
+use core::ptr;
+
+use embedded_hal::spi;
+
+#[cfg(any(
+    feature = "stm32f401",
+    feature = "stm32f405",
+    feature = "stm32f407",
+    feature = "stm32f410",
+    feature = "stm32f411",
+    feature = "stm32f412",
+    feature = "stm32f413",
+    feature = "stm32f415",
+    feature = "stm32f417",
+    feature = "stm32f423",
+    feature = "stm32f427",
+    feature = "stm32f429",
+    feature = "stm32f437",
+    feature = "stm32f439",
+    feature = "stm32f446",
+    feature = "stm32f469",
+    feature = "stm32f479"
+))]
+use crate::stm32::{SPI1, SPI2};
+
+#[cfg(any(
+    feature = "stm32f401",
+    feature = "stm32f405",
+    feature = "stm32f407",
+    feature = "stm32f411",
+    feature = "stm32f412",
+    feature = "stm32f413",
+    feature = "stm32f415",
+    feature = "stm32f417",
+    feature = "stm32f423",
+    feature = "stm32f427",
+    feature = "stm32f429",
+    feature = "stm32f437",
+    feature = "stm32f439",
+    feature = "stm32f446",
+    feature = "stm32f469",
+    feature = "stm32f479"
+))]
+use crate::stm32::SPI3;
+
+#[cfg(any(
+    feature = "stm32f401",
+    feature = "stm32f411",
+    feature = "stm32f412",
+    feature = "stm32f413",
+    feature = "stm32f423",
+    feature = "stm32f427",
+    feature = "stm32f429",
+    feature = "stm32f437",
+    feature = "stm32f439",
+    feature = "stm32f446",
+    feature = "stm32f469",
+    feature = "stm32f479"
+))]
+use crate::stm32::SPI4;
+
+#[cfg(any(
+    feature = "stm32f410",
+    feature = "stm32f411",
+    feature = "stm32f412",
+    feature = "stm32f413",
+    feature = "stm32f423",
+    feature = "stm32f427",
+    feature = "stm32f429",
+    feature = "stm32f437",
+    feature = "stm32f439",
+    feature = "stm32f469",
+    feature = "stm32f479"
+))]
+use crate::stm32::SPI5;
+
+#[cfg(any(
+    feature = "stm32f427",
+    feature = "stm32f429",
+    feature = "stm32f437",
+    feature = "stm32f439",
+    feature = "stm32f469",
+    feature = "stm32f479"
+))]
+use crate::stm32::SPI6;
+
+use crate::bb;
+use crate::dma::traits::{DMASet, PeriAddress};
+use crate::stm32::{spi1, RCC};
+
+use super::{Error, Event, Mode, Phase, PinMiso, PinMosi, PinNss, PinSck, Polarity};
+
+pub trait Pins<SPI> {
+    #[doc(hidden)]
+    const HAS_NSS: bool;
+}
+
+/// `(sck, miso, mosi, nss)`, use `NoNss` for software slave select
+impl<SPI, SCK, MISO, MOSI, NSS> Pins<SPI> for (SCK, MISO, MOSI, NSS)
+where
+    SCK: PinSck<SPI>,
+    MISO: PinMiso<SPI>,
+    MOSI: PinMosi<SPI>,
+    NSS: PinNss<SPI>,
+{
+    const HAS_NSS: bool = NSS::CONNECTED;
+}
+
+/// SPI in slave mode
+#[derive(Debug)]
+pub struct SpiSlave<SPI, PINS> {
+    spi: SPI,
+    pins: PINS,
+}
+
+macro_rules! slave {
+    ($SPIX:ident: ($spiX:ident, $apbXenr:ident, $rcc_bit:expr)) => {
+        impl<PINS> SpiSlave<$SPIX, PINS> {
+            pub fn $spiX(spi: $SPIX, pins: PINS, mode: Mode) -> Self
+            where
+                PINS: Pins<$SPIX>,
+            {
+                unsafe {
+                    // NOTE(unsafe) this reference will only be used for atomic writes with no side effects.
+                    let rcc = &(*RCC::ptr());
+
+                    // Enable clock.
+                    bb::set(&rcc.$apbXenr, $rcc_bit);
+                }
+
+                SpiSlave { spi, pins }.init(mode, PINS::HAS_NSS)
+            }
+        }
+    };
+}
+
+#[cfg(any(
+    feature = "stm32f401",
+    feature = "stm32f405",
+    feature = "stm32f407",
+    feature = "stm32f410",
+    feature = "stm32f411",
+    feature = "stm32f412",
+    feature = "stm32f413",
+    feature = "stm32f415",
+    feature = "stm32f417",
+    feature = "stm32f423",
+    feature = "stm32f427",
+    feature = "stm32f429",
+    feature = "stm32f437",
+    feature = "stm32f439",
+    feature = "stm32f446",
+    feature = "stm32f469",
+    feature = "stm32f479"
+))]
+slave!(SPI1: (spi1, apb2enr, 12));
+
+#[cfg(any(
+    feature = "stm32f401",
+    feature = "stm32f405",
+    feature = "stm32f407",
+    feature = "stm32f410",
+    feature = "stm32f411",
+    feature = "stm32f412",
+    feature = "stm32f413",
+    feature = "stm32f415",
+    feature = "stm32f417",
+    feature = "stm32f423",
+    feature = "stm32f427",
+    feature = "stm32f429",
+    feature = "stm32f437",
+    feature = "stm32f439",
+    feature = "stm32f446",
+    feature = "stm32f469",
+    feature = "stm32f479"
+))]
+slave!(SPI2: (spi2, apb1enr, 14));
+
+#[cfg(any(
+    feature = "stm32f401",
+    feature = "stm32f405",
+    feature = "stm32f407",
+    feature = "stm32f411",
+    feature = "stm32f412",
+    feature = "stm32f413",
+    feature = "stm32f415",
+    feature = "stm32f417",
+    feature = "stm32f423",
+    feature = "stm32f427",
+    feature = "stm32f429",
+    feature = "stm32f437",
+    feature = "stm32f439",
+    feature = "stm32f446",
+    feature = "stm32f469",
+    feature = "stm32f479"
+))]
+slave!(SPI3: (spi3, apb1enr, 15));
+
+#[cfg(any(
+    feature = "stm32f401",
+    feature = "stm32f411",
+    feature = "stm32f412",
+    feature = "stm32f413",
+    feature = "stm32f423",
+    feature = "stm32f427",
+    feature = "stm32f429",
+    feature = "stm32f437",
+    feature = "stm32f439",
+    feature = "stm32f446",
+    feature = "stm32f469",
+    feature = "stm32f479"
+))]
+slave!(SPI4: (spi4, apb2enr, 13));
+
+#[cfg(any(
+    feature = "stm32f410",
+    feature = "stm32f411",
+    feature = "stm32f412",
+    feature = "stm32f413",
+    feature = "stm32f423",
+    feature = "stm32f427",
+    feature = "stm32f429",
+    feature = "stm32f437",
+    feature = "stm32f439",
+    feature = "stm32f469",
+    feature = "stm32f479"
+))]
+slave!(SPI5: (spi5, apb2enr, 20));
+
+#[cfg(any(
+    feature = "stm32f427",
+    feature = "stm32f429",
+    feature = "stm32f437",
+    feature = "stm32f439",
+    feature = "stm32f469",
+    feature = "stm32f479"
+))]
+slave!(SPI6: (spi6, apb2enr, 21));
+
+impl<SPI, PINS> SpiSlave<SPI, PINS>
+where
+    SPI: Deref<Target = spi1::RegisterBlock>,
+{
+    fn init(self, mode: Mode, hardware_nss: bool) -> Self {
+        self.spi.cr2.reset();
+
+        // mstr: slave configuration
+        // ssm: software slave management without an NSS pin
+        // ssi: selected when managed in software
+        // dff: 8 bit frames
+        // spe: enable the SPI bus
+        self.spi.cr1.write(|w| {
+            w.cpha()
+                .bit(mode.phase == Phase::CaptureOnSecondTransition)
+                .cpol()
+                .bit(mode.polarity == Polarity::IdleHigh)
+                .mstr()
+                .clear_bit()
+                .lsbfirst()
+                .clear_bit()
+                .ssm()
+                .bit(!hardware_nss)
+                .ssi()
+                .clear_bit()
+                .rxonly()
+                .clear_bit()
+                .dff()
+                .clear_bit()
+                .bidimode()
+                .clear_bit()
+                .spe()
+                .set_bit()
+        });
+
+        self
+    }
+
+    /// Enable interrupts for the given `event`:
+    ///  - Received data ready to be read (RXNE)
+    ///  - Transmit data register empty (TXE)
+    ///  - Transfer error
+    pub fn listen(&mut self, event: Event) {
+        match event {
+            Event::Rxne => self.spi.cr2.modify(|_, w| w.rxneie().set_bit()),
+            Event::Txe => self.spi.cr2.modify(|_, w| w.txeie().set_bit()),
+            Event::Error => self.spi.cr2.modify(|_, w| w.errie().set_bit()),
+        }
+    }
+
+    /// Disable interrupts for the given `event`:
+    ///  - Received data ready to be read (RXNE)
+    ///  - Transmit data register empty (TXE)
+    ///  - Transfer error
+    pub fn unlisten(&mut self, event: Event) {
+        match event {
+            Event::Rxne => self.spi.cr2.modify(|_, w| w.rxneie().clear_bit()),
+            Event::Txe => self.spi.cr2.modify(|_, w| w.txeie().clear_bit()),
+            Event::Error => self.spi.cr2.modify(|_, w| w.errie().clear_bit()),
+        }
+    }
+
+    /// Writes the byte that's shifted out during the next transfer. Call this before the
+    /// master starts clocking, e.g. after the previous transfer or from the NSS falling edge
+    /// interrupt. The byte is only written if the transmit register is empty.
+    pub fn preload(&mut self, byte: u8) -> nb::Result<(), Error> {
+        if self.spi.sr.read().txe().bit_is_set() {
+            // NOTE(write_volatile) 8-bit write that's not possible through the svd2rust API
+            unsafe { ptr::write_volatile(&self.spi.dr as *const _ as *mut u8, byte) }
+            Ok(())
+        } else {
+            Err(nb::Error::WouldBlock)
+        }
+    }
+
+    /// Selects or deselects the slave when NSS is managed in software (`NoNss`). The SPI
+    /// ignores the clock while deselected.
+    pub fn select(&mut self, selected: bool) {
+        self.spi.cr1.modify(|_, w| w.ssi().bit(!selected));
+    }
+
+    /// Enables or disables the receive and transmit DMA requests
+    pub fn enable_dma(&mut self, rx: bool, tx: bool) {
+        self.spi
+            .cr2
+            .modify(|_, w| w.rxdmaen().bit(rx).txdmaen().bit(tx));
+    }
+
+    /// Return `true` if the TXE flag is set, i.e. new data to transmit
+    /// can be written to the SPI.
+    pub fn is_txe(&self) -> bool {
+        self.spi.sr.read().txe().bit_is_set()
+    }
+
+    /// Return `true` if the RXNE flag is set, i.e. new data has been received
+    /// and can be read from the SPI.
+    pub fn is_rxne(&self) -> bool {
+        self.spi.sr.read().rxne().bit_is_set()
+    }
+
+    /// Return `true` if the OVR flag is set, i.e. new data has been received
+    /// while the receive data register was already filled.
+    pub fn is_ovr(&self) -> bool {
+        self.spi.sr.read().ovr().bit_is_set()
+    }
+
+    /// Return `true` while a transfer is in progress
+    pub fn is_busy(&self) -> bool {
+        self.spi.sr.read().bsy().bit_is_set()
+    }
+
+    /// Disables the SPI and returns the peripheral and the pins
+    pub fn free(self) -> (SPI, PINS) {
+        self.spi.cr2.reset();
+        self.spi.cr1.modify(|_, w| w.spe().clear_bit());
+        (self.spi, self.pins)
+    }
+}
+
+impl<SPI, PINS> spi::FullDuplex<u8> for SpiSlave<SPI, PINS>
+where
+    SPI: Deref<Target = spi1::RegisterBlock>,
+{
+    type Error = Error;
+
+    fn read(&mut self) -> nb::Result<u8, Error> {
+        let sr = self.spi.sr.read();
+
+        Err(if sr.ovr().bit_is_set() {
+            // Reading the data register and then the status register clears the flag
+            unsafe { ptr::read_volatile(&self.spi.dr as *const _ as *const u8) };
+            self.spi.sr.read();
+            nb::Error::Other(Error::Overrun)
+        } else if sr.rxne().bit_is_set() {
+            // NOTE(read_volatile) read only 1 byte (the svd2rust API only allows
+            // reading a half-word)
+            return Ok(unsafe { ptr::read_volatile(&self.spi.dr as *const _ as *const u8) });
+        } else {
+            nb::Error::WouldBlock
+        })
+    }
+
+    fn send(&mut self, byte: u8) -> nb::Result<(), Error> {
+        self.preload(byte)
+    }
+}
+
+unsafe impl<SPI, PINS> PeriAddress for SpiSlave<SPI, PINS>
+where
+    SPI: PeriAddress,
+{
+    #[inline(always)]
+    fn address(&self) -> u32 {
+        self.spi.address()
+    }
+
+    type MemSize = SPI::MemSize;
+
+    const REQUEST: bool = SPI::REQUEST;
+}
+
+unsafe impl<STREAM, CHANNEL, SPI, PINS, DIR> DMASet for (STREAM, CHANNEL, SpiSlave<SPI, PINS>, DIR) where
+    (STREAM, CHANNEL, SPI, DIR): DMASet
+{
+}
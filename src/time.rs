@@ -1,3 +1,6 @@
+use core::convert::TryFrom;
+use core::num::TryFromIntError;
+
 /// Bits per second
 #[derive(PartialEq, PartialOrd, Clone, Copy, Debug)]
 pub struct Bps(pub u32);
@@ -27,6 +30,12 @@ pub trait U32Ext {
 
     /// Wrap in `MilliSeconds`
     fn ms(self) -> MilliSeconds;
+
+    /// Wrap in `MicroSeconds`
+    fn us(self) -> MicroSeconds;
+
+    /// Wrap in `NanoSeconds`
+    fn ns(self) -> NanoSeconds;
 }
 
 impl U32Ext for u32 {
@@ -49,6 +58,14 @@ impl U32Ext for u32 {
     fn ms(self) -> MilliSeconds {
         MilliSeconds(self)
     }
+
+    fn us(self) -> MicroSeconds {
+        MicroSeconds(self)
+    }
+
+    fn ns(self) -> NanoSeconds {
+        NanoSeconds(self)
+    }
 }
 
 impl From<u32> for Hertz {
@@ -78,3 +95,38 @@ impl Into<KiloHertz> for MegaHertz {
 /// Time unit
 #[derive(PartialEq, PartialOrd, Clone, Copy, Debug)]
 pub struct MilliSeconds(pub u32);
+
+/// Time unit
+#[derive(PartialEq, PartialOrd, Clone, Copy, Debug)]
+pub struct MicroSeconds(pub u32);
+
+/// Time unit
+#[derive(PartialEq, PartialOrd, Clone, Copy, Debug)]
+pub struct NanoSeconds(pub u32);
+
+// Longer time units don't fit into shorter ones above about 4.3 seconds (nanoseconds) or 71
+// minutes (microseconds), so these conversions are fallible
+
+impl TryFrom<MilliSeconds> for MicroSeconds {
+    type Error = TryFromIntError;
+
+    fn try_from(t: MilliSeconds) -> Result<Self, Self::Error> {
+        u32::try_from(u64::from(t.0) * 1_000).map(MicroSeconds)
+    }
+}
+
+impl TryFrom<MilliSeconds> for NanoSeconds {
+    type Error = TryFromIntError;
+
+    fn try_from(t: MilliSeconds) -> Result<Self, Self::Error> {
+        u32::try_from(u64::from(t.0) * 1_000_000).map(NanoSeconds)
+    }
+}
+
+impl TryFrom<MicroSeconds> for NanoSeconds {
+    type Error = TryFromIntError;
+
+    fn try_from(t: MicroSeconds) -> Result<Self, Self::Error> {
+        u32::try_from(u64::from(t.0) * 1_000).map(NanoSeconds)
+    }
+}
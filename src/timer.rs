@@ -549,8 +549,11 @@ use crate::gpio::AF1;
 use crate::gpio::AF2;
 
 #[cfg(any(
+    feature = "stm32f401",
     feature = "stm32f405",
     feature = "stm32f407",
+    feature = "stm32f410",
+    feature = "stm32f411",
     feature = "stm32f412",
     feature = "stm32f413",
     feature = "stm32f415",
@@ -566,6 +569,24 @@ use crate::gpio::AF2;
 ))]
 use crate::gpio::AF3;
 
+#[cfg(any(
+    feature = "stm32f405",
+    feature = "stm32f407",
+    feature = "stm32f412",
+    feature = "stm32f413",
+    feature = "stm32f415",
+    feature = "stm32f417",
+    feature = "stm32f423",
+    feature = "stm32f427",
+    feature = "stm32f429",
+    feature = "stm32f437",
+    feature = "stm32f439",
+    feature = "stm32f446",
+    feature = "stm32f469",
+    feature = "stm32f479"
+))]
+use crate::gpio::AF9;
+
 use crate::gpio::{gpioa::*, Alternate};
 
 // Output channels marker traits
@@ -574,6 +595,12 @@ pub trait PinC2<TIM> {}
 pub trait PinC3<TIM> {}
 pub trait PinC4<TIM> {}
 
+// Complementary output and break input marker traits of the advanced-control timers
+pub trait PinC1N<TIM> {}
+pub trait PinC2N<TIM> {}
+pub trait PinC3N<TIM> {}
+pub trait PinBkin<TIM> {}
+
 macro_rules! channel_impl {
     ( $( $TIM:ident, $PINC:ident, $PINX:ident, $MODE:ident<$AF:ident>; )+ ) => {
         $(
@@ -743,3 +770,179 @@ channel_impl!(
 
     TIM2, PinC4, PB2, Alternate<AF1>;
 );
+
+#[cfg(any(
+    feature = "stm32f401",
+    feature = "stm32f405",
+    feature = "stm32f407",
+    feature = "stm32f410",
+    feature = "stm32f411",
+    feature = "stm32f412",
+    feature = "stm32f413",
+    feature = "stm32f415",
+    feature = "stm32f417",
+    feature = "stm32f423",
+    feature = "stm32f427",
+    feature = "stm32f429",
+    feature = "stm32f437",
+    feature = "stm32f439",
+    feature = "stm32f446",
+    feature = "stm32f469",
+    feature = "stm32f479"
+))]
+channel_impl!(
+    TIM9, PinC1, PA2, Alternate<AF3>;
+    TIM9, PinC2, PA3, Alternate<AF3>;
+
+    TIM11, PinC1, PB9, Alternate<AF3>;
+);
+
+#[cfg(any(
+    feature = "stm32f401",
+    feature = "stm32f405",
+    feature = "stm32f407",
+    feature = "stm32f411",
+    feature = "stm32f412",
+    feature = "stm32f413",
+    feature = "stm32f415",
+    feature = "stm32f417",
+    feature = "stm32f423",
+    feature = "stm32f427",
+    feature = "stm32f429",
+    feature = "stm32f437",
+    feature = "stm32f439",
+    feature = "stm32f446",
+    feature = "stm32f469",
+    feature = "stm32f479"
+))]
+channel_impl!(
+    TIM9, PinC1, PE5, Alternate<AF3>;
+    TIM9, PinC2, PE6, Alternate<AF3>;
+
+    TIM10, PinC1, PB8, Alternate<AF3>;
+);
+
+#[cfg(any(
+    feature = "stm32f405",
+    feature = "stm32f407",
+    feature = "stm32f412",
+    feature = "stm32f413",
+    feature = "stm32f415",
+    feature = "stm32f417",
+    feature = "stm32f423",
+    feature = "stm32f427",
+    feature = "stm32f429",
+    feature = "stm32f437",
+    feature = "stm32f439",
+    feature = "stm32f446",
+    feature = "stm32f469",
+    feature = "stm32f479"
+))]
+channel_impl!(
+    TIM12, PinC1, PB14, Alternate<AF9>;
+    TIM12, PinC2, PB15, Alternate<AF9>;
+
+    TIM13, PinC1, PA6, Alternate<AF9>;
+
+    TIM14, PinC1, PA7, Alternate<AF9>;
+);
+
+#[cfg(any(
+    feature = "stm32f401",
+    feature = "stm32f405",
+    feature = "stm32f407",
+    feature = "stm32f410",
+    feature = "stm32f411",
+    feature = "stm32f412",
+    feature = "stm32f413",
+    feature = "stm32f415",
+    feature = "stm32f417",
+    feature = "stm32f423",
+    feature = "stm32f427",
+    feature = "stm32f429",
+    feature = "stm32f437",
+    feature = "stm32f439",
+    feature = "stm32f446",
+    feature = "stm32f469",
+    feature = "stm32f479"
+))]
+channel_impl!(
+    TIM1, PinC1N, PA7, Alternate<AF1>;
+    TIM1, PinC1N, PB13, Alternate<AF1>;
+    TIM1, PinC2N, PB0, Alternate<AF1>;
+    TIM1, PinC2N, PB14, Alternate<AF1>;
+    TIM1, PinC3N, PB1, Alternate<AF1>;
+    TIM1, PinC3N, PB15, Alternate<AF1>;
+    TIM1, PinBkin, PA6, Alternate<AF1>;
+    TIM1, PinBkin, PB12, Alternate<AF1>;
+);
+
+#[cfg(any(
+    feature = "stm32f401",
+    feature = "stm32f405",
+    feature = "stm32f407",
+    feature = "stm32f411",
+    feature = "stm32f412",
+    feature = "stm32f413",
+    feature = "stm32f415",
+    feature = "stm32f417",
+    feature = "stm32f423",
+    feature = "stm32f427",
+    feature = "stm32f429",
+    feature = "stm32f437",
+    feature = "stm32f439",
+    feature = "stm32f446",
+    feature = "stm32f469",
+    feature = "stm32f479"
+))]
+channel_impl!(
+    TIM1, PinC1N, PE8, Alternate<AF1>;
+    TIM1, PinC2N, PE10, Alternate<AF1>;
+    TIM1, PinC3N, PE12, Alternate<AF1>;
+    TIM1, PinBkin, PE15, Alternate<AF1>;
+);
+
+#[cfg(any(
+    feature = "stm32f405",
+    feature = "stm32f407",
+    feature = "stm32f412",
+    feature = "stm32f413",
+    feature = "stm32f415",
+    feature = "stm32f417",
+    feature = "stm32f423",
+    feature = "stm32f427",
+    feature = "stm32f429",
+    feature = "stm32f437",
+    feature = "stm32f439",
+    feature = "stm32f446",
+    feature = "stm32f469",
+    feature = "stm32f479"
+))]
+channel_impl!(
+    TIM8, PinC1N, PA5, Alternate<AF3>;
+    TIM8, PinC1N, PA7, Alternate<AF3>;
+    TIM8, PinC2N, PB0, Alternate<AF3>;
+    TIM8, PinC2N, PB14, Alternate<AF3>;
+    TIM8, PinC3N, PB1, Alternate<AF3>;
+    TIM8, PinC3N, PB15, Alternate<AF3>;
+    TIM8, PinBkin, PA6, Alternate<AF3>;
+);
+
+#[cfg(any(
+    feature = "stm32f405",
+    feature = "stm32f407",
+    feature = "stm32f415",
+    feature = "stm32f417",
+    feature = "stm32f427",
+    feature = "stm32f429",
+    feature = "stm32f437",
+    feature = "stm32f439",
+    feature = "stm32f469",
+    feature = "stm32f479"
+))]
+channel_impl!(
+    TIM8, PinC1N, PH13, Alternate<AF3>;
+    TIM8, PinC2N, PH14, Alternate<AF3>;
+    TIM8, PinC3N, PH15, Alternate<AF3>;
+    TIM8, PinBkin, PI4, Alternate<AF3>;
+);
@@ -1,9 +1,13 @@
 //! Watchdog peripherals
 
+use core::convert::TryInto;
+
 use crate::{
+    bb,
     hal::watchdog::{Watchdog, WatchdogEnable},
-    stm32::{DBGMCU, IWDG},
-    time::MilliSeconds,
+    rcc::Clocks,
+    stm32::{DBGMCU, IWDG, RCC, WWDG},
+    time::{Hertz, MicroSeconds, MilliSeconds},
 };
 
 /// Wraps the Independent Watchdog (IWDG) peripheral
@@ -102,3 +106,174 @@ impl Watchdog for IndependentWatchdog {
         self.iwdg.kr.write(|w| unsafe { w.key().bits(KR_RELOAD) });
     }
 }
+
+/// Wraps the Window Watchdog (WWDG) peripheral
+///
+/// The WWDG resets the device when its counter runs out or when it is fed before the
+/// configured window opens. The counter runs from the APB1 clock, so the timeout is at most a
+/// few tens of milliseconds.
+pub struct WindowWatchdog {
+    wwdg: WWDG,
+    pclk1: Hertz,
+    window: Option<MicroSeconds>,
+    reload: u8,
+}
+
+/// Window watchdog error
+#[derive(Debug, PartialEq)]
+pub enum Error {
+    /// The window for feeding is not open yet, feeding now would reset the device
+    TooEarly,
+    /// The timeout is shorter than one counter step or longer than the longest possible
+    /// timeout, or the window doesn't fit into `MicroSeconds`
+    InvalidTime,
+}
+
+const MAX_WDGTB: u8 = 0b11;
+/// Counter steps from the reload value to the reset, which happens when T6 gets cleared
+const MAX_STEPS: u32 = 64;
+const T_RESET: u8 = 0x3F;
+const CR_WDGA: u32 = 1 << 7;
+const CFR_EWI: u32 = 1 << 9;
+const SR_EWIF: u32 = 1 << 0;
+
+impl WindowWatchdog {
+    /// Wrap the watchdog, it is started by `start`
+    pub fn new(wwdg: WWDG, clocks: &Clocks) -> Self {
+        unsafe {
+            const EN_BIT: u8 = 11;
+            // NOTE(unsafe) this reference will only be used for atomic writes with no side effects.
+            let rcc = &(*RCC::ptr());
+
+            // Enable clock.
+            bb::set(&rcc.apb1enr, EN_BIT);
+        }
+
+        WindowWatchdog {
+            wwdg,
+            pclk1: clocks.pclk1(),
+            window: None,
+            reload: 0x7F,
+        }
+    }
+
+    /// Debug window watchdog stopped when core is halted
+    pub fn stop_on_debug(&self, dbgmcu: &DBGMCU, stop: bool) {
+        dbgmcu.apb1_fz.modify(|_, w| w.dbg_wwdg_stop().bit(stop));
+    }
+
+    /// Only allow feeding during the last `window` before the timeout, applied by the next
+    /// `start`. Without a window the watchdog can be fed at any time.
+    pub fn set_window<T: TryInto<MicroSeconds>>(&mut self, window: T) -> Result<(), Error> {
+        self.window = Some(window.try_into().map_err(|_| Error::InvalidTime)?);
+        Ok(())
+    }
+
+    /// Counter steps for `time` with prescaler `wdgtb`
+    fn steps(&self, wdgtb: u8, time: MicroSeconds) -> u32 {
+        let step_clk = u64::from(self.pclk1.0) / (4096 << wdgtb);
+        (u64::from(time.0) * step_clk / 1_000_000) as u32
+    }
+
+    /// Prescaler and counter steps for `timeout`, the steps are out of range if the timeout is
+    /// too short or too long
+    fn timing(&self, timeout: MicroSeconds) -> (u8, u32) {
+        let mut wdgtb = 0;
+        while wdgtb < MAX_WDGTB && self.steps(wdgtb, timeout) > MAX_STEPS {
+            wdgtb += 1;
+        }
+        (wdgtb, self.steps(wdgtb, timeout))
+    }
+
+    fn setup(&mut self, wdgtb: u8, steps: u32) {
+        let window = match self.window {
+            Some(window) => self.steps(wdgtb, window).max(1).min(steps),
+            None => MAX_STEPS,
+        };
+
+        self.reload = T_RESET + steps as u8;
+        let w = u32::from(T_RESET) + window;
+        self.wwdg
+            .cfr
+            .modify(|r, wr| unsafe { wr.bits((r.bits() & CFR_EWI) | u32::from(wdgtb) << 7 | w) });
+    }
+
+    /// Returns the interval from feeding to the reset
+    pub fn interval(&self) -> MicroSeconds {
+        let wdgtb = (self.wwdg.cfr.read().bits() >> 7) & 0b11;
+        let steps = u64::from(self.reload - T_RESET);
+        let us = steps * (4096 << wdgtb) * 1_000_000 / u64::from(self.pclk1.0);
+        MicroSeconds(us as u32)
+    }
+
+    /// Starts the watchdog with `timeout`, or returns `Error::InvalidTime` if it's shorter than
+    /// one counter step or longer than the longest possible timeout
+    pub fn try_start<T: TryInto<MicroSeconds>>(&mut self, timeout: T) -> Result<(), Error> {
+        let timeout = timeout.try_into().map_err(|_| Error::InvalidTime)?;
+        let (wdgtb, steps) = self.timing(timeout);
+        if !(1..=MAX_STEPS).contains(&steps) {
+            return Err(Error::InvalidTime);
+        }
+
+        self.setup(wdgtb, steps);
+        self.enable();
+        Ok(())
+    }
+
+    fn enable(&mut self) {
+        self.wwdg
+            .cr
+            .write(|w| unsafe { w.bits(CR_WDGA | u32::from(self.reload)) });
+    }
+
+    /// Feeds the watchdog if the window is open
+    pub fn try_feed(&mut self) -> Result<(), Error> {
+        let counter = self.wwdg.cr.read().bits() & 0x7F;
+        let window = self.wwdg.cfr.read().bits() & 0x7F;
+        if counter > window {
+            return Err(Error::TooEarly);
+        }
+
+        self.enable();
+        Ok(())
+    }
+
+    /// Enables the early wakeup interrupt, which fires one counter step before the reset.
+    /// It stays enabled until the next reset.
+    pub fn listen(&mut self) {
+        self.wwdg
+            .cfr
+            .modify(|r, w| unsafe { w.bits(r.bits() | CFR_EWI) });
+    }
+
+    /// Returns `true` if the early wakeup interrupt flag is set
+    pub fn is_early_wakeup(&self) -> bool {
+        self.wwdg.sr.read().bits() & SR_EWIF != 0
+    }
+
+    /// Clears the early wakeup interrupt flag
+    pub fn clear_early_wakeup(&mut self) {
+        self.wwdg.sr.write(|w| unsafe { w.bits(0) });
+    }
+}
+
+/// `start` can't report an error, so timeouts out of range are clamped to one counter step
+/// or to the longest possible timeout, 64 steps of 32768 APB1 clock cycles. Use `try_start`,
+/// which also takes `MilliSeconds`, to get an error instead.
+impl WatchdogEnable for WindowWatchdog {
+    type Time = MicroSeconds;
+
+    fn start<T: Into<Self::Time>>(&mut self, period: T) {
+        let (wdgtb, steps) = self.timing(period.into());
+        self.setup(wdgtb, steps.max(1).min(MAX_STEPS));
+        self.enable();
+    }
+}
+
+/// `feed` always feeds the watchdog, so like the hardware it resets the device if the window
+/// isn't open yet. Use `try_feed` to handle early feeds.
+impl Watchdog for WindowWatchdog {
+    fn feed(&mut self) {
+        self.enable();
+    }
+}